@@ -1,10 +1,14 @@
+mod test_render;
+
 use clap::Parser as ClapParser;
+use clap::{Subcommand, ValueEnum};
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use codespan_reporting::files::SimpleFiles;
 use codespan_reporting::term;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use colored::Colorize;
 use gbasic_common::error::GBasicError;
+use gbasic_irgen::CompileTarget;
 use std::fs;
 use std::process;
 
@@ -12,7 +16,11 @@ use std::process;
 #[command(name = "gbasic")]
 #[command(version)]
 #[command(about = "The G-Basic programming language compiler", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// Source file to compile (.gb)
     file: Option<String>,
 
@@ -36,13 +44,63 @@ struct Cli {
     #[arg(long)]
     skip_typecheck: bool,
 
-    /// Output binary path
+    /// Output binary path (a directory for `--target web`)
     #[arg(short, long, default_value = "output")]
     output: String,
 
+    /// Platform to compile for
+    #[arg(long, value_enum, default_value_t = TargetArg::Native)]
+    target: TargetArg,
+
     /// Run the compiled binary after successful compilation
     #[arg(long)]
     run: bool,
+
+    /// With --run: stop after N frames
+    #[arg(long, value_name = "N")]
+    frames: Option<u64>,
+
+    /// With --run: save every Nth frame as frame_NNNNN.png
+    #[arg(long, value_name = "N")]
+    screenshot_every: Option<u64>,
+
+    /// With --run: log keyboard/mouse input and the random seed to FILE
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    record: Option<String>,
+
+    /// With --run: play back a log written by --record instead of live input
+    #[arg(long, value_name = "FILE")]
+    replay: Option<String>,
+
+    /// With --run: random seed to use instead of one from the clock
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Run a program headlessly and compare frames against golden PNGs
+    TestRender(test_render::TestRenderArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TargetArg {
+    /// Native executable (SDL2 window)
+    Native,
+    /// Native executable with no window or audio; renders offscreen
+    Headless,
+    /// Browser bundle: serve the output directory and open index.html
+    Web,
+}
+
+impl From<TargetArg> for CompileTarget {
+    fn from(t: TargetArg) -> Self {
+        match t {
+            TargetArg::Native => CompileTarget::Native,
+            TargetArg::Headless => CompileTarget::Headless,
+            TargetArg::Web => CompileTarget::Web,
+        }
+    }
 }
 
 fn print_error(filename: &str, source: &str, err: &GBasicError) {
@@ -85,20 +143,48 @@ fn print_error(filename: &str, source: &str, err: &GBasicError) {
     let _ = term::emit(&mut writer.lock(), &config, &files, &diagnostic);
 }
 
+fn read_source(file: &str) -> String {
+    match fs::read_to_string(file) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}: {}: {}", "error".red().bold(), file, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_or_exit(file: &str, source: &str) -> gbasic_common::ast::Program {
+    match gbasic_parser::parse(source) {
+        Ok(p) => p,
+        Err(errors) => {
+            for err in &errors {
+                print_error(file, source, err);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+fn check_or_exit(file: &str, source: &str, program: &gbasic_common::ast::Program) {
+    if let Err(err) = gbasic_typechecker::check(program) {
+        print_error(file, source, &err);
+        process::exit(1);
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
 
+    if let Some(Commands::TestRender(args)) = cli.command {
+        test_render::run(args);
+        return;
+    }
+
     let Some(file) = cli.file else {
         return;
     };
 
-    let source = match fs::read_to_string(&file) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("{}: {}: {}", "error".red().bold(), file, e);
-            process::exit(1);
-        }
-    };
+    let source = read_source(&file);
 
     if cli.dump_tokens {
         let tokens = gbasic_lexer::tokenize(&source);
@@ -108,15 +194,7 @@ fn main() {
         return;
     }
 
-    let program = match gbasic_parser::parse(&source) {
-        Ok(p) => p,
-        Err(errors) => {
-            for err in &errors {
-                print_error(&file, &source, err);
-            }
-            process::exit(1);
-        }
-    };
+    let program = parse_or_exit(&file, &source);
 
     if cli.dump_ast {
         println!("{:#?}", program);
@@ -125,10 +203,7 @@ fn main() {
 
     // Type checking
     if !cli.skip_typecheck {
-        if let Err(err) = gbasic_typechecker::check(&program) {
-            print_error(&file, &source, &err);
-            process::exit(1);
-        }
+        check_or_exit(&file, &source, &program);
     }
 
     if cli.check {
@@ -142,7 +217,7 @@ fn main() {
     }
 
     // Code generation
-    if let Err(err) = gbasic_irgen::codegen(&program, &cli.output, cli.dump_ir) {
+    if let Err(err) = gbasic_irgen::codegen(&program, &source, &cli.output, cli.dump_ir, cli.target.into()) {
         print_error(&file, &source, &err);
         process::exit(1);
     }
@@ -156,9 +231,34 @@ fn main() {
         );
     }
 
+    if cli.run && matches!(cli.target, TargetArg::Web) {
+        eprintln!(
+            "{}: --run is not supported for --target web; serve {} with any static file server",
+            "error".red().bold(),
+            cli.output
+        );
+        process::exit(1);
+    }
+
     // Run the binary if --run was specified
     if cli.run && !cli.dump_ir {
-        let status = std::process::Command::new(&cli.output)
+        let mut cmd = std::process::Command::new(&cli.output);
+        if let Some(n) = cli.frames {
+            cmd.env("GBASIC_FRAMES", n.to_string());
+        }
+        if let Some(n) = cli.screenshot_every {
+            cmd.env("GBASIC_SCREENSHOT_EVERY", n.to_string());
+        }
+        if let Some(path) = &cli.record {
+            cmd.env("GBASIC_RECORD", path);
+        }
+        if let Some(path) = &cli.replay {
+            cmd.env("GBASIC_REPLAY", path);
+        }
+        if let Some(seed) = cli.seed {
+            cmd.env("GBASIC_SEED", seed.to_string());
+        }
+        let status = cmd
             .status()
             .unwrap_or_else(|e| {
                 eprintln!("{}: failed to run {}: {}", "error".red().bold(), cli.output, e);
@@ -0,0 +1,183 @@
+//! `gbasic test-render`: run a program headlessly and compare chosen frames
+//! against golden PNGs.
+
+use crate::{check_or_exit, parse_or_exit, print_error, read_source};
+use clap::Args;
+use colored::Colorize;
+use gbasic_irgen::CompileTarget;
+use gbasic_runtime_core::Framebuffer;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+#[derive(Args)]
+pub struct TestRenderArgs {
+    /// Source file to run (.gb)
+    file: String,
+
+    /// Number of frames to run
+    #[arg(long, default_value_t = 120)]
+    frames: u64,
+
+    /// Directory holding the golden images (frame_NNNNN.png)
+    #[arg(long, value_name = "DIR")]
+    expect: PathBuf,
+
+    /// Frames to compare, e.g. 30,60,120 [default: the last frame]
+    #[arg(long, value_delimiter = ',', value_name = "N,..")]
+    capture: Vec<u64>,
+
+    /// Input script driving the keyboard and mouse
+    #[arg(long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Random seed [default: fixed, since the headless clock starts at zero]
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// How different a pixel may look before it counts, from 0.0 (exact) to 1.0
+    #[arg(long, default_value_t = 0.1)]
+    tolerance: f64,
+
+    /// Fraction of pixels allowed to differ
+    #[arg(long, default_value_t = 0.0)]
+    max_diff: f64,
+
+    /// Save the captured frames as the new golden images
+    #[arg(long)]
+    update: bool,
+}
+
+pub fn run(args: TestRenderArgs) {
+    let source = read_source(&args.file);
+    let program = parse_or_exit(&args.file, &source);
+    check_or_exit(&args.file, &source, &program);
+
+    let work_dir = std::env::temp_dir().join(format!("gbasic_test_render_{}", process::id()));
+    let _ = std::fs::remove_dir_all(&work_dir);
+    if let Err(e) = std::fs::create_dir_all(&work_dir) {
+        let _ = std::fs::remove_dir_all(&work_dir);
+        fail(&format!("{}: {e}", work_dir.display()));
+    }
+    let binary = work_dir.join("program");
+    if let Err(err) = gbasic_irgen::codegen(
+        &program,
+        &source,
+        &binary.to_string_lossy(),
+        false,
+        CompileTarget::Headless,
+    ) {
+        let _ = std::fs::remove_dir_all(&work_dir);
+        print_error(&args.file, &source, &err);
+        process::exit(1);
+    }
+
+    let outcome = render_and_compare(&args, &binary, &work_dir);
+    let _ = std::fs::remove_dir_all(&work_dir);
+    match outcome {
+        Ok(0) => {}
+        Ok(failures) => {
+            println!(
+                "{failures} frame(s) differ; see *.actual.png and *.diff.png in {} \
+                 (rerun with --update to accept)",
+                args.expect.display()
+            );
+            process::exit(1);
+        }
+        Err(message) => fail(&message),
+    }
+}
+
+/// Run the compiled program in `work_dir` and compare its captured frames,
+/// returning how many differ.
+fn render_and_compare(args: &TestRenderArgs, binary: &Path, work_dir: &Path) -> Result<usize, String> {
+    let captures = if args.capture.is_empty() { vec![args.frames] } else { args.capture.clone() };
+    let list = captures.iter().map(u64::to_string).collect::<Vec<_>>().join(",");
+    let mut cmd = Command::new(binary);
+    cmd.env("GBASIC_FRAMES", args.frames.to_string())
+        .env("GBASIC_SCREENSHOT_FRAMES", list)
+        .env("GBASIC_SCREENSHOT_DIR", work_dir);
+    if let Some(input) = &args.input {
+        cmd.env("GBASIC_INPUT", input);
+    }
+    if let Some(seed) = args.seed {
+        cmd.env("GBASIC_SEED", seed.to_string());
+    }
+    let status = cmd.status().map_err(|e| format!("failed to run program: {e}"))?;
+    if !status.success() {
+        return Err(format!("{} exited with {status}", args.file));
+    }
+
+    let mut failures = 0;
+    for frame in captures {
+        let name = format!("frame_{frame:05}");
+        let actual_path = work_dir.join(format!("{name}.png"));
+        let Ok(actual) = Framebuffer::load_png(&actual_path) else {
+            println!("{}: frame {frame} was never reached", "FAIL".red().bold());
+            failures += 1;
+            continue;
+        };
+        let golden_path = args.expect.join(format!("{name}.png"));
+        if args.update {
+            save(&actual, &golden_path)?;
+            println!("{}: frame {frame} -> {}", "updated".green().bold(), golden_path.display());
+            continue;
+        }
+        match compare(&actual, &golden_path, args.tolerance, args.max_diff) {
+            Ok(()) => {
+                let _ = std::fs::remove_file(args.expect.join(format!("{name}.actual.png")));
+                let _ = std::fs::remove_file(args.expect.join(format!("{name}.diff.png")));
+                println!("{}: frame {frame}", "ok".green().bold());
+            }
+            Err((message, diff)) => {
+                failures += 1;
+                save(&actual, &args.expect.join(format!("{name}.actual.png")))?;
+                if let Some(diff) = diff {
+                    save(&diff, &args.expect.join(format!("{name}.diff.png")))?;
+                }
+                println!("{}: frame {frame}: {message}", "FAIL".red().bold());
+            }
+        }
+    }
+    Ok(failures)
+}
+
+/// Check `actual` against the golden image at `golden_path`, returning a
+/// message and, when the sizes match, a diff image on mismatch.
+fn compare(
+    actual: &Framebuffer,
+    golden_path: &Path,
+    tolerance: f64,
+    max_diff: f64,
+) -> Result<(), (String, Option<Framebuffer>)> {
+    let golden = Framebuffer::load_png(golden_path).map_err(|e| {
+        (format!("no golden image at {} ({e}); run with --update", golden_path.display()), None)
+    })?;
+    let Some(diff) = actual.compare(&golden, tolerance) else {
+        return Err((
+            format!(
+                "size {}x{} doesn't match golden {}x{}",
+                actual.width(),
+                actual.height(),
+                golden.width(),
+                golden.height()
+            ),
+            None,
+        ));
+    };
+    if diff.differing as f64 > max_diff * diff.total as f64 {
+        return Err((format!("{} of {} pixels differ", diff.differing, diff.total), Some(diff.image)));
+    }
+    Ok(())
+}
+
+fn save(image: &Framebuffer, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    image.save_png(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}: {message}", "error".red().bold());
+    process::exit(1);
+}
@@ -2,6 +2,7 @@
 //! These tests require the gbasic binary and LLVM to be available.
 //! Run with: cargo test --test e2e
 
+use gbasic_runtime_core::{Color, Framebuffer};
 use std::io::Write;
 use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -17,6 +18,17 @@ fn unique_dir() -> std::path::PathBuf {
 }
 
 fn compile_and_run(source: &str) -> Result<String, String> {
+    compile_and_run_target(source, "native")
+}
+
+fn compile_and_run_target(source: &str, target: &str) -> Result<String, String> {
+    let bin = compile_target(source, target)?;
+    let run = Command::new(&bin).output().expect("failed to run compiled binary");
+    Ok(String::from_utf8_lossy(&run.stdout).trim().to_string())
+}
+
+/// Compile `source` for `target` into a fresh directory, returning the binary.
+fn compile_target(source: &str, target: &str) -> Result<std::path::PathBuf, String> {
     let dir = unique_dir();
     let src_path = dir.join("test.gb");
     let out_path = dir.join("test_bin");
@@ -24,9 +36,10 @@ fn compile_and_run(source: &str) -> Result<String, String> {
     let mut f = std::fs::File::create(&src_path).unwrap();
     f.write_all(source.as_bytes()).unwrap();
 
-    // Compile
     let compile = Command::new(env!("CARGO_BIN_EXE_gbasic"))
         .arg(src_path.to_str().unwrap())
+        .arg("--target")
+        .arg(target)
         .arg("-o")
         .arg(out_path.to_str().unwrap())
         .output()
@@ -35,13 +48,30 @@ fn compile_and_run(source: &str) -> Result<String, String> {
     if !compile.status.success() {
         return Err(String::from_utf8_lossy(&compile.stderr).to_string());
     }
+    Ok(out_path)
+}
 
-    // Run
-    let run = Command::new(out_path.to_str().unwrap())
+/// Compile for the headless runtime and run for `frames` frames with
+/// `GBASIC_SCREENSHOT_EVERY=frames`, returning the last frame.
+fn render_headless(source: &str, frames: u64) -> Result<Framebuffer, String> {
+    let bin = compile_target(source, "headless")?;
+    run_headless(&bin, frames)
+}
+
+/// Run a headless binary from its own directory, returning frame `frames`.
+fn run_headless(bin: &std::path::Path, frames: u64) -> Result<Framebuffer, String> {
+    let dir = bin.parent().unwrap();
+    let run = Command::new(bin)
+        .current_dir(dir)
+        .env("GBASIC_FRAMES", frames.to_string())
+        .env("GBASIC_SCREENSHOT_EVERY", frames.to_string())
+        .env("GBASIC_SCREENSHOT_DIR", dir)
         .output()
         .expect("failed to run compiled binary");
-
-    Ok(String::from_utf8_lossy(&run.stdout).trim().to_string())
+    if !run.status.success() {
+        return Err(String::from_utf8_lossy(&run.stderr).to_string());
+    }
+    Framebuffer::load_png(dir.join(format!("frame_{frames:05}.png"))).map_err(|e| e.to_string())
 }
 
 fn compile_only(source: &str) -> Result<(), String> {
@@ -186,3 +216,816 @@ fn test_for_to_range() {
     .unwrap();
     assert_eq!(out, "1\n2\n3");
 }
+
+#[test]
+fn test_headless_draw_rect() {
+    let fb = render_headless(
+        r#"Screen.Init(64, 48)
+while true {
+    System.FrameBegin()
+    Screen.Clear(0, 0, 40)
+    Screen.DrawRect(10, 10, 20, 5, 255, 128, 0)
+    System.FrameEnd()
+}"#,
+        3,
+    )
+    .unwrap();
+    assert_eq!((fb.width(), fb.height()), (64, 48));
+    assert_eq!(fb.pixel(10, 10), Some([255, 128, 0, 255]));
+    assert_eq!(fb.pixel(29, 14), Some([255, 128, 0, 255]));
+    assert_eq!(fb.pixel(30, 10), Some([0, 0, 40, 255]));
+    assert_eq!(fb.pixel(10, 15), Some([0, 0, 40, 255]));
+}
+
+#[test]
+fn test_headless_game_objects() {
+    let fb = render_headless(
+        r#"let ball = circle(8)
+ball.position = Screen.center
+while true {
+}"#,
+        2,
+    )
+    .unwrap();
+    let (cx, cy) = (fb.width() as i64 / 2, fb.height() as i64 / 2);
+    assert_ne!(fb.pixel(cx, cy), fb.pixel(0, 0));
+    assert_eq!(fb.pixel(cx + 20, cy), fb.pixel(0, 0));
+}
+
+#[test]
+fn test_headless_screenshot() {
+    let dir = unique_dir();
+    let shot = dir.join("shot.png");
+    let source = format!(
+        r#"Screen.Init(16, 16)
+Screen.Clear(0, 255, 0)
+Screen.SetPixel(3, 4, 255, 0, 0)
+Screen.Present()
+System.Screenshot("{}")"#,
+        shot.display()
+    );
+    compile_and_run_target(&source, "headless").unwrap();
+    let fb = Framebuffer::load_png(&shot).unwrap();
+    assert_eq!(fb.pixel(3, 4), Some([255, 0, 0, 255]));
+    assert_eq!(fb.pixel(0, 0), Some([0, 255, 0, 255]));
+}
+
+fn test_render(dir: &std::path::Path, source: &str, extra: &[&str]) -> std::process::Output {
+    let src_path = dir.join("game.gb");
+    std::fs::write(&src_path, source).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_gbasic"))
+        .arg("test-render")
+        .arg(&src_path)
+        .arg("--expect")
+        .arg(dir.join("golden"))
+        .args(extra)
+        .output()
+        .expect("failed to run gbasic")
+}
+
+#[test]
+fn test_render_golden_images() {
+    let dir = unique_dir();
+    std::fs::write(dir.join("input.txt"), "1 press right\n20 release right\n").unwrap();
+    let game = |color: &str| {
+        format!(
+            r#"Screen.Init(80, 60)
+let x = 0
+while true {{
+    if key("right") {{ x = x + 2 }}
+    Screen.Clear(0, 0, 0)
+    Screen.DrawRect(x, 20, 10, 10, {color})
+}}"#
+        )
+    };
+    let input = dir.join("input.txt");
+    let args = ["--frames", "30", "--capture", "10,30", "--input", input.to_str().unwrap()];
+
+    let update = test_render(&dir, &game("255, 255, 255"), &[&args[..], &["--update"]].concat());
+    assert!(update.status.success(), "{}", String::from_utf8_lossy(&update.stderr));
+    let golden = Framebuffer::load_png(dir.join("golden/frame_00030.png")).unwrap();
+    assert_eq!(golden.pixel(38, 25), Some([255, 255, 255, 255]));
+    assert_eq!(golden.pixel(37, 25), Some([0, 0, 0, 255]));
+
+    let same = test_render(&dir, &game("255, 255, 255"), &args);
+    assert!(same.status.success(), "{}", String::from_utf8_lossy(&same.stdout));
+
+    // Slightly off-white passes the default tolerance; a different color doesn't
+    let close = test_render(&dir, &game("250, 250, 250"), &args);
+    assert!(close.status.success(), "{}", String::from_utf8_lossy(&close.stdout));
+    let changed = test_render(&dir, &game("255, 0, 0"), &args);
+    assert!(!changed.status.success());
+    assert!(String::from_utf8_lossy(&changed.stdout).contains("100 of 4800 pixels differ"));
+    assert!(dir.join("golden/frame_00030.diff.png").exists());
+    assert!(dir.join("golden/frame_00030.actual.png").exists());
+}
+
+#[test]
+fn test_replay_is_deterministic() {
+    let bin = compile_target(
+        r#"let frame = 0
+while true {
+    frame = frame + 1
+    let space = 0
+    if key("space") { space = 1 }
+    print("{frame} {space} {Math.Random()} {System.FrameTime()}")
+    if frame == 4 { System.Exit(0) }
+}"#,
+        "headless",
+    )
+    .unwrap();
+    let dir = bin.parent().unwrap();
+    let replay = |seed: u64| {
+        let log = dir.join(format!("input_{seed}.log"));
+        std::fs::write(&log, format!("seed {seed}\n2 press space\n3 release space\n")).unwrap();
+        let run = Command::new(&bin).env("GBASIC_REPLAY", &log).output().unwrap();
+        assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+        String::from_utf8_lossy(&run.stdout).to_string()
+    };
+
+    let first = replay(7);
+    assert_eq!(first, replay(7));
+    assert_ne!(first, replay(8));
+    let keys: Vec<&str> = first.lines().map(|l| l.split(' ').nth(1).unwrap()).collect();
+    assert_eq!(keys, ["0", "1", "0", "0"]);
+    // Frame time is pinned to 1/60 s while replaying (frame 1 has no previous frame)
+    assert!(first.lines().skip(1).all(|l| l.ends_with(&(1.0f64 / 60.0).to_string())), "{first}");
+}
+
+#[test]
+fn test_seeded_random_is_reproducible() {
+    let bin = compile_target(
+        r#"let items = [10, 20, 30, 40, 50]
+shuffle(items)
+let third = items[2]
+print("{random(1, 1000)} {random_float(0, 1)} {pick(items)} {pick_weighted(items, [0, 0, 1, 0, 0])} {third}")
+if chance(1.0) { print("always") }
+Math.Seed(42)
+let a = random(1, 1000000)
+Math.Seed(42)
+print(a == random(1, 1000000))"#,
+        "native",
+    )
+    .unwrap();
+    let run = |seed: &str| {
+        let out = Command::new(&bin).env("GBASIC_SEED", seed).output().unwrap();
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let first = run("7");
+    assert_eq!(first, run("7"));
+    assert_ne!(first, run("8"));
+    let lines: Vec<&str> = first.lines().collect();
+    assert_eq!(lines[1..], ["always", "1"]);
+    let picked: Vec<&str> = lines[0].split(' ').collect();
+    assert!(["10", "20", "30", "40", "50"].contains(&picked[2]));
+    // All the weight is on the third item, wherever the shuffle put it
+    assert_eq!(picked[3], picked[4]);
+}
+
+#[test]
+fn test_fps_and_fixed_timestep_physics() {
+    let run = |source: &str| -> Vec<f64> {
+        let bin = compile_target(source, "headless").unwrap();
+        let out = Command::new(&bin).env("GBASIC_FRAMES", "4").output().unwrap();
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .last()
+            .unwrap()
+            .split(' ')
+            .map(|v| v.parse().unwrap())
+            .collect()
+    };
+
+    // 30 FPS: each frame covers two 1/60 s physics steps at 60 px/s
+    let last = run(r#"System.fps = 30
+let ball = rect(4, 4)
+ball.position = Point(100, 100)
+ball.velocity = (60, 0)
+while true {
+    print("{ball.position.x} {System.frame_time} {System.fps}")
+}"#);
+    assert!((last[0] - 104.0).abs() < 1e-6, "{last:?}");
+    assert!((last[1] - 1.0 / 30.0).abs() < 1e-6, "{last:?}");
+    assert_eq!(last[2], 30.0);
+
+    // Compatibility mode: velocity is pixels per frame, one step per frame
+    let last = run(r#"System.fps = 30
+System.frame_units = true
+let ball = rect(4, 4)
+ball.position = Point(100, 100)
+ball.velocity = (2, 0)
+while true {
+    print("{ball.position.x} {System.frame_time} {System.fps}")
+}"#);
+    assert!((last[0] - 106.0).abs() < 1e-6, "{last:?}");
+}
+
+#[test]
+fn test_resting_contact() {
+    let bin = compile_target(
+        r#"let ground = rect(200, 20)
+ground.position = Point(0, 180)
+ground.solid = true
+let block = rect(20, 20)
+block.position = Point(50, 100)
+block.gravity = 1000
+while true {
+    print("{block.position.y} {block.on_ground} {block.collides(ground)}")
+}"#,
+        "headless",
+    )
+    .unwrap();
+    let out = Command::new(&bin).env("GBASIC_FRAMES", "60").output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let last: Vec<&str> = stdout.lines().last().unwrap().split(' ').collect();
+    assert!((last[0].parse::<f64>().unwrap() - 160.0).abs() < 1e-6, "{last:?}");
+    assert_eq!(last[1..], ["1", "1"]);
+}
+
+#[test]
+fn test_exact_circle_collision() {
+    let out = compile_and_run_target(
+        r#"let paddle = rect(40, 40)
+paddle.position = Point(100, 100)
+let ball = circle(10)
+ball.position = Point(92, 92)
+print(ball.collides(paddle))
+ball.collision_shape = "box"
+print(ball.collides(paddle))"#,
+        "headless",
+    )
+    .unwrap();
+    assert_eq!(out, "0\n1");
+}
+
+#[test]
+fn test_removed_object_is_a_runtime_error() {
+    let bin = compile_target(
+        r#"let a = rect(10, 10)
+let b = rect(10, 10)
+b.remove()
+print(b.alive)
+print(b.alive and b.collides(a))
+print(a.alive or b.collides(a))
+print(b.position.x)"#,
+        "headless",
+    )
+    .unwrap();
+    let run = Command::new(&bin).output().expect("failed to run compiled binary");
+    assert!(!run.status.success());
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert_eq!(stdout.trim(), "0\n0\n1");
+    assert!(stderr.contains("runtime error at line 7, column 7: this object was removed"), "{stderr}");
+}
+
+#[test]
+fn test_sprite_objects() {
+    let bin = compile_target(
+        r#"let a = sprite("arrow")
+a.scale = 10
+let b = sprite("arrow.png")
+b.scale = 10
+b.position = Point(40, 0)
+b.flip_x = true
+let c = sprite("arrow")
+c.scale = 10
+c.position = Point(100, 100)
+c.rotation = 90
+while true {
+}"#,
+        "headless",
+    )
+    .unwrap();
+    // Red on the left, blue on the right
+    let mut arrow = Framebuffer::new(2, 1);
+    arrow.fill_rect(0, 0, 1, 1, Color::rgb(255, 0, 0));
+    arrow.fill_rect(1, 0, 1, 1, Color::rgb(0, 0, 255));
+    arrow.save_png(bin.parent().unwrap().join("arrow.png")).unwrap();
+
+    let fb = run_headless(&bin, 1).unwrap();
+    let (red, blue) = (Some([255, 0, 0, 255]), Some([0, 0, 255, 255]));
+    assert_eq!((fb.pixel(5, 5), fb.pixel(15, 5)), (red, blue));
+    assert_eq!((fb.pixel(45, 5), fb.pixel(55, 5)), (blue, red));
+    // Turned a quarter clockwise about its center (110, 105)
+    assert_eq!((fb.pixel(110, 97), fb.pixel(110, 112)), (red, blue));
+    assert_eq!(fb.pixel(100, 105), fb.pixel(0, 50));
+}
+
+#[test]
+fn test_sprite_sheet_animation() {
+    let bin = compile_target(
+        r#"let s = sprite_sheet("strip.png", 1, 1)
+s.scale = 10
+s.animations.add("once", [0, 1, 2], fps: 60, loop: false)
+s.play("once")
+let t = sprite_sheet("strip", 1, 1)
+t.scale = 10
+t.position = Point(20, 0)
+t.frame = 2
+while true {
+    if s.animation_finished {
+        t.frame = 0
+    }
+}"#,
+        "headless",
+    )
+    .unwrap();
+    // Frames 0, 1, 2: red, green, blue
+    let mut strip = Framebuffer::new(3, 1);
+    strip.fill_rect(0, 0, 1, 1, Color::rgb(255, 0, 0));
+    strip.fill_rect(1, 0, 1, 1, Color::rgb(0, 255, 0));
+    strip.fill_rect(2, 0, 1, 1, Color::rgb(0, 0, 255));
+    strip.save_png(bin.parent().unwrap().join("strip.png")).unwrap();
+
+    let (red, green, blue) = (Some([255, 0, 0, 255]), Some([0, 255, 0, 255]), Some([0, 0, 255, 255]));
+    // One frame per tick at 60 FPS, starting from the second frame
+    let fb = run_headless(&bin, 2).unwrap();
+    assert_eq!((fb.pixel(5, 5), fb.pixel(25, 5)), (green, blue));
+    // The one-shot stops on its last frame and reports finishing once
+    let fb = run_headless(&bin, 6).unwrap();
+    assert_eq!((fb.pixel(5, 5), fb.pixel(25, 5)), (blue, red));
+}
+
+#[test]
+fn test_tilemap() {
+    let bin = compile_target(
+        r#"let map = tilemap("level.csv", "tiles.png", 10, 10)
+print(map.columns)
+print(map.rows)
+print(map.tile_at(15, 35))
+print(map.tile_at(15, 5))
+map.set_solid(1, false)
+map.set_tile(35, 35, -1)
+let a = rect(6, 6)
+a.position = Point(2, 0)
+a.color = green
+a.gravity = 2000
+let b = rect(6, 6)
+b.position = Point(12, 0)
+b.color = green
+b.gravity = 2000
+while true {
+    clear(black)
+}"#,
+        "headless",
+    )
+    .unwrap();
+    let dir = bin.parent().unwrap();
+    std::fs::write(dir.join("level.csv"), "-1,-1,-1,-1\n,,,\n\n-1,-1\n0,1,0,0\n").unwrap();
+    // Tile 0 red, tile 1 blue
+    let mut tiles = Framebuffer::new(20, 10);
+    tiles.fill_rect(0, 0, 10, 10, Color::rgb(255, 0, 0));
+    tiles.fill_rect(10, 0, 10, 10, Color::rgb(0, 0, 255));
+    tiles.save_png(dir.join("tiles.png")).unwrap();
+
+    let run = Command::new(&bin).env("GBASIC_FRAMES", "1").current_dir(dir).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&run.stdout).trim(), "4\n4\n1\n-1");
+
+    let fb = run_headless(&bin, 60).unwrap();
+    let (red, green, blue) = (Some([255, 0, 0, 255]), Some([0, 255, 0, 255]), Some([0, 0, 255, 255]));
+    assert_eq!((fb.pixel(5, 35), fb.pixel(15, 35), fb.pixel(25, 35)), (red, blue, red));
+    let black = Some([0, 0, 0, 255]);
+    assert_eq!(fb.pixel(35, 35), black);
+    // `a` rests on the solid tile; `b` fell through the passable one
+    assert_eq!((fb.pixel(5, 27), fb.pixel(15, 27)), (green, black));
+}
+
+#[test]
+fn test_camera() {
+    let out = compile_and_run_target(
+        r#"Screen.camera.x = 500
+Screen.camera.zoom = 2
+print(Screen.camera.world_x(0))
+print(Screen.camera.screen_y(300))"#,
+        "headless",
+    )
+    .unwrap();
+    let values: Vec<f64> = out.lines().map(|l| l.parse().unwrap()).collect();
+    assert_eq!(values, [300.0, 300.0]);
+
+    let fb = render_headless(
+        r#"let far = rect(10, 10)
+far.position = Point(1000, 300)
+far.color = red
+let hud = rect(10, 10)
+hud.color = blue
+hud.ui = true
+Screen.camera.follow(far)
+while true {
+    clear(black)
+}"#,
+        1,
+    )
+    .unwrap();
+    // The followed object is centered; the UI stays in the corner
+    assert_eq!(fb.pixel(400, 300), Some([255, 0, 0, 255]));
+    assert_eq!(fb.pixel(5, 5), Some([0, 0, 255, 255]));
+}
+
+#[test]
+fn test_text() {
+    let out = compile_and_run_target(
+        r#"let label = text("Hi")
+label.size = 40
+label.align = "center"
+label.position = Point(400, 10)
+print(label.size.width == measure_text("Hi", 40))
+print(label.position.x)
+label.content = "Hello"
+print(label.position.x)
+print(label.align)"#,
+        "headless",
+    )
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "1");
+    assert_eq!(lines[1].parse::<f64>().unwrap(), 400.0);
+    assert_eq!(lines[2].parse::<f64>().unwrap(), 400.0);
+    assert_eq!(lines[3], "center");
+
+    let fb = render_headless(
+        r#"let title = text("HELLO")
+title.size = 60
+title.color = yellow
+title.align = "right"
+title.position = Point(800, 0)
+while true {
+    clear(black)
+}"#,
+        1,
+    )
+    .unwrap();
+    // Right-aligned against the screen edge: ink on the right, none on the left
+    let ink = |x1: i64, x2: i64| (x1..x2).flat_map(|x| (0..80).map(move |y| (x, y))).filter(|&(x, y)| fb.pixel(x, y) == Some([255, 255, 0, 255])).count();
+    assert!(ink(600, 800) > 200);
+    assert_eq!(ink(0, 500), 0);
+}
+
+#[test]
+fn test_vector_shapes() {
+    let out = compile_and_run_target(
+        r#"let ship = polygon([Point(100, 100), Point(130, 110), Point(100, 120), Point(106, 110)])
+print(ship.position.x)
+print(ship.size.width)
+let wall = line(Point(125, 90), Point(125, 130))
+print(ship.collides(wall))
+wall.position.x = 140
+print(ship.collides(wall))
+let pie = arc(20, 30, 330)
+pie.end_angle = 300
+print(pie.end_angle)
+print(pie.fill)"#,
+        "headless",
+    )
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0].parse::<f64>().unwrap(), 100.0);
+    assert_eq!(lines[1].parse::<f64>().unwrap(), 30.0);
+    assert_eq!(lines[2], "1");
+    assert_eq!(lines[3], "0");
+    assert_eq!(lines[4].parse::<f64>().unwrap(), 300.0);
+    assert_eq!(lines[5], "1");
+
+    let fb = render_headless(
+        r#"let box = rect(100, 100)
+box.position = Point(50, 50)
+box.color = red
+box.fill = false
+box.thickness = 4
+let roof = triangle(Point(300, 100), Point(350, 50), Point(400, 100))
+roof.color = green
+while true {
+    clear(black)
+}"#,
+        1,
+    )
+    .unwrap();
+    // An outlined rect is hollow; the triangle is filled
+    assert_eq!(fb.pixel(51, 100), Some([255, 0, 0, 255]));
+    assert_eq!(fb.pixel(100, 100), Some([0, 0, 0, 255]));
+    assert_eq!(fb.pixel(350, 90), Some([0, 255, 0, 255]));
+    assert_eq!(fb.pixel(310, 60), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn test_alpha_blending_and_outlines() {
+    let out = compile_and_run_target(
+        r#"Screen.blend = "add"
+print(Screen.blend)
+Screen.thickness = 2.5
+print(Screen.thickness)
+print(Screen.antialias)"#,
+        "headless",
+    )
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "add");
+    assert_eq!(lines[1].parse::<f64>().unwrap(), 2.5);
+    assert_eq!(lines[2], "0");
+
+    let fb = render_headless(
+        r#"let glass = rect(10, 10)
+glass.position = Point(200, 200)
+glass.color = Color(255, 255, 255, 128)
+let ghost = rect(10, 10)
+ghost.position = Point(220, 200)
+ghost.color = red
+ghost.opacity = 0.5
+let green_glass = Color(0, 255, 0, 128)
+Screen.thickness = 3
+while true {
+    clear(Color(100, 100, 100))
+    Screen.DrawRect(0, 0, 10, 10, Color(255, 0, 0, 128))
+    Screen.DrawRect(10, 0, 10, 10, 255, 0, 0, 128)
+    Screen.blend = "add"
+    Screen.DrawRect(20, 0, 10, 10, 100, 50, 0)
+    Screen.blend = "normal"
+    Screen.DrawRect(30, 0, 10, 10, green_glass)
+    Screen.DrawRectOutline(40, 0, 20, 20, white)
+    Screen.DrawLine(0, 50, 100, 50, blue)
+    Screen.DrawGradient(0, 100, 5, 1, black, Color(200, 0, 100), "right")
+}"#,
+        1,
+    )
+    .unwrap();
+    // Half see-through red over gray, whichever way the color is written
+    assert_eq!(fb.pixel(5, 5), Some([178, 50, 50, 255]));
+    assert_eq!(fb.pixel(15, 5), Some([178, 50, 50, 255]));
+    assert_eq!(fb.pixel(25, 5), Some([200, 150, 100, 255]));
+    assert_eq!(fb.pixel(35, 5), Some([50, 178, 50, 255]));
+    // Outline and line are three pixels wide
+    assert_eq!(fb.pixel(42, 10), Some([255, 255, 255, 255]));
+    assert_eq!(fb.pixel(43, 10), Some([100, 100, 100, 255]));
+    assert_eq!(fb.pixel(50, 49), Some([0, 0, 255, 255]));
+    assert_eq!(fb.pixel(50, 52), Some([100, 100, 100, 255]));
+    assert_eq!(fb.pixel(2, 100), Some([100, 0, 50, 255]));
+    // Objects blend by their color's alpha and their opacity
+    assert_eq!(fb.pixel(205, 205), Some([178, 178, 178, 255]));
+    assert_eq!(fb.pixel(225, 205), Some([178, 50, 50, 255]));
+}
+
+#[test]
+fn test_layers_scroll_fade_hide_and_keep_paint() {
+    let out = compile_and_run_target(
+        r#"Screen.Layer(2).opacity = 0.5
+print(Screen.Layer(2).opacity)
+print(Screen.Layer(3).opacity)
+Screen.Layer(4).visible = false
+print(Screen.Layer(4).visible)"#,
+        "headless",
+    )
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0].parse::<f64>().unwrap(), 0.5);
+    assert_eq!(lines[1].parse::<f64>().unwrap(), 1.0);
+    assert_eq!(lines[2], "0");
+
+    let fb = render_headless(
+        r#"let far = rect(20, 20)
+far.position = Point(400, 300)
+far.color = white
+far.layer = 1
+Screen.Layer(1).scroll = 0.5
+let ghost = rect(10, 10)
+ghost.position = Point(100, 100)
+ghost.color = red
+ghost.layer = 2
+Screen.Layer(2).opacity = 0.5
+let hidden = rect(10, 10)
+hidden.position = Point(150, 150)
+hidden.color = white
+hidden.layer = 4
+Screen.Layer(4).visible = false
+let faded = rect(10, 10)
+faded.position = Point(300, 400)
+faded.color = red
+faded.layer = 5
+Screen.Layer(5).grayscale = 1
+Screen.Layer(3).DrawRect(0, 0, 10, 10, blue)
+Screen.camera.x = 500
+while true {
+    clear(Color(100, 100, 100))
+}"#,
+        1,
+    )
+    .unwrap();
+    let gray = Some([100, 100, 100, 255]);
+    // The camera moved 100 right; layer 1 follows half as far
+    assert_eq!(fb.pixel(365, 305), Some([255, 255, 255, 255]));
+    assert_eq!(fb.pixel(305, 305), gray);
+    assert_eq!(fb.pixel(5, 105), Some([178, 50, 50, 255]));
+    assert_eq!(fb.pixel(55, 155), gray);
+    assert_eq!(fb.pixel(205, 405), Some([54, 54, 54, 255]));
+    // Painted once before the loop, kept under each frame's clear, and
+    // scrolled with the world, wrapping round the screen
+    assert_eq!(fb.pixel(705, 5), Some([0, 0, 255, 255]));
+    assert_eq!(fb.pixel(5, 5), gray);
+}
+
+#[test]
+fn test_emitter_bursts_and_moves_its_particles() {
+    let out = compile_and_run_target(
+        r#"let sparks = emitter()
+sparks.rate = 0
+sparks.burst(12)
+print(sparks.count)
+print(sparks.particle_lifetime)"#,
+        "headless",
+    )
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "12");
+    assert_eq!(lines[1].parse::<f64>().unwrap(), 1.0);
+
+    let fb = render_headless(
+        r#"let sparks = emitter()
+sparks.position = Point(200, 300)
+sparks.rate = 0
+sparks.speed = 600
+sparks.angle = 0
+sparks.spread = 0
+sparks.start_size = 10
+sparks.end_size = 10
+sparks.color = red
+sparks.end_color = red
+sparks.burst(3)
+while true {
+    clear(Color(100, 100, 100))
+}"#,
+        2,
+    )
+    .unwrap();
+    // The first frame takes no time; after the second they've gone 600 px/s
+    // to the right for 1/60 s
+    assert_eq!(fb.pixel(210, 300), Some([255, 0, 0, 255]));
+    assert_eq!(fb.pixel(200, 300), Some([100, 100, 100, 255]));
+}
+
+#[test]
+fn test_tween_eases_to_its_value_and_calls_back() {
+    let fb = render_headless(
+        r#"fun arrived(obj: Int) {
+    obj.color = green
+}
+let box = rect(10, 10)
+box.position = Point(100, 100)
+box.color = red
+box.tween("position.x", 300, 0.05, ease: "out_bounce", on_done: arrived)
+let bar = rect(10, 10)
+bar.position = Point(100, 200)
+let wait = bar.tween("opacity", 0, 1)
+bar.tween("position.y", 400, 0.05, after: wait)
+while true {
+    clear(black)
+}"#,
+        4,
+    )
+    .unwrap();
+    // Three 1/60 s frames after the first make the 0.05 s
+    assert_eq!(fb.pixel(305, 105), Some([0, 255, 0, 255]));
+    assert_eq!(fb.pixel(105, 105), Some([0, 0, 0, 255]));
+    // Waiting on a tween still going
+    assert_ne!(fb.pixel(105, 205), Some([0, 0, 0, 255]));
+    assert_eq!(fb.pixel(105, 405), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn test_tagged_objects_and_children_following_a_parent() {
+    let fb = render_headless(
+        r#"let ship = rect(20, 20)
+ship.position = Point(100, 100)
+ship.velocity = Point(600, 0)
+let gun = rect(10, 10)
+gun.position = Point(105, 80)
+gun.color = red
+gun.parent = ship
+for i in 0..3 {
+    let junk = rect(10, 10)
+    junk.position = Point(100 + i * 50, 300)
+    junk.tag = "junk"
+}
+while true {
+    let near = Screen.nearest("junk", 210, 305)
+    if Screen.count("junk") == 3 {
+        near.color = green
+    }
+    clear(black)
+}"#,
+        3,
+    )
+    .unwrap();
+    // Two 10 px physics steps after the first frame, and the gun kept up
+    assert_eq!(fb.pixel(130, 85), Some([255, 0, 0, 255]));
+    assert_eq!(fb.pixel(110, 85), Some([0, 0, 0, 255]));
+    assert_eq!(fb.pixel(205, 305), Some([0, 255, 0, 255]));
+    assert_eq!(fb.pixel(105, 305), Some([255, 255, 255, 255]));
+}
+
+#[test]
+fn test_wrapping_and_lifetime_with_on_removed() {
+    let fb = render_headless(
+        r#"fun mark(obj: Int) {
+    let m = rect(10, 10)
+    m.position = Point(obj.position.x + 100, obj.position.y)
+    m.color = green
+}
+let rock = rect(20, 20)
+rock.position = Point(790, 100)
+rock.velocity = Point(600, 0)
+rock.color = red
+rock.wraps = true
+let shot = rect(10, 10)
+shot.position = Point(300, 300)
+shot.color = yellow
+shot.lifetime = 0.02
+shot.on_removed = mark
+while true {
+    clear(black)
+}"#,
+        3,
+    )
+    .unwrap();
+    // Two 10 px steps take the rock past the right edge, back in on the left
+    assert_eq!(fb.pixel(5, 105), Some([255, 0, 0, 255]));
+    // The shot lived a step and a bit, and left its mark
+    assert_eq!(fb.pixel(305, 305), Some([0, 0, 0, 255]));
+    assert_eq!(fb.pixel(405, 305), Some([0, 255, 0, 255]));
+}
+
+#[test]
+fn test_raycast_line_of_sight_and_objects_near() {
+    let fb = render_headless(
+        r#"let wall = rect(20, 100)
+wall.position = Point(200, 0)
+wall.solid = true
+let target = rect(20, 20)
+target.position = Point(300, 40)
+let eye = rect(10, 10)
+eye.position = Point(50, 45)
+let hit = Screen.raycast(Point(55, 50), Point(1, 0), 500)
+hit.color = red
+let marker = rect(10, 10)
+marker.position = Point(Screen.hit.x, Screen.hit.y + 100)
+marker.color = yellow
+if not eye.can_see(target) {
+    target.color = green
+}
+for o in Screen.objects_near(Point(55, 50), 3) {
+    o.color = blue
+}
+while true {
+    clear(black)
+}"#,
+        1,
+    )
+    .unwrap();
+    // The ray starts inside the eye, so the wall is the first thing it meets
+    assert_eq!(fb.pixel(205, 50), Some([255, 0, 0, 255]));
+    assert_eq!(fb.pixel(205, 155), Some([255, 255, 0, 255]));
+    assert_eq!(fb.pixel(305, 45), Some([0, 255, 0, 255]));
+    assert_eq!(fb.pixel(52, 47), Some([0, 0, 255, 255]));
+}
+
+#[test]
+fn test_grid_flood_fill_and_path() {
+    let out = compile_and_run(
+        r#"let g = Grid(5, 3)
+g.set(2, 0, 1)
+g.set(1, 1, 1)
+g.set(2, 1, 1)
+g.set(4, 1, 1)
+let way = Math.Path(g, Point(0, 0), Point(4, 2))
+print(way.length)
+print(way[2])
+print(way[3])
+print(Math.Path(g, Point(0, 0), Point(2, 0)).length)
+print(g.neighbors(0, 0).length)
+print(g.flood_fill(3, 0, 7))
+print(g.get(4, 0))
+print(g.get(9, 9))
+print("{g.width}x{g.height}")"#,
+    )
+    .unwrap();
+    // Seven cells of x, y pairs, down the left side first; the wall can't be reached
+    assert_eq!(out, "14\n0\n1\n0\n4\n11\n7\n-1\n5x3");
+}
+
+#[test]
+fn test_grid_calls_need_a_grid_and_paths_are_read_only() {
+    let out = compile_and_run("fun walls(board: Int) -> Int {\n    return board.get(1, 0)\n}\nlet g = Grid(2, 2)\ng.set(1, 0, 5)\nprint(walls(g))").unwrap();
+    assert_eq!(out, "5");
+    let bin = compile_target("let box = rect(10, 10)\nprint(box.get(0, 0))", "headless").unwrap();
+    let run = Command::new(&bin).output().expect("failed to run compiled binary");
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(stderr.contains("runtime error at line 2, column 7: this is not a grid"), "{stderr}");
+    let bin = compile_target("let g = Grid(2, 2)\nprint(g[0])", "headless").unwrap();
+    let run = Command::new(&bin).output().expect("failed to run compiled binary");
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(stderr.contains("this is a grid, not an array"), "{stderr}");
+    let err = compile_only("let g = Grid(2, 2)\ng.width = 3").unwrap_err();
+    assert!(err.contains(".width is read-only"), "{err}");
+    let err = compile_only("let g = Grid(2, 2)\nlet way = Math.Path(g, Point(0, 0), Point(1, 1))\nway[0] = 1").unwrap_err();
+    assert!(err.contains("this array is read-only"), "{err}");
+}
@@ -174,6 +174,12 @@ pub enum Expression {
         end: Box<Expression>,
         span: Span,
     },
+    /// Named call argument: `fps: 10`
+    NamedArg {
+        name: Identifier,
+        value: Box<Expression>,
+        span: Span,
+    },
 }
 
 /// A part of an interpolated string.
@@ -199,7 +205,8 @@ impl Expression {
             | Expression::Array { span, .. }
             | Expression::Assignment { span, .. }
             | Expression::StringInterp { span, .. }
-            | Expression::Range { span, .. } => *span,
+            | Expression::Range { span, .. }
+            | Expression::NamedArg { span, .. } => *span,
         }
     }
 }
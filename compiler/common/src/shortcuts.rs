@@ -48,6 +48,18 @@ pub static SHORTCUTS: &[ShortcutDef] = &[
         prefix_chain: "Random",
         description: "Generate a random number",
     },
+    ShortcutDef {
+        name: "random_float",
+        namespace: "Math",
+        prefix_chain: "RandomFloat",
+        description: "Random float in a range",
+    },
+    ShortcutDef {
+        name: "chance",
+        namespace: "Math",
+        prefix_chain: "Chance",
+        description: "True with the given probability",
+    },
     ShortcutDef {
         name: "abs",
         namespace: "Math",
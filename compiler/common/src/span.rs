@@ -37,3 +37,39 @@ impl Default for Span {
         Self::dummy()
     }
 }
+
+/// Maps byte offsets in a source text to 1-based line and column numbers.
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    /// Byte offset where each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { line_starts }
+    }
+
+    /// Line and column of a byte offset; the column counts bytes.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset).max(1);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let index = LineIndex::new("let a = 1\n\n  b.x = 2\n");
+        assert_eq!(index.line_col(0), (1, 1));
+        assert_eq!(index.line_col(4), (1, 5));
+        assert_eq!(index.line_col(10), (2, 1));
+        assert_eq!(index.line_col(13), (3, 3));
+    }
+}
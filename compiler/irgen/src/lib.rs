@@ -1,20 +1,37 @@
 #[cfg(feature = "llvm")]
 pub mod llvm_backend;
 
+/// What the compiled program runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompileTarget {
+    /// Native executable linked against the SDL2 desktop runtime.
+    #[default]
+    Native,
+    /// Native executable linked against the headless runtime: no window,
+    /// no audio, frames run as fast as possible into an offscreen framebuffer.
+    Headless,
+    /// Browser bundle (wasm + JS host) linked against the web runtime.
+    /// `output_path` names the directory to write it into.
+    Web,
+}
+
 pub fn codegen(
     program: &gbasic_common::ast::Program,
+    source: &str,
     output_path: &str,
     dump_ir: bool,
+    target: CompileTarget,
 ) -> Result<(), gbasic_common::error::GBasicError> {
     #[cfg(feature = "llvm")]
     {
         let context = inkwell::context::Context::create();
-        llvm_backend::Codegen::compile(&context, program, output_path, dump_ir)
+        llvm_backend::Codegen::compile(&context, program, source, output_path, dump_ir, target)
     }
     #[cfg(not(feature = "llvm"))]
     {
-        let _ = (program, output_path, dump_ir);
+        let _ = (program, source, output_path, dump_ir, target);
         Err(gbasic_common::error::GBasicError::CodegenError {
+            span: None,
             message: "LLVM backend not enabled. Rebuild with --features llvm".into(),
         })
     }
@@ -1,19 +1,21 @@
 use gbasic_common::ast::*;
 use gbasic_common::error::GBasicError;
-use gbasic_common::span::Span;
+use gbasic_common::span::{LineIndex, Span};
 use gbasic_common::types::Type;
 use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
 use inkwell::targets::{
-    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine,
+    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple,
 };
-use inkwell::types::{BasicMetadataTypeEnum, BasicType};
-use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, PointerValue};
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, FunctionType};
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, IntValue, PointerValue};
 use inkwell::OptimizationLevel;
+use crate::CompileTarget;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// LLVM type descriptor for namespace method signatures
@@ -24,6 +26,14 @@ enum LType {
     Bool,
     Ptr, // *const i8
     Void,
+    /// r, g, b, a as four i64s. As the last parameter it takes a color value
+    /// or its r, g, b[, a] parts; elsewhere just a color value.
+    Color,
+    /// x, y as two f64s, given as `Point(x, y)` or a Screen position.
+    Point,
+    /// An i64 the compiler fills in with a number for the call site, so a
+    /// runtime call returning an array can hand each site back its own one.
+    Site,
 }
 
 impl LType {
@@ -34,6 +44,9 @@ impl LType {
             LType::Bool => Type::Bool,
             LType::Ptr => Type::String,
             LType::Void => Type::Void,
+            LType::Color => Type::Int,
+            LType::Point => Type::Float,
+            LType::Site => Type::Int,
         }
     }
 }
@@ -52,14 +65,21 @@ fn method_to_snake(method: &str) -> &str {
         "drawrect" => "draw_rect",
         "drawline" => "draw_line",
         "drawcircle" => "draw_circle",
+        "drawrectoutline" => "draw_rect_outline",
+        "drawcircleoutline" => "draw_circle_outline",
+        "drawgradient" => "draw_gradient",
         "keypressed" => "key_pressed",
         "mousex" => "mouse_x",
         "mousey" => "mouse_y",
         "readfile" => "read_file",
         "writefile" => "write_file",
+        "removeall" => "remove_all",
+        "objectsin" => "objects_in",
+        "objectsnear" => "objects_near",
         "framebegin" => "frame_begin",
         "frameend" => "frame_end",
         "frametime" => "frame_time",
+        "randomfloat" => "random_float",
         "spriteload" => "sprite_load",
         "spriteat" => "sprite_at",
         "spritescale" => "sprite_scale",
@@ -80,19 +100,51 @@ fn get_namespace_method(namespace: NamespaceRef, method: &str) -> Option<MethodE
         (Math, "sin" | "cos" | "sqrt" | "abs" | "floor" | "ceil") => (vec![F64], F64),
         (Math, "pow" | "max" | "min") => (vec![F64, F64], F64),
         (Math, "random" | "pi") => (vec![], F64),
+        (Math, "randomfloat") => (vec![F64, F64], F64),
+        (Math, "chance") => (vec![F64], Bool),
+        (Math, "seed") => (vec![I64], Void),
+        (Math, "path") => (vec![Site, I64, Point, Point], I64),
         // Screen
         (Screen, "init") => (vec![I64, I64], Void),
-        (Screen, "clear") => (vec![I64, I64, I64], Void),
-        (Screen, "setpixel") => (vec![I64, I64, I64, I64, I64], Void),
-        (Screen, "drawrect") => (vec![I64, I64, I64, I64, I64, I64, I64], Void),
-        (Screen, "drawline") => (vec![I64, I64, I64, I64, I64, I64, I64], Void),
+        (Screen, "clear") => (vec![Color], Void),
+        (Screen, "setpixel") => (vec![I64, I64, Color], Void),
+        (Screen, "drawrect" | "drawline" | "drawrectoutline") => (vec![I64, I64, I64, I64, Color], Void),
         (Screen, "present") => (vec![], Void),
         (Screen, "width" | "height") => (vec![], I64),
-        (Screen, "drawcircle") => (vec![I64, I64, I64, I64, I64, I64], Void),
+        (Screen, "drawcircle" | "drawcircleoutline") => (vec![I64, I64, I64, Color], Void),
+        (Screen, "drawgradient") => (vec![I64, I64, I64, I64, Color, Color, Ptr], Void),
+        (Screen, "blend") => (vec![], Ptr),
+        (Screen, "set_blend") => (vec![Ptr], Void),
+        (Screen, "thickness") => (vec![], F64),
+        (Screen, "set_thickness") => (vec![F64], Void),
+        (Screen, "antialias") => (vec![], Bool),
+        (Screen, "set_antialias") => (vec![Bool], Void),
         (Screen, "spriteload") => (vec![Ptr], I64),
         (Screen, "spriteat") => (vec![I64, F64, F64], I64),
         (Screen, "spritescale") => (vec![I64, F64], I64),
         (Screen, "spritedraw") => (vec![I64], Void),
+        (Screen, "camera_x" | "camera_y" | "camera_zoom" | "camera_smoothing") => (vec![], F64),
+        (Screen, "set_camera_x" | "set_camera_y" | "set_camera_zoom" | "set_camera_smoothing") => (vec![F64], Void),
+        (Screen, "camera_follow") => (vec![I64], Void),
+        (Screen, "camera_unfollow") => (vec![], Void),
+        (Screen, "camera_deadzone" | "camera_shake") => (vec![F64, F64], Void),
+        (Screen, "camera_bounds") => (vec![F64, F64, F64, F64], Void),
+        (Screen, "camera_world_x" | "camera_world_y" | "camera_screen_x" | "camera_screen_y") => (vec![F64], F64),
+        (Screen, "layer") => (vec![I64], Void),
+        (Screen, "layer_opacity" | "layer_scroll" | "layer_blur" | "layer_grayscale" | "layer_brightness") => (vec![I64], F64),
+        (Screen, "set_layer_opacity" | "set_layer_scroll" | "set_layer_blur" | "set_layer_grayscale" | "set_layer_brightness") => {
+            (vec![I64, F64], Void)
+        }
+        (Screen, "layer_visible") => (vec![I64], Bool),
+        (Screen, "set_layer_visible") => (vec![I64, Bool], Void),
+        (Screen, "objects") => (vec![Site, Ptr], I64),
+        (Screen, "count") => (vec![Ptr], I64),
+        (Screen, "nearest") => (vec![Ptr, F64, F64], I64),
+        (Screen, "removeall" | "remove_all") => (vec![Ptr], Void),
+        (Screen, "raycast") => (vec![Point, Point, F64], I64),
+        (Screen, "hit_x" | "hit_y" | "hit_distance") => (vec![], F64),
+        (Screen, "objectsin" | "objects_in") => (vec![Site, I64], I64),
+        (Screen, "objectsnear" | "objects_near") => (vec![Site, Point, F64], I64),
         // Input
         (Input, "keypressed") => (vec![Ptr], Bool),
         (Input, "mousex" | "mousey") => (vec![], I64),
@@ -103,7 +155,12 @@ fn get_namespace_method(namespace: NamespaceRef, method: &str) -> Option<MethodE
         (System, "exit") => (vec![I64], Void),
         (System, "framebegin") => (vec![], Void),
         (System, "frameend") => (vec![], Void),
-        (System, "frametime") => (vec![], F64),
+        (System, "frametime" | "frame_time") => (vec![], F64),
+        (System, "fps") => (vec![], I64),
+        (System, "set_fps") => (vec![I64], Void),
+        (System, "frame_units") => (vec![], Bool),
+        (System, "set_frame_units") => (vec![Bool], Void),
+        (System, "screenshot") => (vec![Ptr], Void),
         // Sound
         (Sound, "beep") => (vec![I64, I64], Void),
         (Sound, "effectload") => (vec![Ptr], I64),
@@ -137,16 +194,51 @@ fn get_namespace_method(namespace: NamespaceRef, method: &str) -> Option<MethodE
     Some(MethodEntry { params, ret, runtime_name })
 }
 
+/// Settings of a layer, read and assigned as `Screen.Layer(n).<setting>`.
+const LAYER_SETTINGS: &[&str] = &["opacity", "visible", "scroll", "blur", "grayscale", "brightness"];
+
+/// Number settings of a particle emitter, `runtime_{get,set}_emitter_<setting>`.
+const EMITTER_SETTINGS: &[&str] =
+    &["rate", "particle_lifetime", "speed", "speed_spread", "angle", "spread", "start_size", "end_size"];
+
+/// Fold `Screen.camera.<member>` into a single `camera_<member>` call, and
+/// `Screen.Layer(n).<setting>` into `layer_<setting>(n)`: the names they
+/// have in the namespace table. Drawing on a layer, as in
+/// `Screen.Layer(n).DrawRect(...)`, stays two calls.
+fn flatten_groups(namespace: NamespaceRef, chain: &[MethodCall]) -> Cow<'_, [MethodCall]> {
+    let [group, member, rest @ ..] = chain else { return Cow::Borrowed(chain) };
+    if namespace != NamespaceRef::Screen {
+        return Cow::Borrowed(chain);
+    }
+    let args = match group.method.name.as_str() {
+        "camera" | "hit" if group.args.is_empty() => member.args.clone(),
+        "layer" if member.args.is_empty() && LAYER_SETTINGS.contains(&member.method.name.as_str()) => group.args.clone(),
+        _ => return Cow::Borrowed(chain),
+    };
+    let name = format!("{}_{}", group.method.name, member.method.name);
+    let method = Identifier { name, span: group.method.span.merge(member.method.span) };
+    let call = MethodCall { method, args, span: group.span.merge(member.span) };
+    Cow::Owned(std::iter::once(call).chain(rest.iter().cloned()).collect())
+}
+
 /// Variable info: alloca pointer + type
 struct VarInfo<'ctx> {
     ptr: PointerValue<'ctx>,
     ty: Type,
+    /// Length of a fixed-size array literal stored in this variable.
+    len: Option<u64>,
+}
+
+/// An array passed to `pick`/`shuffle`: a fixed-size literal or a dynamic array handle.
+enum ArrayArg<'ctx> {
+    Fixed { ptr: PointerValue<'ctx>, len: u64, elem_ty: Type },
+    Dynamic(IntValue<'ctx>),
 }
 
 /// Named color RGB constants.
 fn named_color(name: &str) -> Option<(u8, u8, u8)> {
     match name {
-        "black" => Some((0, 0, 0)),
+        "black" | "transparent" => Some((0, 0, 0)),
         "white" => Some((255, 255, 255)),
         "red" => Some((255, 0, 0)),
         "green" => Some((0, 255, 0)),
@@ -162,6 +254,17 @@ fn named_color(name: &str) -> Option<(u8, u8, u8)> {
     }
 }
 
+/// Alpha of a named color: only `transparent` isn't opaque.
+fn named_alpha(name: &str) -> u8 {
+    if name == "transparent" { 0 } else { 255 }
+}
+
+/// A color as one Int: `t << 24 | r << 16 | g << 8 | b`, where `t` is
+/// 255 - alpha so that plain `r << 16 | g << 8 | b` values stay opaque.
+fn pack_color(r: u8, g: u8, b: u8, a: u8) -> u64 {
+    ((255 - a as u64) << 24) | ((r as u64) << 16) | ((g as u64) << 8) | b as u64
+}
+
 /// Resolve nested field access chain to a property path string.
 /// E.g. `paddle.position.x` → ("paddle", "position.x")
 fn resolve_field_chain(expr: &Expression) -> Option<(String, String)> {
@@ -183,6 +286,33 @@ fn resolve_field_chain(expr: &Expression) -> Option<(String, String)> {
     }
 }
 
+/// Match a built-in method's arguments to its parameters: positional ones in
+/// order, then `name: value` ones by name. Parameters left out are `None`.
+fn bind_args<'a>(method: &str, params: &[&str], args: &'a [Expression]) -> Result<Vec<Option<&'a Expression>>, GBasicError> {
+    let mut bound = vec![None; params.len()];
+    for (i, arg) in args.iter().enumerate() {
+        let (slot, value) = match arg {
+            Expression::NamedArg { name, value, span } => {
+                let slot = params.iter().position(|p| *p == name.name).ok_or_else(|| GBasicError::CodegenError {
+                    span: Some(*span), message: format!("{method}() has no parameter '{}'", name.name),
+                })?;
+                (slot, value.as_ref())
+            }
+            _ if i < params.len() => (i, arg),
+            _ => return Err(GBasicError::CodegenError {
+                span: Some(arg.span()), message: format!("{method}() takes at most {} arguments", params.len()),
+            }),
+        };
+        if bound[slot].is_some() {
+            return Err(GBasicError::CodegenError {
+                span: Some(arg.span()), message: format!("{method}() got '{}' twice", params[slot]),
+            });
+        }
+        bound[slot] = Some(value);
+    }
+    Ok(bound)
+}
+
 pub struct Codegen<'ctx> {
     context: &'ctx Context,
     module: Module<'ctx>,
@@ -193,10 +323,14 @@ pub struct Codegen<'ctx> {
     loop_exit_stack: Vec<(BasicBlock<'ctx>, BasicBlock<'ctx>)>,
     /// Whether we're inside an auto-framed while-true loop
     in_auto_frame: bool,
+    /// Turns spans into the line:column reported by runtime errors
+    lines: LineIndex,
+    /// Call sites numbered so far, for `LType::Site` parameters.
+    sites: u64,
 }
 
 impl<'ctx> Codegen<'ctx> {
-    pub fn new(context: &'ctx Context) -> Self {
+    pub fn new(context: &'ctx Context, source: &str) -> Self {
         let module = context.create_module("gbasic");
         let builder = context.create_builder();
         Self {
@@ -207,9 +341,24 @@ impl<'ctx> Codegen<'ctx> {
             current_function: None,
             loop_exit_stack: Vec::new(),
             in_auto_frame: false,
+            lines: LineIndex::new(source),
+            sites: 0,
         }
     }
 
+    /// Tell the runtime which source location the following calls come
+    /// from, so a runtime error can point at it.
+    fn set_location(&self, span: Span) {
+        let (line, column) = self.lines.line_col(span.start);
+        let i64_type = self.context.i64_type();
+        self.call_runtime(
+            "runtime_set_location",
+            &[LType::I64, LType::I64],
+            LType::Void,
+            &[i64_type.const_int(line as u64, false).into(), i64_type.const_int(column as u64, false).into()],
+        );
+    }
+
     fn needs_terminator(&self) -> bool {
         self.builder.get_insert_block().unwrap().get_terminator().is_none()
     }
@@ -248,6 +397,13 @@ impl<'ctx> Codegen<'ctx> {
         None
     }
 
+    /// A number for a new call site, passed where a runtime call takes an
+    /// `LType::Site`.
+    fn next_site(&mut self) -> IntValue<'ctx> {
+        self.sites += 1;
+        self.context.i64_type().const_int(self.sites, false)
+    }
+
     /// Declare (or reuse) a runtime function and call it. Returns the call site value.
     fn call_runtime(
         &self,
@@ -259,15 +415,7 @@ impl<'ctx> Codegen<'ctx> {
         let function = if let Some(f) = self.module.get_function(name) {
             f
         } else {
-            let params: Vec<BasicMetadataTypeEnum> = param_types.iter().map(|t| self.ltype_to_meta(*t)).collect();
-            let fn_type = match ret {
-                LType::Void => self.context.void_type().fn_type(&params, false),
-                LType::I64 => self.context.i64_type().fn_type(&params, false),
-                LType::F64 => self.context.f64_type().fn_type(&params, false),
-                LType::Bool => self.context.i64_type().fn_type(&params, false),
-                LType::Ptr => self.context.ptr_type(inkwell::AddressSpace::default()).fn_type(&params, false),
-            };
-            self.module.add_function(name, fn_type, None)
+            self.module.add_function(name, self.runtime_fn_type(param_types, ret), None)
         };
         let label = if ret == LType::Void { "" } else { "rt_call" };
         let result = self.builder.build_call(function, args, label).unwrap();
@@ -318,10 +466,12 @@ impl<'ctx> Codegen<'ctx> {
     pub fn compile(
         context: &'ctx Context,
         program: &Program,
+        source: &str,
         output_path: &str,
         dump_ir: bool,
+        target: CompileTarget,
     ) -> Result<(), GBasicError> {
-        let mut cg = Codegen::new(context);
+        let mut cg = Codegen::new(context, source);
         cg.declare_runtime_functions();
 
         // First pass: declare all top-level functions
@@ -371,7 +521,11 @@ impl<'ctx> Codegen<'ctx> {
         }
 
         // Emit and link
-        cg.emit_and_link(output_path)?;
+        match target {
+            CompileTarget::Native => cg.emit_and_link(output_path, "desktop")?,
+            CompileTarget::Headless => cg.emit_and_link(output_path, "headless")?,
+            CompileTarget::Web => cg.emit_web(output_path)?,
+        }
         Ok(())
     }
 
@@ -420,7 +574,7 @@ impl<'ctx> Codegen<'ctx> {
             self.builder.build_store(alloca, param_val).unwrap();
             self.insert_var(
                 param.name.name.clone(),
-                VarInfo { ptr: alloca, ty },
+                VarInfo { ptr: alloca, ty, len: None },
             );
         }
 
@@ -491,11 +645,15 @@ impl<'ctx> Codegen<'ctx> {
             Statement::Let { name, value, .. } => {
                 let val = self.codegen_expression(value)?;
                 let ty = self.infer_expr_type(value);
+                let len = match value {
+                    Expression::Array { elements, .. } if !elements.is_empty() => Some(elements.len() as u64),
+                    _ => None,
+                };
                 match val {
                     Some(v) => {
                         let alloca = self.build_alloca_for_type(&ty, &name.name);
                         self.builder.build_store(alloca, v).unwrap();
-                        self.insert_var(name.name.clone(), VarInfo { ptr: alloca, ty });
+                        self.insert_var(name.name.clone(), VarInfo { ptr: alloca, ty, len });
                     }
                     None => {} // void expression in let — skip
                 }
@@ -685,7 +843,7 @@ impl<'ctx> Codegen<'ctx> {
         exit_bb: BasicBlock<'ctx>,
     ) -> Result<(), GBasicError> {
         self.push_scope();
-        self.insert_var(var_name.to_string(), VarInfo { ptr: var_alloca, ty: var_ty });
+        self.insert_var(var_name.to_string(), VarInfo { ptr: var_alloca, ty: var_ty, len: None });
         self.loop_exit_stack.push((inc_bb, exit_bb));
         for s in &body.statements {
             self.codegen_statement(s)?;
@@ -902,7 +1060,7 @@ impl<'ctx> Codegen<'ctx> {
                     self.push_scope();
                     let alloca = self.build_alloca_for_type(&subject_ty, &id.name);
                     self.builder.build_store(alloca, subject_val).unwrap();
-                    self.insert_var(id.name.clone(), VarInfo { ptr: alloca, ty: subject_ty.clone() });
+                    self.insert_var(id.name.clone(), VarInfo { ptr: alloca, ty: subject_ty.clone(), len: None });
                     for s in &arm.body.statements {
                         self.codegen_statement(s)?;
                     }
@@ -972,8 +1130,7 @@ impl<'ctx> Codegen<'ctx> {
             Expression::Identifier(id) => {
                 // Check for named colors first
                 if let Some((r, g, b)) = named_color(&id.name) {
-                    // Pack as i64: r << 16 | g << 8 | b
-                    let packed = ((r as u64) << 16) | ((g as u64) << 8) | (b as u64);
+                    let packed = pack_color(r, g, b, named_alpha(&id.name));
                     return Ok(Some(self.context.i64_type().const_int(packed, false).into()));
                 }
 
@@ -1003,6 +1160,9 @@ impl<'ctx> Codegen<'ctx> {
                     ).unwrap();
                     return Ok(result.try_as_basic_value().left());
                 }
+                if matches!(op, BinaryOp::And | BinaryOp::Or) {
+                    return self.codegen_logical(left, op, right);
+                }
 
                 let right_ty = self.infer_expr_type(right);
                 let lv = self.codegen_expression(left)?.unwrap();
@@ -1067,6 +1227,10 @@ impl<'ctx> Codegen<'ctx> {
                 self.codegen_call(callee, args)
             }
             Expression::Assignment { target, value, span } => {
+                // Namespace property setter: `System.fps = 30` → runtime_system_set_fps
+                if let Expression::MethodChain { base, chain, .. } = target.as_ref() {
+                    return self.codegen_namespace_property_set(*base, chain, value, *span);
+                }
                 // Check if target is a field access (property setter)
                 if let Some((var_name, prop_path)) = resolve_field_chain(target) {
                     let var = self.lookup_var(&var_name).ok_or_else(|| {
@@ -1080,6 +1244,15 @@ impl<'ctx> Codegen<'ctx> {
                     return self.codegen_property_set(handle, &prop_path, value, *span);
                 }
 
+                // A dynamic array is a handle the runtime only reads from
+                if let Expression::Index { object, .. } = target.as_ref()
+                    && matches!(self.infer_expr_type(object), Type::Int)
+                {
+                    return Err(GBasicError::CodegenError {
+                        span: Some(*span),
+                        message: "this array is read-only; arrays from the runtime or built with .add() can't have elements assigned".into(),
+                    });
+                }
                 let val = self.codegen_expression(value)?.unwrap();
                 if let Expression::Identifier(id) = target.as_ref() {
                     let var = self.lookup_var(&id.name).ok_or_else(|| {
@@ -1117,6 +1290,9 @@ impl<'ctx> Codegen<'ctx> {
             Expression::FieldAccess { .. } => {
                 self.codegen_field_access_read(expr)
             }
+            Expression::NamedArg { name, span, .. } => Err(GBasicError::CodegenError {
+                span: Some(*span), message: format!("unexpected named argument '{}'", name.name),
+            }),
         }
     }
 
@@ -1156,6 +1332,62 @@ impl<'ctx> Codegen<'ctx> {
         Ok(Some(alloca.into()))
     }
 
+    /// Evaluate an array argument of a builtin like `pick`, keeping the length
+    /// of fixed-size literals so the runtime can see every element.
+    fn codegen_array_arg(&mut self, expr: &Expression, builtin: &str) -> Result<ArrayArg<'ctx>, GBasicError> {
+        let len = match expr {
+            Expression::Array { elements, .. } if !elements.is_empty() => Some(elements.len() as u64),
+            Expression::Identifier(id) => self.lookup_var(&id.name).and_then(|v| v.len),
+            _ => None,
+        };
+        let ty = self.infer_expr_type(expr);
+        let val = self.codegen_expression(expr)?;
+        match (val, len, ty) {
+            (Some(v), Some(len), Type::Array(inner)) if v.is_pointer_value() => {
+                Ok(ArrayArg::Fixed { ptr: v.into_pointer_value(), len, elem_ty: *inner })
+            }
+            (Some(v), None, _) if v.is_int_value() => Ok(ArrayArg::Dynamic(v.into_int_value())),
+            _ => Err(GBasicError::CodegenError {
+                span: Some(expr.span()),
+                message: format!("{builtin}() needs a list: a [..] literal, a variable holding one, or a list built with add()"),
+            }),
+        }
+    }
+
+    /// An element of `items`: at `index` if given (clamped into range), otherwise at random.
+    fn codegen_pick(
+        &mut self,
+        items: ArrayArg<'ctx>,
+        index: Option<IntValue<'ctx>>,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
+        let i64_type = self.context.i64_type();
+        match items {
+            ArrayArg::Fixed { ptr, len, elem_ty } => {
+                let last = i64_type.const_int(len - 1, false);
+                let idx = match index {
+                    Some(idx) => {
+                        let zero = i64_type.const_int(0, false);
+                        let neg = self.builder.build_int_compare(inkwell::IntPredicate::SLT, idx, zero, "neg").unwrap();
+                        let idx = self.builder.build_select(neg, zero, idx, "idx").unwrap().into_int_value();
+                        let over = self.builder.build_int_compare(inkwell::IntPredicate::SGT, idx, last, "over").unwrap();
+                        self.builder.build_select(over, last, idx, "idx").unwrap().into_int_value()
+                    }
+                    None => self
+                        .call_runtime("runtime_math_random_range", &[LType::I64, LType::I64], LType::I64, &[i64_type.const_int(0, false).into(), last.into()])
+                        .unwrap()
+                        .into_int_value(),
+                };
+                let llvm_elem_ty = self.type_to_llvm_basic(&elem_ty);
+                let gep = unsafe { self.builder.build_gep(llvm_elem_ty, ptr, &[idx], "pick_ptr").unwrap() };
+                Ok(Some(self.builder.build_load(llvm_elem_ty, gep, "pick").unwrap()))
+            }
+            ArrayArg::Dynamic(h) => Ok(match index {
+                Some(idx) => self.call_runtime("runtime_array_get", &[LType::I64, LType::I64], LType::I64, &[h.into(), idx.into()]),
+                None => self.call_runtime("runtime_array_pick", &[LType::I64], LType::I64, &[h.into()]),
+            }),
+        }
+    }
+
     fn codegen_index(
         &mut self,
         object: &Expression,
@@ -1164,6 +1396,11 @@ impl<'ctx> Codegen<'ctx> {
         let obj_val = self.codegen_expression(object)?.unwrap();
         let idx_val = self.codegen_expression(index)?.unwrap().into_int_value();
 
+        // A dynamic array is a handle; the runtime looks the element up
+        if obj_val.is_int_value() {
+            return Ok(self.call_runtime("runtime_array_get", &[LType::I64, LType::I64], LType::I64, &[obj_val.into(), idx_val.into()]));
+        }
+
         // Infer element type from the array expression
         let elem_ty = match self.infer_expr_type(object) {
             Type::Array(inner) => *inner,
@@ -1188,11 +1425,31 @@ impl<'ctx> Codegen<'ctx> {
 
     fn ltype_to_meta(&self, t: LType) -> BasicMetadataTypeEnum<'ctx> {
         match t {
-            LType::I64 => self.context.i64_type().into(),
+            LType::I64 | LType::Site => self.context.i64_type().into(),
             LType::F64 => self.context.f64_type().into(),
             LType::Bool => self.context.i64_type().into(), // bool passed as i64 in ABI
             LType::Ptr => self.context.ptr_type(inkwell::AddressSpace::default()).into(),
-            LType::Void => unreachable!(),
+            LType::Void | LType::Color | LType::Point => unreachable!(),
+        }
+    }
+
+    /// Signature of a runtime function, with each color parameter spread
+    /// over four i64s and each point over two f64s.
+    fn runtime_fn_type(&self, params: &[LType], ret: LType) -> FunctionType<'ctx> {
+        let params: Vec<BasicMetadataTypeEnum> = params
+            .iter()
+            .flat_map(|&t| match t {
+                LType::Color => vec![self.context.i64_type().into(); 4],
+                LType::Point => vec![self.context.f64_type().into(); 2],
+                t => vec![self.ltype_to_meta(t)],
+            })
+            .collect();
+        match ret {
+            LType::Void => self.context.void_type().fn_type(&params, false),
+            LType::I64 | LType::Bool => self.context.i64_type().fn_type(&params, false),
+            LType::F64 => self.context.f64_type().fn_type(&params, false),
+            LType::Ptr => self.context.ptr_type(inkwell::AddressSpace::default()).fn_type(&params, false),
+            LType::Color | LType::Point | LType::Site => unreachable!("colors, points and sites are only parameters"),
         }
     }
 
@@ -1212,20 +1469,45 @@ impl<'ctx> Codegen<'ctx> {
         let function = if let Some(f) = self.module.get_function(&fn_name) {
             f
         } else {
-            let params: Vec<BasicMetadataTypeEnum> = param_types.iter().map(|t| self.ltype_to_meta(*t)).collect();
-            let fn_type = match ret_type {
-                LType::Void => self.context.void_type().fn_type(&params, false),
-                LType::I64 => self.context.i64_type().fn_type(&params, false),
-                LType::F64 => self.context.f64_type().fn_type(&params, false),
-                LType::Bool => self.context.i64_type().fn_type(&params, false),
-                LType::Ptr => self.context.ptr_type(inkwell::AddressSpace::default()).fn_type(&params, false),
-            };
-            self.module.add_function(&fn_name, fn_type, None)
+            self.module.add_function(&fn_name, self.runtime_fn_type(&param_types, ret_type), None)
         };
 
         Ok((function, param_types, ret_type))
     }
 
+    fn codegen_namespace_property_set(
+        &mut self,
+        namespace: NamespaceRef,
+        chain: &[MethodCall],
+        value: &Expression,
+        span: Span,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
+        let chain = flatten_groups(namespace, chain);
+        let [call] = &chain[..] else {
+            return Err(GBasicError::CodegenError {
+                span: Some(span), message: format!("cannot assign to a {namespace} method call"),
+            });
+        };
+        let property = &call.method.name;
+        let setter = format!("set_{property}");
+        // A setting of something picked by arguments, like a layer, takes them first
+        if get_namespace_method(namespace, &setter).is_none_or(|entry| entry.params.len() != call.args.len() + 1) {
+            return Err(GBasicError::CodegenError {
+                span: Some(span), message: format!("{namespace}.{property} cannot be assigned"),
+            });
+        }
+        let (function, param_types, _) = self.get_or_declare_runtime_fn(namespace, &setter)?;
+        let mut compiled_args: Vec<BasicMetadataValueEnum> = Vec::new();
+        for (arg, &expected) in call.args.iter().chain(std::iter::once(value)).zip(&param_types) {
+            let val = self.codegen_expression(arg)?.ok_or_else(|| GBasicError::CodegenError {
+                span: Some(span), message: format!("void expression assigned to {namespace}.{property}"),
+            })?;
+            compiled_args.push(self.coerce_to_ltype(val, &self.infer_expr_type(arg), expected)?.into());
+        }
+        self.builder.build_call(function, &compiled_args, "").unwrap();
+        Ok(None)
+    }
+
     fn codegen_method_chain(
         &mut self,
         namespace: NamespaceRef,
@@ -1234,7 +1516,7 @@ impl<'ctx> Codegen<'ctx> {
         let mut last_result: Option<BasicValueEnum<'ctx>> = None;
         let mut last_screen_pos: Option<String> = None;
 
-        for call in chain {
+        for call in flatten_groups(namespace, chain).iter() {
             let method_name = &call.method.name; // already lowercased by lexer
 
             // Handle Screen properties that aren't in the namespace table
@@ -1280,14 +1562,37 @@ impl<'ctx> Codegen<'ctx> {
 
             // Codegen args, casting as needed
             let mut compiled_args: Vec<BasicMetadataValueEnum> = Vec::new();
-            for (i, arg) in call.args.iter().enumerate() {
+            let mut args = &call.args[..];
+            for i in 0.. {
+                if param_types.get(i) == Some(&LType::Site) {
+                    compiled_args.push(self.next_site().into());
+                    continue;
+                }
+                let Some(arg) = args.first() else { break };
+                let expected = param_types.get(i).copied().unwrap_or(LType::I64);
+                if expected == LType::Color {
+                    // The last color may be spelled out as r, g, b[, a]
+                    let parts = if i + 1 == param_types.len() && args.len() >= 3 { args.len().min(4) } else { 1 };
+                    let what = format!("{namespace}.{method_name}");
+                    compiled_args.extend(self.codegen_color_parts(&args[..parts], &what, call.span)?.map(BasicMetadataValueEnum::from));
+                    args = &args[parts..];
+                    continue;
+                }
+                if expected == LType::Point {
+                    let (x, y) = self.codegen_point(arg, call.span)?.ok_or_else(|| GBasicError::CodegenError {
+                        span: Some(arg.span()),
+                        message: format!("{namespace}.{method_name} needs a point, like Point(100, 50) or Screen.center"),
+                    })?;
+                    compiled_args.extend([BasicMetadataValueEnum::from(x), y.into()]);
+                    args = &args[1..];
+                    continue;
+                }
                 let val = self.codegen_expression(arg)?.ok_or_else(|| GBasicError::CodegenError {
                     span: None, message: format!("void expression as argument to {namespace}.{method_name}"),
                 })?;
-
-                let expected = param_types.get(i).copied().unwrap_or(LType::I64);
                 let converted = self.coerce_to_ltype(val, &self.infer_expr_type(arg), expected)?;
                 compiled_args.push(converted.into());
+                args = &args[1..];
             }
 
             let call_result = self.builder
@@ -1303,6 +1608,71 @@ impl<'ctx> Codegen<'ctx> {
         Ok(last_result)
     }
 
+    /// A color given as one value (a named color, `Color(...)` or a packed
+    /// Int) or as r, g, b[, a] parts, as four i64s.
+    fn codegen_color_parts(&mut self, args: &[Expression], what: &str, span: Span) -> Result<[IntValue<'ctx>; 4], GBasicError> {
+        match args {
+            [color] => self.codegen_color(color),
+            [r, g, b] | [r, g, b, _] => {
+                let i64_type = self.context.i64_type();
+                let mut parts = [i64_type.const_int(255, false); 4];
+                for (part, arg) in parts.iter_mut().zip([r, g, b].into_iter().chain(args.get(3))) {
+                    let val = self.codegen_expression(arg)?.ok_or_else(|| GBasicError::CodegenError {
+                        span: Some(arg.span()), message: format!("void expression as color for {what}"),
+                    })?;
+                    *part = self.coerce_to_ltype(val, &self.infer_expr_type(arg), LType::I64)?.into_int_value();
+                }
+                Ok(parts)
+            }
+            _ => Err(GBasicError::CodegenError {
+                span: Some(span),
+                message: format!("{what} needs a color, like red or Color(255, 0, 0), or r, g, b with an optional alpha"),
+            }),
+        }
+    }
+
+    /// One color value as r, g, b, a.
+    fn codegen_color(&mut self, value: &Expression) -> Result<[IntValue<'ctx>; 4], GBasicError> {
+        let i64_type = self.context.i64_type();
+        if let Expression::Identifier(id) = value
+            && let Some((r, g, b)) = named_color(&id.name)
+        {
+            return Ok([r, g, b, named_alpha(&id.name)].map(|c| i64_type.const_int(c as u64, false)));
+        }
+        if let Expression::Call { callee, args, span } = value
+            && let Expression::Identifier(id) = callee.as_ref()
+            && id.name == "color"
+            && args.len() != 1
+        {
+            return self.codegen_color_parts(args, "Color", *span);
+        }
+        // Packed color: unpack each byte, turning the top one back into alpha
+        let val = self.codegen_expression(value)?.ok_or_else(|| GBasicError::CodegenError {
+            span: Some(value.span()), message: "void expression used as a color".into(),
+        })?;
+        let packed = self.coerce_to_ltype(val, &self.infer_expr_type(value), LType::I64)?.into_int_value();
+        let byte = |shift: u64, name: &str| {
+            let shifted = self.builder.build_right_shift(packed, i64_type.const_int(shift, false), false, name).unwrap();
+            self.builder.build_and(shifted, i64_type.const_int(0xFF, false), name).unwrap()
+        };
+        let (r, g, b, t) = (byte(16, "r"), byte(8, "g"), byte(0, "b"), byte(24, "t"));
+        let a = self.builder.build_int_sub(i64_type.const_int(255, false), t, "a").unwrap();
+        Ok([r, g, b, a])
+    }
+
+    /// Pack r, g, b, a into one Int the way [`pack_color`] does.
+    fn pack_color_value(&self, [r, g, b, a]: [IntValue<'ctx>; 4]) -> IntValue<'ctx> {
+        let i64_type = self.context.i64_type();
+        let byte = |v: IntValue<'ctx>, shift: u64, name: &str| {
+            let masked = self.builder.build_and(v, i64_type.const_int(0xFF, false), name).unwrap();
+            self.builder.build_left_shift(masked, i64_type.const_int(shift, false), name).unwrap()
+        };
+        let t = self.builder.build_int_sub(i64_type.const_int(255, false), a, "t").unwrap();
+        let packed = self.builder.build_or(byte(t, 24, "t"), byte(r, 16, "r"), "rgba").unwrap();
+        let packed = self.builder.build_or(packed, byte(g, 8, "g"), "rgba").unwrap();
+        self.builder.build_or(packed, byte(b, 0, "b"), "rgba").unwrap()
+    }
+
     fn coerce_to_ltype(
         &self,
         val: BasicValueEnum<'ctx>,
@@ -1322,6 +1692,12 @@ impl<'ctx> Codegen<'ctx> {
                     val.into_float_value(), self.context.i64_type(), "ftoi"
                 ).unwrap().into())
             }
+            // Bool (i1) → i64, the runtime's bool ABI
+            (Type::Bool, LType::Bool) if val.into_int_value().get_type().get_bit_width() == 1 => {
+                Ok(self.builder.build_int_z_extend(
+                    val.into_int_value(), self.context.i64_type(), "bool_ext"
+                ).unwrap().into())
+            }
             _ => Ok(val),
         }
     }
@@ -1343,6 +1719,13 @@ impl<'ctx> Codegen<'ctx> {
                     }
                 }
             }
+            // obj.animations.add(name, frames, fps: .., loop: ..)
+            if field.name == "add"
+                && let Expression::FieldAccess { object: owner, field: list, .. } = object.as_ref()
+                && list.name == "animations"
+            {
+                return self.codegen_add_animation(owner, args);
+            }
             return self.codegen_object_method(object, &field.name, args);
         }
 
@@ -1362,6 +1745,108 @@ impl<'ctx> Codegen<'ctx> {
                     let rf = self.coerce_to_ltype(r, &self.infer_expr_type(&args[0]), LType::F64)?;
                     return Ok(self.call_runtime("runtime_create_circle", &[LType::F64], LType::I64, &[rf.into()]));
                 }
+                "sprite" if args.len() == 1 => {
+                    let name = self.codegen_expression(&args[0])?.unwrap();
+                    self.set_location(id.span);
+                    return Ok(self.call_runtime("runtime_create_sprite", &[LType::Ptr], LType::I64, &[name.into()]));
+                }
+                "sprite_sheet" if args.len() == 3 => {
+                    let name = self.codegen_expression(&args[0])?.unwrap();
+                    let w = self.codegen_expression(&args[1])?.unwrap();
+                    let h = self.codegen_expression(&args[2])?.unwrap();
+                    let wi = self.coerce_to_ltype(w, &self.infer_expr_type(&args[1]), LType::I64)?;
+                    let hi = self.coerce_to_ltype(h, &self.infer_expr_type(&args[2]), LType::I64)?;
+                    self.set_location(id.span);
+                    return Ok(self.call_runtime("runtime_create_sprite_sheet", &[LType::Ptr, LType::I64, LType::I64], LType::I64, &[name.into(), wi.into(), hi.into()]));
+                }
+                "tilemap" if args.len() == 4 => {
+                    let path = self.codegen_expression(&args[0])?.unwrap();
+                    let tileset = self.codegen_expression(&args[1])?.unwrap();
+                    let w = self.codegen_expression(&args[2])?.unwrap();
+                    let h = self.codegen_expression(&args[3])?.unwrap();
+                    let wi = self.coerce_to_ltype(w, &self.infer_expr_type(&args[2]), LType::I64)?;
+                    let hi = self.coerce_to_ltype(h, &self.infer_expr_type(&args[3]), LType::I64)?;
+                    self.set_location(id.span);
+                    return Ok(self.call_runtime("runtime_create_tilemap", &[LType::Ptr, LType::Ptr, LType::I64, LType::I64], LType::I64, &[path.into(), tileset.into(), wi.into(), hi.into()]));
+                }
+                "text" if args.len() == 1 => {
+                    let content = self.codegen_as_string(&args[0])?;
+                    return Ok(self.call_runtime("runtime_create_text", &[LType::Ptr], LType::I64, &[content.into()]));
+                }
+                "line" if args.len() == 2 => {
+                    let mut coords: Vec<BasicMetadataValueEnum> = Vec::new();
+                    for point in args {
+                        let Some((x, y)) = self.codegen_point(point, point.span())? else {
+                            return Err(GBasicError::CodegenError {
+                                span: Some(point.span()),
+                                message: "line() takes two points, like line(Point(0, 0), Point(100, 50))".into(),
+                            });
+                        };
+                        coords.push(x.into());
+                        coords.push(y.into());
+                    }
+                    return Ok(self.call_runtime("runtime_create_line", &[LType::F64; 4], LType::I64, &coords));
+                }
+                "triangle" if args.len() == 3 => {
+                    let (ptr, count) = self.codegen_point_list(args, "triangle", "triangle(Point(0, 20), Point(10, 0), Point(20, 20))")?;
+                    self.set_location(id.span);
+                    return Ok(self.call_runtime("runtime_create_polygon", &[LType::Ptr, LType::I64], LType::I64, &[ptr.into(), count.into()]));
+                }
+                "polygon" if args.len() == 1 => {
+                    let example = "polygon([Point(0, 0), Point(30, 10), Point(0, 20)])";
+                    let Expression::Array { elements, .. } = &args[0] else {
+                        return Err(GBasicError::CodegenError {
+                            span: Some(args[0].span()),
+                            message: format!("polygon() takes a [..] list of points, like {example}"),
+                        });
+                    };
+                    let (ptr, count) = self.codegen_point_list(elements, "polygon", example)?;
+                    self.set_location(id.span);
+                    return Ok(self.call_runtime("runtime_create_polygon", &[LType::Ptr, LType::I64], LType::I64, &[ptr.into(), count.into()]));
+                }
+                "ellipse" if args.len() == 2 => {
+                    let w = self.codegen_expression(&args[0])?.unwrap();
+                    let h = self.codegen_expression(&args[1])?.unwrap();
+                    let wf = self.coerce_to_ltype(w, &self.infer_expr_type(&args[0]), LType::F64)?;
+                    let hf = self.coerce_to_ltype(h, &self.infer_expr_type(&args[1]), LType::F64)?;
+                    return Ok(self.call_runtime("runtime_create_ellipse", &[LType::F64, LType::F64], LType::I64, &[wf.into(), hf.into()]));
+                }
+                "arc" if args.len() == 3 => {
+                    let mut vals: Vec<BasicMetadataValueEnum> = Vec::new();
+                    for arg in args {
+                        let v = self.codegen_expression(arg)?.unwrap();
+                        vals.push(self.coerce_to_ltype(v, &self.infer_expr_type(arg), LType::F64)?.into());
+                    }
+                    return Ok(self.call_runtime("runtime_create_arc", &[LType::F64; 3], LType::I64, &vals));
+                }
+                "emitter" if args.is_empty() => {
+                    return Ok(self.call_runtime("runtime_create_emitter", &[], LType::I64, &[]));
+                }
+                "tween_group" if args.len() <= 1 => {
+                    self.set_location(id.span);
+                    let group = self.call_runtime("runtime_tween_group", &[], LType::I64, &[]).unwrap();
+                    if let Some(after) = args.first() {
+                        let v = self.codegen_expression(after)?.unwrap();
+                        let v = self.coerce_to_ltype(v, &self.infer_expr_type(after), LType::I64)?;
+                        self.call_runtime("runtime_tween_group_set_after", &[LType::I64, LType::I64], LType::Void, &[group.into(), v.into()]);
+                    }
+                    return Ok(Some(group));
+                }
+                "grid" if args.len() == 2 => {
+                    let mut vals: Vec<BasicMetadataValueEnum> = Vec::new();
+                    for arg in args {
+                        let v = self.codegen_expression(arg)?.unwrap();
+                        vals.push(self.coerce_to_ltype(v, &self.infer_expr_type(arg), LType::I64)?.into());
+                    }
+                    self.set_location(id.span);
+                    return Ok(self.call_runtime("runtime_grid_new", &[LType::I64; 2], LType::I64, &vals));
+                }
+                "measure_text" if args.len() == 2 => {
+                    let content = self.codegen_as_string(&args[0])?;
+                    let size = self.codegen_expression(&args[1])?.unwrap();
+                    let sf = self.coerce_to_ltype(size, &self.infer_expr_type(&args[1]), LType::F64)?;
+                    return Ok(self.call_runtime("runtime_measure_text", &[LType::Ptr, LType::F64], LType::F64, &[content.into(), sf.into()]));
+                }
                 "key" if args.len() == 1 => {
                     // Ensure screen is init (for input polling)
                     self.call_runtime("ensure_screen_init", &[], LType::Void, &[]);
@@ -1375,31 +1860,60 @@ impl<'ctx> Codegen<'ctx> {
                 }
                 "clear" => {
                     self.call_runtime("ensure_screen_init", &[], LType::Void, &[]);
-                    if args.len() == 1 {
-                        // clear(named_color) or clear(packed_color)
-                        let val = self.codegen_expression(&args[0])?.unwrap();
-                        // Unpack i64 color: r = (v >> 16) & 0xFF, g = (v >> 8) & 0xFF, b = v & 0xFF
-                        let i64_type = self.context.i64_type();
-                        let iv = val.into_int_value();
-                        let r = self.builder.build_right_shift(iv, i64_type.const_int(16, false), false, "r").unwrap();
-                        let g = self.builder.build_right_shift(iv, i64_type.const_int(8, false), false, "g").unwrap();
-                        let b = self.builder.build_and(iv, i64_type.const_int(0xFF, false), "b").unwrap();
-                        let r = self.builder.build_and(r, i64_type.const_int(0xFF, false), "r").unwrap();
-                        let g = self.builder.build_and(g, i64_type.const_int(0xFF, false), "g").unwrap();
-                        self.call_runtime("runtime_screen_clear", &[LType::I64, LType::I64, LType::I64], LType::Void, &[r.into(), g.into(), b.into()]);
-                    } else if args.len() == 3 {
-                        let r = self.codegen_expression(&args[0])?.unwrap();
-                        let g = self.codegen_expression(&args[1])?.unwrap();
-                        let b = self.codegen_expression(&args[2])?.unwrap();
-                        self.call_runtime("runtime_screen_clear", &[LType::I64, LType::I64, LType::I64], LType::Void, &[r.into(), g.into(), b.into()]);
-                    }
+                    // The screen is always opaque; alpha only matters on a layer
+                    let parts = self.codegen_color_parts(args, "clear", id.span)?;
+                    self.call_runtime("runtime_screen_clear", &[LType::Color], LType::Void, &parts.map(BasicMetadataValueEnum::from));
                     return Ok(None);
                 }
+                "color" if args.len() != 1 => {
+                    let parts = self.codegen_color_parts(args, "Color", id.span)?;
+                    return Ok(Some(self.pack_color_value(parts).into()));
+                }
                 "random" if args.len() == 2 => {
                     let min = self.codegen_expression(&args[0])?.unwrap();
                     let max = self.codegen_expression(&args[1])?.unwrap();
                     return Ok(self.call_runtime("runtime_math_random_range", &[LType::I64, LType::I64], LType::I64, &[min.into(), max.into()]));
                 }
+                "random_float" if args.len() == 2 => {
+                    let min = self.codegen_expression(&args[0])?.unwrap();
+                    let max = self.codegen_expression(&args[1])?.unwrap();
+                    let minf = self.coerce_to_ltype(min, &self.infer_expr_type(&args[0]), LType::F64)?;
+                    let maxf = self.coerce_to_ltype(max, &self.infer_expr_type(&args[1]), LType::F64)?;
+                    return Ok(self.call_runtime("runtime_math_random_float", &[LType::F64, LType::F64], LType::F64, &[minf.into(), maxf.into()]));
+                }
+                "chance" if args.len() == 1 => {
+                    let p = self.codegen_expression(&args[0])?.unwrap();
+                    let pf = self.coerce_to_ltype(p, &self.infer_expr_type(&args[0]), LType::F64)?;
+                    return Ok(self.call_runtime("runtime_math_chance", &[LType::F64], LType::Bool, &[pf.into()]));
+                }
+                "pick" if args.len() == 1 => {
+                    let items = self.codegen_array_arg(&args[0], "pick")?;
+                    return self.codegen_pick(items, None);
+                }
+                "pick_weighted" if args.len() == 2 => {
+                    let items = self.codegen_array_arg(&args[0], "pick_weighted")?;
+                    let index = match self.codegen_array_arg(&args[1], "pick_weighted")? {
+                        ArrayArg::Fixed { ptr, len, elem_ty: Type::Float } => self.call_runtime("runtime_math_weighted_float", &[LType::Ptr, LType::I64], LType::I64, &[ptr.into(), self.context.i64_type().const_int(len, false).into()]),
+                        ArrayArg::Fixed { ptr, len, .. } => self.call_runtime("runtime_math_weighted_int", &[LType::Ptr, LType::I64], LType::I64, &[ptr.into(), self.context.i64_type().const_int(len, false).into()]),
+                        ArrayArg::Dynamic(h) => self.call_runtime("runtime_array_weighted", &[LType::I64], LType::I64, &[h.into()]),
+                    };
+                    return self.codegen_pick(items, Some(index.unwrap().into_int_value()));
+                }
+                "shuffle" if args.len() == 1 => {
+                    match self.codegen_array_arg(&args[0], "shuffle")? {
+                        ArrayArg::Fixed { ptr, len, elem_ty } => {
+                            let i64_type = self.context.i64_type();
+                            let size = self.type_to_llvm_basic(&elem_ty).size_of()
+                                .unwrap_or_else(|| i64_type.const_int(8, false));
+                            let size = self.builder.build_int_cast(size, i64_type, "elem_size").unwrap();
+                            self.call_runtime("runtime_math_shuffle", &[LType::Ptr, LType::I64, LType::I64], LType::Void, &[ptr.into(), i64_type.const_int(len, false).into(), size.into()]);
+                        }
+                        ArrayArg::Dynamic(h) => {
+                            self.call_runtime("runtime_array_shuffle", &[LType::I64], LType::Void, &[h.into()]);
+                        }
+                    }
+                    return Ok(None);
+                }
                 "point" if args.len() == 2 => {
                     // Point(x, y) constructor — pack as two f64 values
                     // For now, just return x as the primary value (used contextually in property setters)
@@ -1561,6 +2075,19 @@ impl<'ctx> Codegen<'ctx> {
         Ok(None)
     }
 
+    /// A value to show as text: strings as they are, numbers formatted.
+    fn codegen_as_string(
+        &mut self,
+        expr: &Expression,
+    ) -> Result<BasicValueEnum<'ctx>, GBasicError> {
+        let val = self.codegen_expression(expr)?.unwrap();
+        Ok(match self.infer_expr_type(expr) {
+            Type::Int => self.call_runtime("runtime_int_to_str", &[LType::I64], LType::Ptr, &[val.into()]).unwrap(),
+            Type::Float => self.call_runtime("runtime_float_to_str", &[LType::F64], LType::Ptr, &[val.into()]).unwrap(),
+            _ => val,
+        })
+    }
+
     /// Build an interpolated string into a single runtime string pointer.
     fn build_interp_string(
         &mut self,
@@ -1684,6 +2211,40 @@ impl<'ctx> Codegen<'ctx> {
         })
     }
 
+    /// `and` / `or` only evaluate the right side when the left doesn't decide
+    /// the result, so `ball.alive and ball.collides(wall)` never touches a
+    /// removed ball.
+    fn codegen_logical(
+        &mut self,
+        left: &Expression,
+        op: &BinaryOp,
+        right: &Expression,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
+        let function = self.current_function.unwrap();
+        let lv = self.codegen_expression(left)?.unwrap();
+        let l1 = self.ensure_i1(lv.into_int_value());
+        let left_end = self.builder.get_insert_block().unwrap();
+        let rhs_bb = self.context.append_basic_block(function, "logic_rhs");
+        let done_bb = self.context.append_basic_block(function, "logic_done");
+        if matches!(op, BinaryOp::And) {
+            self.builder.build_conditional_branch(l1, rhs_bb, done_bb).unwrap();
+        } else {
+            self.builder.build_conditional_branch(l1, done_bb, rhs_bb).unwrap();
+        }
+
+        self.builder.position_at_end(rhs_bb);
+        let rv = self.codegen_expression(right)?.unwrap();
+        let r1 = self.ensure_i1(rv.into_int_value());
+        let rhs_end = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(done_bb).unwrap();
+
+        // Skipping the right side leaves the left's value as the result
+        self.builder.position_at_end(done_bb);
+        let phi = self.builder.build_phi(self.context.bool_type(), "logic").unwrap();
+        phi.add_incoming(&[(&l1, left_end), (&r1, rhs_end)]);
+        Ok(Some(phi.as_basic_value()))
+    }
+
     fn codegen_float_binop(
         &self,
         lv: inkwell::values::FloatValue<'ctx>,
@@ -1772,14 +2333,28 @@ impl<'ctx> Codegen<'ctx> {
                 UnaryOp::Not => Type::Bool,
                 UnaryOp::Neg => self.infer_expr_type(operand),
             },
-            Expression::Call { callee, .. } => {
+            Expression::Call { callee, args, .. } => {
                 if let Expression::Identifier(id) = callee.as_ref() {
                     match id.name.as_str() {
                         "print" | "play" | "clear" => return Type::Void,
-                        "rect" | "circle" => return Type::Int, // handle is i64
+                        "rect" | "circle" | "sprite" | "sprite_sheet" | "tilemap" | "text"
+                        | "line" | "triangle" | "polygon" | "ellipse" | "arc" | "emitter" | "grid" | "tween_group" => return Type::Int, // handle is i64
+                        "measure_text" => return Type::Float,
                         "key" => return Type::Bool,
                         "random" => return Type::Int,
+                        "random_float" => return Type::Float,
+                        "chance" => return Type::Bool,
+                        "shuffle" => return Type::Void,
+                        "pick" | "pick_weighted" => {
+                            if let Some(Type::Array(inner)) = args.first().map(|a| self.infer_expr_type(a))
+                                && *inner != Type::Unknown
+                            {
+                                return *inner;
+                            }
+                            return Type::Int; // dynamic arrays hold Int
+                        }
                         "point" => return Type::Float, // MVP: Point returns float-ish
+                        "color" => return Type::Int,
                         _ => {}
                     }
                     if let Some(func) = self.module.get_function(&id.name) {
@@ -1802,8 +2377,10 @@ impl<'ctx> Codegen<'ctx> {
                 // Method call on object: check known return types
                 if let Expression::FieldAccess { field, .. } = callee.as_ref() {
                     match field.name.as_str() {
-                        "collides" | "contains" => return Type::Bool,
-                        "move" | "remove" | "add" | "at" => return Type::Void,
+                        "collides" | "collides_any" | "contains" | "can_see" => return Type::Bool,
+                        "tile_at" | "tween" | "get" | "neighbors" | "flood_fill" | "grid" => return Type::Int,
+                        "move" | "remove" | "add" | "at" | "play" | "stop" | "set_tile" | "set_solid" | "burst" | "stop_tweens" | "detach"
+                        | "set" | "fill" => return Type::Void,
                         _ => {}
                     }
                 }
@@ -1812,7 +2389,7 @@ impl<'ctx> Codegen<'ctx> {
             Expression::StringInterp { .. } => Type::String,
             Expression::Assignment { value, .. } => self.infer_expr_type(value),
             Expression::MethodChain { base, chain, .. } => {
-                if let Some(last) = chain.last() {
+                if let Some(last) = flatten_groups(*base, chain).last() {
                     // Screen properties
                     if *base == NamespaceRef::Screen {
                         match last.method.name.as_str() {
@@ -1836,10 +2413,12 @@ impl<'ctx> Codegen<'ctx> {
             Expression::Index { object, .. } => {
                 match self.infer_expr_type(object) {
                     Type::Array(inner) => *inner,
+                    Type::Int => Type::Int, // a dynamic array handle
                     _ => Type::Unknown,
                 }
             }
             Expression::Range { .. } => Type::Unknown,
+            Expression::NamedArg { value, .. } => self.infer_expr_type(value),
             Expression::FieldAccess { .. } => {
                 if let Some((var_name, prop_path)) = resolve_field_chain(expr) {
                     if var_name == "screen" {
@@ -1851,7 +2430,15 @@ impl<'ctx> Codegen<'ctx> {
                     }
                     return match prop_path.as_str() {
                         "position.x" | "position.y" | "velocity.x" | "velocity.y"
-                        | "size.width" | "size.height" | "x" | "y" => Type::Float,
+                        | "size.width" | "size.height" | "x" | "y"
+                        | "gravity" | "bounciness" | "friction" | "mass"
+                        | "rotation" | "scale" | "scale_x" | "scale_y" | "opacity" | "size"
+                        | "thickness" | "start_angle" | "end_angle" | "lifetime" => Type::Float,
+                        name if EMITTER_SETTINGS.contains(&name) => Type::Float,
+                        "on_ground" | "alive" | "flip_x" | "flip_y" | "animation_finished" | "ui" | "fill"
+                        | "wraps" | "remove_offscreen" => Type::Bool,
+                        "frame" | "columns" | "rows" | "count" | "parent" | "children" | "width" | "height" => Type::Int,
+                        "animation" | "content" | "font" | "align" | "tag" => Type::String,
                         _ => Type::Unknown,
                     };
                 }
@@ -1895,6 +2482,105 @@ impl<'ctx> Codegen<'ctx> {
         self.type_to_llvm_basic(ty).into()
     }
 
+    /// The x and y of a `Point(x, y)` call or a Screen position like
+    /// `Screen.center`, or None for any other expression.
+    fn codegen_point(
+        &mut self,
+        value: &Expression,
+        span: Span,
+    ) -> Result<Option<(BasicValueEnum<'ctx>, BasicValueEnum<'ctx>)>, GBasicError> {
+        if let Expression::Call { callee, args, .. } = value {
+            if let Expression::Identifier(id) = callee.as_ref() {
+                if id.name == "point" && args.len() == 2 {
+                    let x = self.codegen_expression(&args[0])?.unwrap();
+                    let y = self.codegen_expression(&args[1])?.unwrap();
+                    let xf = self.coerce_to_ltype(x, &self.infer_expr_type(&args[0]), LType::F64)?;
+                    let yf = self.coerce_to_ltype(y, &self.infer_expr_type(&args[1]), LType::F64)?;
+                    return Ok(Some((xf, yf)));
+                }
+            }
+        }
+        // Handle Screen.center, Screen.bottom_center, etc. as points
+        if let Expression::MethodChain { base, chain, .. } = value {
+            if *base == NamespaceRef::Screen {
+                if let Some(last) = chain.last() {
+                    let f64_type = self.context.f64_type();
+                    let zero = f64_type.const_float(0.0);
+                    let (px, py) = match last.method.name.as_str() {
+                        "center" => {
+                            let cx = self.call_runtime("runtime_screen_center_x", &[], LType::F64, &[]).unwrap();
+                            let cy = self.call_runtime("runtime_screen_center_y", &[], LType::F64, &[]).unwrap();
+                            (cx, cy)
+                        }
+                        "bottom_center" => {
+                            let cx = self.call_runtime("runtime_screen_center_x", &[], LType::F64, &[]).unwrap();
+                            let sh = self.call_runtime("runtime_screen_height", &[], LType::I64, &[]).unwrap();
+                            let shy = self.builder.build_signed_int_to_float(sh.into_int_value(), f64_type, "sh").unwrap();
+                            (cx, shy.into())
+                        }
+                        "top_center" => {
+                            let cx = self.call_runtime("runtime_screen_center_x", &[], LType::F64, &[]).unwrap();
+                            (cx, zero.into())
+                        }
+                        "top_left" => (zero.into(), zero.into()),
+                        "top_right" => {
+                            let sw = self.call_runtime("runtime_screen_width", &[], LType::I64, &[]).unwrap();
+                            let swf = self.builder.build_signed_int_to_float(sw.into_int_value(), f64_type, "sw").unwrap();
+                            (swf.into(), zero.into())
+                        }
+                        "bottom_left" => {
+                            let sh = self.call_runtime("runtime_screen_height", &[], LType::I64, &[]).unwrap();
+                            let shy = self.builder.build_signed_int_to_float(sh.into_int_value(), f64_type, "sh").unwrap();
+                            (zero.into(), shy.into())
+                        }
+                        "bottom_right" => {
+                            let sw = self.call_runtime("runtime_screen_width", &[], LType::I64, &[]).unwrap();
+                            let sh = self.call_runtime("runtime_screen_height", &[], LType::I64, &[]).unwrap();
+                            let swf = self.builder.build_signed_int_to_float(sw.into_int_value(), f64_type, "sw").unwrap();
+                            let shy = self.builder.build_signed_int_to_float(sh.into_int_value(), f64_type, "sh").unwrap();
+                            (swf.into(), shy.into())
+                        }
+                        _ => {
+                            return Err(GBasicError::CodegenError {
+                                span: Some(span), message: format!("unknown Screen property '{}'", last.method.name),
+                            });
+                        }
+                    };
+                    return Ok(Some((px, py)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Points for a shape builtin, stored as x, y pairs in a stack array;
+    /// returns the array and how many points it holds.
+    fn codegen_point_list(
+        &mut self,
+        points: &[Expression],
+        builtin: &str,
+        example: &str,
+    ) -> Result<(PointerValue<'ctx>, IntValue<'ctx>), GBasicError> {
+        let f64_type = self.context.f64_type();
+        let i64_type = self.context.i64_type();
+        let array_ty = f64_type.array_type(2 * points.len() as u32);
+        let mut coords = array_ty.get_undef();
+        for (i, point) in points.iter().enumerate() {
+            let Some((x, y)) = self.codegen_point(point, point.span())? else {
+                return Err(GBasicError::CodegenError {
+                    span: Some(point.span()),
+                    message: format!("{builtin}() takes points, like {example}"),
+                });
+            };
+            for (j, v) in [x, y].into_iter().enumerate() {
+                coords = self.builder.build_insert_value(coords, v, (2 * i + j) as u32, "coords").unwrap().into_array_value();
+            }
+        }
+        let alloca = self.builder.build_alloca(array_ty, "points").unwrap();
+        self.builder.build_store(alloca, coords).unwrap();
+        Ok((alloca, i64_type.const_int(points.len() as u64, false)))
+    }
+
     // ─── Object property setter ───
 
     fn codegen_property_set(
@@ -1905,72 +2591,14 @@ impl<'ctx> Codegen<'ctx> {
         span: Span,
     ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
         let h: BasicMetadataValueEnum = handle.into();
+        self.set_location(span);
 
         match prop_path {
             "position" => {
                 // Value should be Point(x, y) call or Screen.center etc.
-                if let Expression::Call { callee, args, .. } = value {
-                    if let Expression::Identifier(id) = callee.as_ref() {
-                        if id.name == "point" && args.len() == 2 {
-                            let x = self.codegen_expression(&args[0])?.unwrap();
-                            let y = self.codegen_expression(&args[1])?.unwrap();
-                            let xf = self.coerce_to_ltype(x, &self.infer_expr_type(&args[0]), LType::F64)?;
-                            let yf = self.coerce_to_ltype(y, &self.infer_expr_type(&args[1]), LType::F64)?;
-                            self.call_runtime("runtime_set_position", &[LType::I64, LType::F64, LType::F64], LType::Void, &[h, xf.into(), yf.into()]);
-                            return Ok(None);
-                        }
-                    }
-                }
-                // Handle Screen.center, Screen.bottom_center, etc. as position values
-                if let Expression::MethodChain { base, chain, .. } = value {
-                    if *base == NamespaceRef::Screen {
-                        if let Some(last) = chain.last() {
-                            let f64_type = self.context.f64_type();
-                            let zero = f64_type.const_float(0.0);
-                            let (px, py) = match last.method.name.as_str() {
-                                "center" => {
-                                    let cx = self.call_runtime("runtime_screen_center_x", &[], LType::F64, &[]).unwrap();
-                                    let cy = self.call_runtime("runtime_screen_center_y", &[], LType::F64, &[]).unwrap();
-                                    (cx, cy)
-                                }
-                                "bottom_center" => {
-                                    let cx = self.call_runtime("runtime_screen_center_x", &[], LType::F64, &[]).unwrap();
-                                    let sh = self.call_runtime("runtime_screen_height", &[], LType::I64, &[]).unwrap();
-                                    let shy = self.builder.build_signed_int_to_float(sh.into_int_value(), f64_type, "sh").unwrap();
-                                    (cx, shy.into())
-                                }
-                                "top_center" => {
-                                    let cx = self.call_runtime("runtime_screen_center_x", &[], LType::F64, &[]).unwrap();
-                                    (cx, zero.into())
-                                }
-                                "top_left" => (zero.into(), zero.into()),
-                                "top_right" => {
-                                    let sw = self.call_runtime("runtime_screen_width", &[], LType::I64, &[]).unwrap();
-                                    let swf = self.builder.build_signed_int_to_float(sw.into_int_value(), f64_type, "sw").unwrap();
-                                    (swf.into(), zero.into())
-                                }
-                                "bottom_left" => {
-                                    let sh = self.call_runtime("runtime_screen_height", &[], LType::I64, &[]).unwrap();
-                                    let shy = self.builder.build_signed_int_to_float(sh.into_int_value(), f64_type, "sh").unwrap();
-                                    (zero.into(), shy.into())
-                                }
-                                "bottom_right" => {
-                                    let sw = self.call_runtime("runtime_screen_width", &[], LType::I64, &[]).unwrap();
-                                    let sh = self.call_runtime("runtime_screen_height", &[], LType::I64, &[]).unwrap();
-                                    let swf = self.builder.build_signed_int_to_float(sw.into_int_value(), f64_type, "sw").unwrap();
-                                    let shy = self.builder.build_signed_int_to_float(sh.into_int_value(), f64_type, "sh").unwrap();
-                                    (swf.into(), shy.into())
-                                }
-                                _ => {
-                                    return Err(GBasicError::CodegenError {
-                                        span: Some(span), message: format!("unknown Screen property '{}'", last.method.name),
-                                    });
-                                }
-                            };
-                            self.call_runtime("runtime_set_position", &[LType::I64, LType::F64, LType::F64], LType::Void, &[h, px.into(), py.into()]);
-                            return Ok(None);
-                        }
-                    }
+                if let Some((px, py)) = self.codegen_point(value, span)? {
+                    self.call_runtime("runtime_set_position", &[LType::I64, LType::F64, LType::F64], LType::Void, &[h, px.into(), py.into()]);
+                    return Ok(None);
                 }
                 Err(GBasicError::CodegenError {
                     span: Some(span), message: "unsupported value for .position assignment; use Point(x, y) or Screen.center".into(),
@@ -1989,37 +2617,8 @@ impl<'ctx> Codegen<'ctx> {
                 Ok(None)
             }
             "color" => {
-                // Handle named color identifier or Color(r, g, b)
-                if let Expression::Identifier(id) = value {
-                    if let Some((r, g, b)) = named_color(&id.name) {
-                        let ri = self.context.i64_type().const_int(r as u64, false);
-                        let gi = self.context.i64_type().const_int(g as u64, false);
-                        let bi = self.context.i64_type().const_int(b as u64, false);
-                        self.call_runtime("runtime_set_color", &[LType::I64, LType::I64, LType::I64, LType::I64], LType::Void, &[h, ri.into(), gi.into(), bi.into()]);
-                        return Ok(None);
-                    }
-                }
-                if let Expression::Call { callee, args, .. } = value {
-                    if let Expression::Identifier(id) = callee.as_ref() {
-                        if id.name == "color" && args.len() == 3 {
-                            let r = self.codegen_expression(&args[0])?.unwrap();
-                            let g = self.codegen_expression(&args[1])?.unwrap();
-                            let b = self.codegen_expression(&args[2])?.unwrap();
-                            self.call_runtime("runtime_set_color", &[LType::I64, LType::I64, LType::I64, LType::I64], LType::Void, &[h, r.into(), g.into(), b.into()]);
-                            return Ok(None);
-                        }
-                    }
-                }
-                // Packed color (from named color used as expression)
-                let val = self.codegen_expression(value)?.unwrap();
-                let i64_type = self.context.i64_type();
-                let iv = val.into_int_value();
-                let r = self.builder.build_right_shift(iv, i64_type.const_int(16, false), false, "r").unwrap();
-                let g = self.builder.build_right_shift(iv, i64_type.const_int(8, false), false, "g").unwrap();
-                let b = self.builder.build_and(iv, i64_type.const_int(0xFF, false), "b").unwrap();
-                let r = self.builder.build_and(r, i64_type.const_int(0xFF, false), "r").unwrap();
-                let g = self.builder.build_and(g, i64_type.const_int(0xFF, false), "g").unwrap();
-                self.call_runtime("runtime_set_color", &[LType::I64, LType::I64, LType::I64, LType::I64], LType::Void, &[h, r.into(), g.into(), b.into()]);
+                let [r, g, b, a] = self.codegen_color(value)?;
+                self.call_runtime("runtime_set_color", &[LType::I64, LType::Color], LType::Void, &[h, r.into(), g.into(), b.into(), a.into()]);
                 Ok(None)
             }
             "velocity" => {
@@ -2052,10 +2651,81 @@ impl<'ctx> Codegen<'ctx> {
                 self.call_runtime("runtime_set_velocity_y", &[LType::I64, LType::F64], LType::Void, &[h, vf.into()]);
                 Ok(None)
             }
-            "gravity" => {
+            "gravity" | "bounciness" | "friction" | "mass"
+            | "rotation" | "scale" | "scale_x" | "scale_y" | "opacity"
+            | "thickness" | "start_angle" | "end_angle" | "lifetime" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                let vf = self.coerce_to_ltype(val, &self.infer_expr_type(value), LType::F64)?;
+                self.call_runtime(&format!("runtime_set_{prop_path}"), &[LType::I64, LType::F64], LType::Void, &[h, vf.into()]);
+                Ok(None)
+            }
+            name if EMITTER_SETTINGS.contains(&name) => {
                 let val = self.codegen_expression(value)?.unwrap();
                 let vf = self.coerce_to_ltype(val, &self.infer_expr_type(value), LType::F64)?;
-                self.call_runtime("runtime_set_gravity", &[LType::I64, LType::F64], LType::Void, &[h, vf.into()]);
+                self.call_runtime(&format!("runtime_set_emitter_{name}"), &[LType::I64, LType::F64], LType::Void, &[h, vf.into()]);
+                Ok(None)
+            }
+            "end_color" => {
+                let [r, g, b, a] = self.codegen_color(value)?;
+                self.call_runtime("runtime_set_emitter_end_color", &[LType::I64, LType::Color], LType::Void, &[h, r.into(), g.into(), b.into(), a.into()]);
+                Ok(None)
+            }
+            "count" => Err(GBasicError::CodegenError {
+                span: Some(span), message: ".count is read-only; it's how many particles are alive".into(),
+            }),
+            "collision_shape" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                self.call_runtime("runtime_set_collision_shape", &[LType::I64, LType::Ptr], LType::Void, &[h, val.into()]);
+                Ok(None)
+            }
+            "content" => {
+                let val = self.codegen_as_string(value)?;
+                self.call_runtime("runtime_set_content", &[LType::I64, LType::Ptr], LType::Void, &[h, val.into()]);
+                Ok(None)
+            }
+            "font" | "align" | "tag" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                self.call_runtime(&format!("runtime_set_{prop_path}"), &[LType::I64, LType::Ptr], LType::Void, &[h, val.into()]);
+                Ok(None)
+            }
+            "size" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                let vf = self.coerce_to_ltype(val, &self.infer_expr_type(value), LType::F64)?;
+                self.call_runtime("runtime_set_text_size", &[LType::I64, LType::F64], LType::Void, &[h, vf.into()]);
+                Ok(None)
+            }
+            "on_ground" => Err(GBasicError::CodegenError {
+                span: Some(span), message: ".on_ground is read-only; physics sets it".into(),
+            }),
+            "alive" => Err(GBasicError::CodegenError {
+                span: Some(span), message: ".alive is read-only; use .remove() to remove an object".into(),
+            }),
+            "width" | "height" => Err(GBasicError::CodegenError {
+                span: Some(span), message: format!(".{prop_path} is read-only; a grid keeps the size it was made with"),
+            }),
+            "columns" | "rows" => Err(GBasicError::CodegenError {
+                span: Some(span), message: format!(".{prop_path} is read-only; it comes from the map file"),
+            }),
+            "animation" | "animation_finished" => Err(GBasicError::CodegenError {
+                span: Some(span), message: format!(".{prop_path} is read-only; use .play(name) to start an animation"),
+            }),
+            "frame" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                let vi = self.coerce_to_ltype(val, &self.infer_expr_type(value), LType::I64)?;
+                self.call_runtime("runtime_set_frame", &[LType::I64, LType::I64], LType::Void, &[h, vi.into()]);
+                Ok(None)
+            }
+            "parent" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                self.call_runtime("runtime_set_parent", &[LType::I64, LType::I64], LType::Void, &[h, val.into()]);
+                Ok(None)
+            }
+            "children" => Err(GBasicError::CodegenError {
+                span: Some(span), message: ".children is read-only; set each child's .parent instead".into(),
+            }),
+            "on_removed" => {
+                let (f, takes_object) = self.callback(value, "on_removed")?;
+                self.call_runtime("runtime_set_on_removed", &[LType::I64, LType::Ptr, LType::I64], LType::Void, &[h, f.into(), takes_object.into()]);
                 Ok(None)
             }
             "solid" => {
@@ -2073,6 +2743,13 @@ impl<'ctx> Codegen<'ctx> {
                 self.call_runtime("runtime_set_bounces", &[LType::I64, LType::I64], LType::Void, &[h, i64_val.into()]);
                 Ok(None)
             }
+            "flip_x" | "flip_y" | "ui" | "fill" | "wraps" | "remove_offscreen" => {
+                let val = self.codegen_expression(value)?.unwrap();
+                let iv = val.into_int_value();
+                let i64_val = self.builder.build_int_z_extend(iv, self.context.i64_type(), "bool_ext").unwrap();
+                self.call_runtime(&format!("runtime_set_{prop_path}"), &[LType::I64, LType::I64], LType::Void, &[h, i64_val.into()]);
+                Ok(None)
+            }
             "visible" => {
                 let val = self.codegen_expression(value)?.unwrap();
                 let iv = val.into_int_value();
@@ -2165,15 +2842,39 @@ impl<'ctx> Codegen<'ctx> {
                 let ptr = var.ptr;
                 let handle = self.builder.build_load(handle_ty, ptr, "handle").unwrap();
                 let h: BasicMetadataValueEnum = handle.into();
+                if prop_path == "alive" {
+                    return Ok(self.call_runtime("runtime_get_alive", &[LType::I64], LType::Bool, &[h]));
+                }
+                self.set_location(expr.span());
 
                 return match prop_path.as_str() {
                     "position.x" | "x" => Ok(self.call_runtime("runtime_get_position_x", &[LType::I64], LType::F64, &[h])),
                     "position.y" | "y" => Ok(self.call_runtime("runtime_get_position_y", &[LType::I64], LType::F64, &[h])),
                     "velocity.x" => Ok(self.call_runtime("runtime_get_velocity_x", &[LType::I64], LType::F64, &[h])),
                     "velocity.y" => Ok(self.call_runtime("runtime_get_velocity_y", &[LType::I64], LType::F64, &[h])),
+                    "gravity" | "bounciness" | "friction" | "mass"
+                    | "rotation" | "scale" | "scale_x" | "scale_y" | "opacity"
+                    | "thickness" | "start_angle" | "end_angle" | "lifetime" =>
+                        Ok(self.call_runtime(&format!("runtime_get_{prop_path}"), &[LType::I64], LType::F64, &[h])),
+                    "on_ground" | "flip_x" | "flip_y" | "animation_finished" | "ui" | "fill"
+                    | "wraps" | "remove_offscreen" =>
+                        Ok(self.call_runtime(&format!("runtime_get_{prop_path}"), &[LType::I64], LType::Bool, &[h])),
+                    "frame" | "columns" | "rows" | "parent" =>
+                        Ok(self.call_runtime(&format!("runtime_get_{prop_path}"), &[LType::I64], LType::I64, &[h])),
+                    "children" => {
+                        let site = self.next_site();
+                        Ok(self.call_runtime("runtime_get_children", &[LType::I64, LType::I64], LType::I64, &[site.into(), h]))
+                    }
+                    name if EMITTER_SETTINGS.contains(&name) =>
+                        Ok(self.call_runtime(&format!("runtime_get_emitter_{name}"), &[LType::I64], LType::F64, &[h])),
+                    "count" => Ok(self.call_runtime("runtime_get_emitter_count", &[LType::I64], LType::I64, &[h])),
+                    "animation" | "content" | "font" | "align" | "tag" =>
+                        Ok(self.call_runtime(&format!("runtime_get_{prop_path}"), &[LType::I64], LType::Ptr, &[h])),
+                    "size" => Ok(self.call_runtime("runtime_get_text_size", &[LType::I64], LType::F64, &[h])),
                     "size.width" => Ok(self.call_runtime("runtime_get_size_width", &[LType::I64], LType::F64, &[h])),
                     "size.height" => Ok(self.call_runtime("runtime_get_size_height", &[LType::I64], LType::F64, &[h])),
                     "length" => Ok(self.call_runtime("runtime_array_length", &[LType::I64], LType::I64, &[h])),
+                    "width" | "height" => Ok(self.call_runtime(&format!("runtime_grid_{prop_path}"), &[LType::I64], LType::I64, &[h])),
                     _ => {
                         let null = self.context.ptr_type(inkwell::AddressSpace::default()).const_null();
                         Ok(Some(null.into()))
@@ -2189,6 +2890,168 @@ impl<'ctx> Codegen<'ctx> {
 
     // ─── Object method call ───
 
+    /// `obj.animations.add(name, frames, fps: 10, loop: true)`
+    fn codegen_add_animation(
+        &mut self,
+        object: &Expression,
+        args: &[Expression],
+    ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
+        let bound = bind_args("animations.add", &["name", "frames", "fps", "loop"], args)?;
+        let (Some(name), Some(frame_list)) = (bound[0], bound[1]) else {
+            return Err(GBasicError::CodegenError {
+                span: Some(object.span()), message: "animations.add() needs a name and a list of frames".into(),
+            });
+        };
+        let h = self.codegen_expression(object)?.unwrap();
+        let name = self.codegen_expression(name)?.unwrap();
+        let frames = self.codegen_array_arg(frame_list, "animations.add")?;
+        let fps = match bound[2] {
+            Some(fps) => {
+                let val = self.codegen_expression(fps)?.unwrap();
+                self.coerce_to_ltype(val, &self.infer_expr_type(fps), LType::F64)?
+            }
+            None => self.context.f64_type().const_float(10.0).into(),
+        };
+        let looping = match bound[3] {
+            Some(looping) => {
+                let val = self.codegen_expression(looping)?.unwrap();
+                self.coerce_to_ltype(val, &self.infer_expr_type(looping), LType::Bool)?
+            }
+            None => self.context.i64_type().const_int(1, false).into(),
+        };
+        self.set_location(object.span());
+        match frames {
+            ArrayArg::Fixed { elem_ty, .. } if elem_ty != Type::Int => {
+                return Err(GBasicError::CodegenError {
+                    span: Some(frame_list.span()), message: "animation frames must be whole numbers".into(),
+                });
+            }
+            ArrayArg::Fixed { ptr, len, .. } => {
+                let len = self.context.i64_type().const_int(len, false);
+                self.call_runtime(
+                    "runtime_object_add_animation",
+                    &[LType::I64, LType::Ptr, LType::Ptr, LType::I64, LType::F64, LType::Bool],
+                    LType::Void,
+                    &[h.into(), name.into(), ptr.into(), len.into(), fps.into(), looping.into()],
+                );
+            }
+            ArrayArg::Dynamic(list) => {
+                self.call_runtime(
+                    "runtime_object_add_animation_list",
+                    &[LType::I64, LType::Ptr, LType::I64, LType::F64, LType::Bool],
+                    LType::Void,
+                    &[h.into(), name.into(), list.into(), fps.into(), looping.into()],
+                );
+            }
+        }
+        Ok(None)
+    }
+
+    /// `obj.tween(property, to, duration, ease: .., delay: .., loop: ..,
+    /// yoyo: .., after: .., on_done: ..)`: the tween's id.
+    fn codegen_tween(
+        &mut self,
+        h: BasicMetadataValueEnum<'ctx>,
+        span: Span,
+        args: &[Expression],
+    ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
+        let params = ["property", "to", "duration", "ease", "delay", "loop", "yoyo", "after", "group", "on_done"];
+        let bound = bind_args("tween", &params, args)?;
+        let (Some(property), Some(to), Some(duration)) = (bound[0], bound[1], bound[2]) else {
+            return Err(GBasicError::CodegenError {
+                span: Some(span), message: "tween() needs a property, a value and a duration, like tween(\"position\", Point(400, 100), 0.5)".into(),
+            });
+        };
+        let Expression::Literal(Literal { kind: LiteralKind::String(name), .. }) = property else {
+            return Err(GBasicError::CodegenError {
+                span: Some(property.span()), message: "tween()'s property is a name in quotes, like \"position\"".into(),
+            });
+        };
+        let name_ptr = self.codegen_expression(property)?.unwrap();
+        let secs = self.codegen_expression(duration)?.unwrap();
+        let secs = self.coerce_to_ltype(secs, &self.infer_expr_type(duration), LType::F64)?;
+        let id = match name.to_ascii_lowercase().as_str() {
+            "position" | "velocity" => {
+                let Some((x, y)) = self.codegen_point(to, to.span())? else {
+                    return Err(GBasicError::CodegenError {
+                        span: Some(to.span()), message: format!("tweening .{name} needs a Point, like Point(400, 100)"),
+                    });
+                };
+                self.set_location(span);
+                let params = [LType::I64, LType::Ptr, LType::F64, LType::F64, LType::F64];
+                self.call_runtime("runtime_object_tween_point", &params, LType::I64, &[h, name_ptr.into(), x.into(), y.into(), secs.into()])
+            }
+            "color" => {
+                let [r, g, b, a] = self.codegen_color(to)?;
+                self.set_location(span);
+                let params = [LType::I64, LType::Ptr, LType::Color, LType::F64];
+                self.call_runtime("runtime_object_tween_color", &params, LType::I64, &[h, name_ptr.into(), r.into(), g.into(), b.into(), a.into(), secs.into()])
+            }
+            _ => {
+                let val = self.codegen_expression(to)?.unwrap();
+                let val = self.coerce_to_ltype(val, &self.infer_expr_type(to), LType::F64)?;
+                self.set_location(span);
+                let params = [LType::I64, LType::Ptr, LType::F64, LType::F64];
+                self.call_runtime("runtime_object_tween", &params, LType::I64, &[h, name_ptr.into(), val.into(), secs.into()])
+            }
+        }
+        .unwrap();
+
+        for (setting, value) in params.iter().zip(&bound).skip(3) {
+            let Some(value) = value else { continue };
+            let (arg, ty): (BasicMetadataValueEnum, _) = match *setting {
+                "on_done" => {
+                    let (f, takes_object) = self.callback(value, "on_done")?;
+                    self.call_runtime("runtime_tween_set_on_done", &[LType::I64, LType::Ptr, LType::I64], LType::Void, &[id.into(), f.into(), takes_object.into()]);
+                    continue;
+                }
+                "ease" => (self.codegen_expression(value)?.unwrap().into(), LType::Ptr),
+                setting => {
+                    let ty = match setting {
+                        "delay" => LType::F64,
+                        "loop" | "yoyo" => LType::Bool,
+                        _ => LType::I64,
+                    };
+                    let val = self.codegen_expression(value)?.unwrap();
+                    (self.coerce_to_ltype(val, &self.infer_expr_type(value), ty)?.into(), ty)
+                }
+            };
+            self.set_location(value.span());
+            self.call_runtime(&format!("runtime_tween_set_{setting}"), &[LType::I64, ty], LType::Void, &[id.into(), arg]);
+        }
+        Ok(Some(id))
+    }
+
+    /// A function named as a callback, like `on_done: landed`: its address,
+    /// and 1 if it takes the object as its one parameter.
+    fn callback(
+        &self,
+        value: &Expression,
+        what: &str,
+    ) -> Result<(PointerValue<'ctx>, IntValue<'ctx>), GBasicError> {
+        let err = || GBasicError::CodegenError {
+            span: Some(value.span()),
+            message: format!("{what}: takes the name of a function with no parameters, or one for the object, that returns nothing"),
+        };
+        let Expression::Identifier(id) = value else { return Err(err()) };
+        if self.lookup_var(&id.name).is_some() {
+            return Err(err());
+        }
+        let function = self.module.get_function(&id.name).ok_or_else(err)?;
+        let ty = function.get_type();
+        let params = ty.get_param_types();
+        let takes_object = match params.as_slice() {
+            [] => false,
+            [p] if *p == self.context.i64_type().into() => true,
+            _ => return Err(err()),
+        };
+        if ty.get_return_type().is_some() {
+            return Err(err());
+        }
+        let takes_object = self.context.i64_type().const_int(takes_object as u64, false);
+        Ok((function.as_global_value().as_pointer_value(), takes_object))
+    }
+
     fn codegen_object_method(
         &mut self,
         object: &Expression,
@@ -2197,6 +3060,8 @@ impl<'ctx> Codegen<'ctx> {
     ) -> Result<Option<BasicValueEnum<'ctx>>, GBasicError> {
         let obj_val = self.codegen_expression(object)?.unwrap();
         let h: BasicMetadataValueEnum = obj_val.into();
+        // Arguments may set a location of their own; reset it right before each call
+        let span = object.span();
 
         match method {
             "move" if args.len() == 2 => {
@@ -2204,26 +3069,114 @@ impl<'ctx> Codegen<'ctx> {
                 let dy = self.codegen_expression(&args[1])?.unwrap();
                 let dxf = self.coerce_to_ltype(dx, &self.infer_expr_type(&args[0]), LType::F64)?;
                 let dyf = self.coerce_to_ltype(dy, &self.infer_expr_type(&args[1]), LType::F64)?;
+                self.set_location(span);
                 self.call_runtime("runtime_object_move", &[LType::I64, LType::F64, LType::F64], LType::Void, &[h, dxf.into(), dyf.into()]);
                 Ok(None)
             }
             "collides" if args.len() == 1 => {
                 let other = self.codegen_expression(&args[0])?.unwrap();
+                self.set_location(span);
                 let result = self.call_runtime("runtime_object_collides", &[LType::I64, LType::I64], LType::Bool, &[h, other.into()]);
                 Ok(result)
             }
+            "can_see" if args.len() == 1 => {
+                let other = self.codegen_expression(&args[0])?.unwrap();
+                self.set_location(span);
+                Ok(self.call_runtime("runtime_object_can_see", &[LType::I64, LType::I64], LType::Bool, &[h, other.into()]))
+            }
+            "collides_any" if args.len() == 1 => {
+                let tag = self.codegen_expression(&args[0])?.unwrap();
+                self.set_location(span);
+                Ok(self.call_runtime("runtime_object_collides_any", &[LType::I64, LType::Ptr], LType::Bool, &[h, tag.into()]))
+            }
             "contains" if args.len() == 2 => {
                 let x = self.codegen_expression(&args[0])?.unwrap();
                 let y = self.codegen_expression(&args[1])?.unwrap();
                 let xf = self.coerce_to_ltype(x, &self.infer_expr_type(&args[0]), LType::F64)?;
                 let yf = self.coerce_to_ltype(y, &self.infer_expr_type(&args[1]), LType::F64)?;
+                self.set_location(span);
                 let result = self.call_runtime("runtime_object_contains", &[LType::I64, LType::F64, LType::F64], LType::Bool, &[h, xf.into(), yf.into()]);
                 Ok(result)
             }
             "remove" => {
+                self.set_location(span);
                 self.call_runtime("runtime_object_remove", &[LType::I64], LType::Void, &[h]);
                 Ok(None)
             }
+            "detach" if args.is_empty() => {
+                self.set_location(span);
+                self.call_runtime("runtime_object_detach", &[LType::I64], LType::Void, &[h]);
+                Ok(None)
+            }
+            "play" if args.len() == 1 => {
+                let name = self.codegen_expression(&args[0])?.unwrap();
+                self.set_location(span);
+                self.call_runtime("runtime_object_play", &[LType::I64, LType::Ptr], LType::Void, &[h, name.into()]);
+                Ok(None)
+            }
+            "stop" if args.is_empty() => {
+                self.set_location(span);
+                self.call_runtime("runtime_object_stop", &[LType::I64], LType::Void, &[h]);
+                Ok(None)
+            }
+            "tile_at" if args.len() == 2 => {
+                let x = self.codegen_expression(&args[0])?.unwrap();
+                let y = self.codegen_expression(&args[1])?.unwrap();
+                let xf = self.coerce_to_ltype(x, &self.infer_expr_type(&args[0]), LType::F64)?;
+                let yf = self.coerce_to_ltype(y, &self.infer_expr_type(&args[1]), LType::F64)?;
+                self.set_location(span);
+                Ok(self.call_runtime("runtime_tilemap_tile_at", &[LType::I64, LType::F64, LType::F64], LType::I64, &[h, xf.into(), yf.into()]))
+            }
+            "set_tile" if args.len() == 3 => {
+                let x = self.codegen_expression(&args[0])?.unwrap();
+                let y = self.codegen_expression(&args[1])?.unwrap();
+                let tile = self.codegen_expression(&args[2])?.unwrap();
+                let xf = self.coerce_to_ltype(x, &self.infer_expr_type(&args[0]), LType::F64)?;
+                let yf = self.coerce_to_ltype(y, &self.infer_expr_type(&args[1]), LType::F64)?;
+                let ti = self.coerce_to_ltype(tile, &self.infer_expr_type(&args[2]), LType::I64)?;
+                self.set_location(span);
+                self.call_runtime("runtime_tilemap_set_tile", &[LType::I64, LType::F64, LType::F64, LType::I64], LType::Void, &[h, xf.into(), yf.into(), ti.into()]);
+                Ok(None)
+            }
+            "set_solid" if args.len() == 2 => {
+                let tile = self.codegen_expression(&args[0])?.unwrap();
+                let solid = self.codegen_expression(&args[1])?.unwrap();
+                let ti = self.coerce_to_ltype(tile, &self.infer_expr_type(&args[0]), LType::I64)?;
+                let sb = self.coerce_to_ltype(solid, &self.infer_expr_type(&args[1]), LType::Bool)?;
+                self.set_location(span);
+                self.call_runtime("runtime_tilemap_set_solid", &[LType::I64, LType::I64, LType::Bool], LType::Void, &[h, ti.into(), sb.into()]);
+                Ok(None)
+            }
+            "tween" => self.codegen_tween(h, span, args),
+            "stop_tweens" if args.is_empty() => {
+                self.set_location(span);
+                self.call_runtime("runtime_object_stop_tweens", &[LType::I64], LType::Void, &[h]);
+                Ok(None)
+            }
+            "burst" if args.len() == 1 => {
+                let n = self.codegen_expression(&args[0])?.unwrap();
+                let ni = self.coerce_to_ltype(n, &self.infer_expr_type(&args[0]), LType::I64)?;
+                self.set_location(span);
+                self.call_runtime("runtime_emitter_burst", &[LType::I64, LType::I64], LType::Void, &[h, ni.into()]);
+                Ok(None)
+            }
+            "get" | "set" | "fill" | "neighbors" | "flood_fill"
+                if args.len() == match method { "fill" => 1, "get" | "neighbors" => 2, _ => 3 } =>
+            {
+                // Each .neighbors() call refills its own array
+                let mut vals = if method == "neighbors" { vec![self.next_site().into(), h] } else { vec![h] };
+                for arg in args {
+                    let v = self.codegen_expression(arg)?.unwrap();
+                    vals.push(self.coerce_to_ltype(v, &self.infer_expr_type(arg), LType::I64)?.into());
+                }
+                self.set_location(span);
+                let ret = if matches!(method, "set" | "fill") { LType::Void } else { LType::I64 };
+                Ok(self.call_runtime(&format!("runtime_grid_{method}"), &vec![LType::I64; vals.len()], ret, &vals))
+            }
+            "grid" if args.is_empty() => {
+                self.set_location(span);
+                Ok(self.call_runtime("runtime_tilemap_grid", &[LType::I64], LType::I64, &[h]))
+            }
             "add" if args.len() == 1 => {
                 let val = self.codegen_expression(&args[0])?.unwrap();
                 self.call_runtime("runtime_array_add", &[LType::I64, LType::I64], LType::Void, &[h, val.into()]);
@@ -2257,7 +3210,9 @@ impl<'ctx> Codegen<'ctx> {
         }
     }
 
-    fn emit_and_link(&self, output_path: &str) -> Result<(), GBasicError> {
+    /// Compile to a native object and link it against
+    /// `libgbasic_runtime_{runtime}.a` ("desktop" or "headless").
+    fn emit_and_link(&self, output_path: &str, runtime: &str) -> Result<(), GBasicError> {
         Target::initialize_native(&InitializationConfig::default()).map_err(|e| {
             GBasicError::CodegenError {
                 span: None, message: format!("failed to init native target: {e}"),
@@ -2289,40 +3244,15 @@ impl<'ctx> Codegen<'ctx> {
                 span: None, message: format!("failed to write object file: {e}"),
             })?;
 
-        // Find workspace root: try exe dir ancestors, then CARGO_MANIFEST_DIR, then cwd
-        let workspace_root = std::env::current_exe()
-            .ok()
-            .and_then(|exe| {
-                // exe is typically in target/debug/gbasic, so go up 3 levels
-                let mut p = exe.as_path();
-                for _ in 0..3 {
-                    p = p.parent()?;
-                }
-                // Verify it looks like our workspace
-                if p.join("Cargo.toml").exists() {
-                    Some(p.to_path_buf())
-                } else {
-                    None
-                }
-            })
-            .or_else(|| {
-                std::env::var("CARGO_MANIFEST_DIR").ok().map(|d| {
-                    Path::new(&d)
-                        .parent()
-                        .unwrap()
-                        .parent()
-                        .unwrap()
-                        .to_path_buf()
-                })
-            })
-            .unwrap_or_else(|| std::env::current_dir().unwrap());
+        let workspace_root = find_workspace_root();
 
         // Try release first, then debug
+        let lib_name = format!("libgbasic_runtime_{runtime}.a");
         let (target_dir, runtime_lib) = {
             let release_dir = workspace_root.join("target/release");
-            let release_lib = release_dir.join("libgbasic_runtime_desktop.a");
+            let release_lib = release_dir.join(&lib_name);
             let debug_dir = workspace_root.join("target/debug");
-            let debug_lib = debug_dir.join("libgbasic_runtime_desktop.a");
+            let debug_lib = debug_dir.join(&lib_name);
             if release_lib.exists() {
                 (release_dir, release_lib)
             } else {
@@ -2333,7 +3263,9 @@ impl<'ctx> Codegen<'ctx> {
         // Find SDL2 bundled lib
         let build_dir = target_dir.join("build");
         let mut sdl2_lib_dir = None;
-        if let Ok(entries) = std::fs::read_dir(&build_dir) {
+        if runtime == "desktop"
+            && let Ok(entries) = std::fs::read_dir(&build_dir)
+        {
             for entry in entries.flatten() {
                 let name = entry.file_name().to_string_lossy().to_string();
                 if name.starts_with("sdl2-sys-") {
@@ -2369,6 +3301,9 @@ impl<'ctx> Codegen<'ctx> {
                     .arg("-framework").arg("Metal")
                     .arg("-liconv");
             }
+            if cfg!(target_os = "linux") {
+                cmd.arg("-lm").arg("-lpthread").arg("-ldl");
+            }
         }
 
         let status = cmd.status().map_err(|e| GBasicError::CodegenError {
@@ -2386,4 +3321,140 @@ impl<'ctx> Codegen<'ctx> {
 
         Ok(())
     }
+
+    /// Build a browser bundle into the directory `output_dir`: the program is
+    /// compiled to wasm32, linked with the web runtime, passed through
+    /// wasm-bindgen and Asyncify, and served by the host page in runtime/web/www.
+    fn emit_web(&self, output_dir: &str) -> Result<(), GBasicError> {
+        let err = |message: String| GBasicError::CodegenError { span: None, message };
+
+        Target::initialize_webassembly(&InitializationConfig::default());
+        let triple = TargetTriple::create("wasm32-unknown-unknown");
+        let target = Target::from_triple(&triple)
+            .map_err(|e| err(format!("failed to get wasm32 target: {e}")))?;
+        let machine = target
+            .create_target_machine(
+                &triple,
+                "generic",
+                "",
+                OptimizationLevel::Default,
+                RelocMode::Default,
+                CodeModel::Default,
+            )
+            .ok_or_else(|| err("failed to create wasm32 target machine".into()))?;
+        self.module.set_triple(&triple);
+        self.module.set_data_layout(&machine.get_target_data().get_data_layout());
+
+        let out_dir = Path::new(output_dir);
+        std::fs::create_dir_all(out_dir)
+            .map_err(|e| err(format!("failed to create {output_dir}: {e}")))?;
+        let obj_path = out_dir.join("game.o");
+        machine
+            .write_to_file(&self.module, FileType::Object, &obj_path)
+            .map_err(|e| err(format!("failed to write object file: {e}")))?;
+
+        let workspace_root = find_workspace_root();
+        let runtime_lib = ["release", "debug"]
+            .iter()
+            .map(|profile| {
+                workspace_root
+                    .join("target/wasm32-unknown-unknown")
+                    .join(profile)
+                    .join("libgbasic_runtime_web.a")
+            })
+            .find(|p| p.exists())
+            .ok_or_else(|| {
+                err("web runtime not built; run `cargo build -p gbasic-runtime-web --target wasm32-unknown-unknown`".into())
+            })?;
+
+        // 1. Link. The whole runtime archive is kept so wasm-bindgen can find
+        //    its descriptors; `main` and the runtime exports stay visible to JS.
+        let linked = out_dir.join("game_linked.wasm");
+        run_tool(
+            Command::new("wasm-ld")
+                .arg(&obj_path)
+                .arg("--whole-archive")
+                .arg(&runtime_lib)
+                .arg("--no-whole-archive")
+                .args(["--no-entry", "--export=main", "--export-dynamic", "--allow-undefined"])
+                .arg("-o")
+                .arg(&linked),
+        )?;
+
+        // 2. Generate the JS glue for web-sys (game.js + game_bg.wasm)
+        run_tool(
+            Command::new("wasm-bindgen")
+                .args(["--target", "web", "--no-typescript", "--out-name", "game", "--out-dir"])
+                .arg(out_dir)
+                .arg(&linked),
+        )?;
+
+        // 3. Let the frame/sleep host calls suspend `main` between frames
+        let bg_wasm = out_dir.join("game_bg.wasm");
+        run_tool(
+            Command::new("wasm-opt")
+                .arg("--asyncify")
+                .arg("--pass-arg=asyncify-imports@gbasic_host.frame_yield,gbasic_host.sleep")
+                .arg("-O2")
+                .arg(&bg_wasm)
+                .arg("-o")
+                .arg(&bg_wasm),
+        )?;
+
+        // 4. Host page
+        let www = workspace_root.join("runtime/web/www");
+        for file in ["index.html", "gbasic.js"] {
+            std::fs::copy(www.join(file), out_dir.join(file))
+                .map_err(|e| err(format!("failed to copy {file}: {e}")))?;
+        }
+
+        let _ = std::fs::remove_file(&obj_path);
+        let _ = std::fs::remove_file(&linked);
+
+        Ok(())
+    }
+}
+
+/// Find workspace root: try exe dir ancestors, then CARGO_MANIFEST_DIR, then cwd
+fn find_workspace_root() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            // exe is typically in target/debug/gbasic, so go up 3 levels
+            let mut p = exe.as_path();
+            for _ in 0..3 {
+                p = p.parent()?;
+            }
+            // Verify it looks like our workspace
+            if p.join("Cargo.toml").exists() {
+                Some(p.to_path_buf())
+            } else {
+                None
+            }
+        })
+        .or_else(|| {
+            std::env::var("CARGO_MANIFEST_DIR").ok().map(|d| {
+                Path::new(&d)
+                    .parent()
+                    .unwrap()
+                    .parent()
+                    .unwrap()
+                    .to_path_buf()
+            })
+        })
+        .unwrap_or_else(|| std::env::current_dir().unwrap())
+}
+
+/// Run an external build tool, turning a missing binary or failure into a codegen error.
+fn run_tool(cmd: &mut Command) -> Result<(), GBasicError> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let status = cmd.status().map_err(|e| GBasicError::CodegenError {
+        span: None, message: format!("failed to run {program}: {e}"),
+    })?;
+    if !status.success() {
+        return Err(GBasicError::CodegenError {
+            span: None, message: format!("{program} failed with status: {status}"),
+        });
+    }
+    Ok(())
 }
@@ -152,7 +152,7 @@ impl Parser {
             match self.current() {
                 Token::LParen => {
                     self.advance();
-                    let args = self.parse_arg_list()?;
+                    let args = self.parse_call_args()?;
                     let end = self.expect(&Token::RParen)?;
                     let span = expr.span().merge(end);
                     expr = Expression::Call {
@@ -315,6 +315,42 @@ impl Parser {
         Ok(args)
     }
 
+    /// Arguments of a call: positional ones, then any `name: value` ones.
+    pub fn parse_call_args(&mut self) -> Result<Vec<Expression>, GBasicError> {
+        let mut args = Vec::new();
+        if matches!(self.current(), Token::RParen) {
+            return Ok(args);
+        }
+        loop {
+            let arg = if let (Token::Ident(name), Token::Colon) = (self.current().clone(), self.peek_ahead(1)) {
+                let name_span = self.current_span();
+                self.advance();
+                self.advance();
+                let value = self.parse_expression()?;
+                let span = name_span.merge(value.span());
+                Expression::NamedArg {
+                    name: Identifier { name, span: name_span },
+                    value: Box::new(value),
+                    span,
+                }
+            } else {
+                let value = self.parse_expression()?;
+                if matches!(args.last(), Some(Expression::NamedArg { .. })) {
+                    return Err(GBasicError::SyntaxError {
+                        message: "positional argument after a named one".into(),
+                        span: value.span(),
+                    });
+                }
+                value
+            };
+            args.push(arg);
+            if !matches!(self.current(), Token::Comma) {
+                return Ok(args);
+            }
+            self.advance();
+        }
+    }
+
     /// Parse a string with `{expr}` interpolation into StringInterp parts.
     fn parse_string_interp(&mut self, s: &str, span: Span) -> Result<Expression, GBasicError> {
         let mut parts = Vec::new();
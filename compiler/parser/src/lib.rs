@@ -218,6 +218,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_named_args() {
+        let program = parse(r#"hero.animations.add("run", [0, 1], fps: 10, loop: false)"#).unwrap();
+        if let Statement::Expression { expr: Expression::Call { args, .. }, .. } = &program.statements[0] {
+            assert_eq!(args.len(), 4);
+            assert!(matches!(&args[2], Expression::NamedArg { name, .. } if name.name == "fps"));
+            assert!(matches!(&args[3], Expression::NamedArg { name, .. } if name.name == "loop"));
+        } else {
+            panic!("expected call");
+        }
+        assert!(parse("f(fps: 10, 2)").is_err());
+    }
+
     #[test]
     fn test_parse_nested_expressions() {
         let program = parse("let x = (1 + 2) * 3").unwrap();
@@ -45,7 +45,7 @@ impl Parser {
             // Allow both Method(args) and Field (no parens, treated as zero-arg call)
             let (args, end) = if matches!(self.current(), Token::LParen) {
                 self.advance();
-                let args = self.parse_arg_list()?;
+                let args = self.parse_call_args()?;
                 let end = self.expect(&Token::RParen)?;
                 (args, end)
             } else {
@@ -17,12 +17,15 @@ pub fn check(program: &Program) -> Result<(), GBasicError> {
 
 struct TypeChecker {
     symbols: SymbolTable,
+    /// Names the compiler turns into runtime calls, which a `fun` can't reuse.
+    builtins: Vec<&'static str>,
 }
 
 impl TypeChecker {
     fn new() -> Self {
         Self {
             symbols: SymbolTable::new(),
+            builtins: vec!["print"],
         }
     }
 
@@ -42,14 +45,34 @@ impl TypeChecker {
         let builtins: &[(&str, Vec<Type>, Type)] = &[
             ("rect", vec![Type::Unknown, Type::Unknown], Type::Int),
             ("circle", vec![Type::Unknown], Type::Int),
+            ("sprite", vec![Type::String], Type::Int),
+            ("sprite_sheet", vec![Type::String, Type::Unknown, Type::Unknown], Type::Int),
+            ("tilemap", vec![Type::String, Type::String, Type::Unknown, Type::Unknown], Type::Int),
+            ("text", vec![Type::Unknown], Type::Int),
+            ("measure_text", vec![Type::Unknown, Type::Unknown], Type::Float),
+            ("line", vec![Type::Unknown, Type::Unknown], Type::Int),
+            ("triangle", vec![Type::Unknown, Type::Unknown, Type::Unknown], Type::Int),
+            ("polygon", vec![Type::Unknown], Type::Int),
+            ("ellipse", vec![Type::Unknown, Type::Unknown], Type::Int),
+            ("arc", vec![Type::Unknown, Type::Unknown, Type::Unknown], Type::Int),
+            ("emitter", vec![], Type::Int),
+            ("grid", vec![Type::Unknown, Type::Unknown], Type::Int),
+            ("tween_group", vec![Type::Int], Type::Int),
             ("key", vec![Type::String], Type::Bool),
             ("play", vec![Type::String], Type::Void),
             ("clear", vec![Type::Unknown], Type::Void),
             ("random", vec![Type::Int, Type::Int], Type::Int),
+            ("random_float", vec![Type::Unknown, Type::Unknown], Type::Float),
+            ("chance", vec![Type::Unknown], Type::Bool),
+            ("pick", vec![Type::Unknown], Type::Unknown),
+            ("pick_weighted", vec![Type::Unknown, Type::Unknown], Type::Unknown),
+            ("shuffle", vec![Type::Unknown], Type::Void),
             ("point", vec![Type::Unknown, Type::Unknown], Type::Unknown),
-            ("color", vec![Type::Int, Type::Int, Type::Int], Type::Unknown),
+            // r, g, b and an optional alpha
+            ("color", vec![Type::Int, Type::Int, Type::Int, Type::Int], Type::Int),
         ];
         for (name, params, ret) in builtins {
+            self.builtins.push(name);
             self.symbols.insert(
                 (*name).into(),
                 Symbol {
@@ -64,7 +87,7 @@ impl TypeChecker {
         // Named colors as global constants
         for color in &[
             "black", "white", "red", "green", "blue", "yellow",
-            "orange", "purple", "pink", "cyan", "gray", "grey", "brown",
+            "orange", "purple", "pink", "cyan", "gray", "grey", "brown", "transparent",
         ] {
             self.symbols.insert(
                 (*color).into(),
@@ -101,6 +124,12 @@ impl TypeChecker {
                 );
             }
             Statement::Function(func) => {
+                if self.builtins.contains(&func.name.name.as_str()) {
+                    return Err(GBasicError::TypeError {
+                        message: format!("'{}' is a built-in function; give your function another name", func.name.name),
+                        span: func.name.span,
+                    });
+                }
                 let param_types: Vec<Type> = func
                     .params
                     .iter()
@@ -290,13 +319,25 @@ impl TypeChecker {
                 let callee_ty = self.check_expression(callee)?;
                 match callee_ty {
                     Type::Function { params, ret } => {
-                        if params.len() != args.len() {
+                        // Only built-in methods take named arguments
+                        if let Some(Expression::NamedArg { name, span, .. }) =
+                            args.iter().find(|a| matches!(a, Expression::NamedArg { .. }))
+                        {
                             return Err(GBasicError::TypeError {
-                                message: format!(
-                                    "expected {} argument(s), found {}",
-                                    params.len(),
-                                    args.len()
-                                ),
+                                message: format!("unexpected named argument '{}'", name.name),
+                                span: *span,
+                            });
+                        }
+                        // Color(r, g, b) may leave out its alpha, and
+                        // tween_group() what it waits for
+                        let optional = matches!(callee.as_ref(), Expression::Identifier(id) if id.name == "color" || id.name == "tween_group");
+                        if args.len() > params.len() || args.len() + (optional as usize) < params.len() {
+                            let expected = match optional {
+                                true => format!("{} or {}", params.len() - 1, params.len()),
+                                false => params.len().to_string(),
+                            };
+                            return Err(GBasicError::TypeError {
+                                message: format!("expected {expected} argument(s), found {}", args.len()),
                                 span: *span,
                             });
                         }
@@ -393,6 +434,7 @@ impl TypeChecker {
                 self.check_expression(end)?;
                 Ok(Type::Unknown)
             }
+            Expression::NamedArg { value, .. } => self.check_expression(value),
         }
     }
 
@@ -629,4 +671,42 @@ mod tests {
         let r = check_src("let x = 1 and 2");
         assert!(r.is_err());
     }
+
+    #[test]
+    fn random_builtins() {
+        assert!(check_src("let f: Float = random_float(1, 2.5)").is_ok());
+        assert!(check_src("if chance(0.25) { print(pick([1, 2, 3])) }").is_ok());
+        assert!(check_src("let a = [3, 1, 2]\nshuffle(a)\nprint(pick_weighted(a, [1, 0, 5]))").is_ok());
+        assert!(check_src("let b: Int = chance(0.5)").is_err());
+        assert!(check_src("shuffle()").is_err());
+    }
+
+    #[test]
+    fn named_args_only_for_methods() {
+        assert!(check_src("let hero = sprite_sheet(\"hero.png\", 16, 16)\nhero.animations.add(\"run\", [0, 1], fps: 8)").is_ok());
+        assert!(check_src("fun f(a) { print(a) }\nf(a: 1)").is_err());
+    }
+
+    #[test]
+    fn color_alpha_is_optional() {
+        assert!(check_src("let c: Int = Color(255, 0, 0)").is_ok());
+        assert!(check_src("clear(Color(0, 0, 0, 128))").is_ok());
+        assert!(check_src("let c = transparent").is_ok());
+        assert!(check_src("let c = Color(255, 0)").is_err());
+        assert!(check_src("let c = Color(1, 2, 3, 4, 5)").is_err());
+    }
+
+    #[test]
+    fn tween_group_may_wait_for_another() {
+        assert!(check_src("let a = tween_group()\nlet b = tween_group(a)").is_ok());
+        assert!(check_src("let a = tween_group(1, 2)").is_err());
+    }
+
+    #[test]
+    fn functions_cannot_take_a_builtin_name() {
+        let err = check_src("fun text(s: String) {\n    print(s)\n}").unwrap_err();
+        assert!(err.to_string().contains("'text' is a built-in function"), "{err}");
+        assert!(check_src("fun line(a: Int) {\n    print(a)\n}").is_err());
+        assert!(check_src("fun label(s: String) {\n    print(s)\n}").is_ok());
+    }
 }
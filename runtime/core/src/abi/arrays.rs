@@ -0,0 +1,141 @@
+use super::*;
+use super::math::{weighted_index, with_rng};
+use std::collections::HashMap;
+
+thread_local! {
+    static DYN_ARRAYS: RefCell<Vec<Vec<i64>>> = const { RefCell::new(Vec::new()) };
+    /// The array each query call site hands back, by site number.
+    static SITE_ARRAYS: RefCell<HashMap<i64, i64>> = RefCell::new(HashMap::new());
+}
+
+// ─── Dynamic arrays ───
+
+/// The array `handle` stands for, or None for a value that isn't one; a grid
+/// is a runtime error, as reading it here would give nothing useful.
+fn with_array<R>(handle: i64, f: impl FnOnce(&mut Vec<i64>) -> R) -> Option<R> {
+    if HandleKind::Grid.index(handle).is_some() {
+        runtime_error("this is a grid, not an array; read its cells with .get(x, y)");
+    }
+    let index = HandleKind::Array.index(handle)?;
+    DYN_ARRAYS.with(|arrs| arrs.borrow_mut().get_mut(index).map(f))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_new() -> i64 {
+    DYN_ARRAYS.with(|arrs| {
+        let mut arrs = arrs.borrow_mut();
+        arrs.push(Vec::new());
+        HandleKind::Array.handle(arrs.len() - 1)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_add(handle: i64, value: i64) {
+    with_array(handle, |arr| arr.push(value));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_length(handle: i64) -> i64 {
+    with_array(handle, |arr| arr.len() as i64).unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_get(handle: i64, index: i64) -> i64 {
+    with_array(handle, |arr| arr.get(index as usize).copied()).flatten().unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_remove_value(handle: i64, value: i64) {
+    with_array(handle, |arr| {
+        if let Some(pos) = arr.iter().position(|&v| v == value) {
+            arr.remove(pos);
+        }
+    });
+}
+
+/// A random element, or 0 for an empty array.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_pick(handle: i64) -> i64 {
+    with_array(handle, |arr| {
+        if arr.is_empty() {
+            return 0;
+        }
+        arr[with_rng(|rng| rng.range(0, arr.len() as i64 - 1)) as usize]
+    })
+    .unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_shuffle(handle: i64) {
+    with_array(handle, |arr| with_rng(|rng| rng.shuffle(arr)));
+}
+
+/// Weighted random index using the array's values as weights, or -1.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_array_weighted(handle: i64) -> i64 {
+    let weights = with_array(handle, |arr| arr.iter().map(|&w| w as f64).collect::<Vec<_>>());
+    weights.map_or(-1, |w| weighted_index(&w))
+}
+
+/// The array of call site `site`, refilled with `values`, for runtime calls
+/// that return a list. Each place in the program that calls one gets the
+/// same array back every time, so a query run every frame reuses one array
+/// instead of piling up new ones; the compiler numbers the sites.
+pub(super) fn site_array(site: i64, values: Vec<i64>) -> i64 {
+    let handle = SITE_ARRAYS.with(|sites| *sites.borrow_mut().entry(site).or_insert_with(|| runtime_array_new()));
+    with_array(handle, |arr| *arr = values);
+    handle
+}
+
+/// Every element of a dynamic array, or `None` for a bad handle.
+pub(super) fn array_values(handle: i64) -> Option<Vec<i64>> {
+    with_array(handle, |arr| arr.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_add_get_remove() {
+        let a = runtime_array_new();
+        runtime_array_add(a, 10);
+        runtime_array_add(a, 20);
+        runtime_array_add(a, 10);
+        assert_eq!(runtime_array_length(a), 3);
+        runtime_array_remove_value(a, 10);
+        assert_eq!(runtime_array_length(a), 2);
+        assert_eq!(runtime_array_get(a, 0), 20);
+        assert_eq!(runtime_array_get(a, 5), 0);
+        assert_eq!(runtime_array_length(a + 100), 0);
+    }
+
+    #[test]
+    fn test_array_pick_and_shuffle() {
+        let a = runtime_array_new();
+        assert_eq!(runtime_array_pick(a), 0);
+        for v in 1..=20 {
+            runtime_array_add(a, v);
+        }
+        assert!((1..=20).contains(&runtime_array_pick(a)));
+        runtime_array_shuffle(a);
+        let mut values: Vec<i64> = (0..20).map(|i| runtime_array_get(a, i)).collect();
+        values.sort();
+        assert_eq!(values, (1..=20).collect::<Vec<_>>());
+
+        let w = runtime_array_new();
+        runtime_array_add(w, 0);
+        runtime_array_add(w, 5);
+        assert_eq!(runtime_array_weighted(w), 1);
+    }
+
+    #[test]
+    fn test_a_call_site_reuses_its_array() {
+        let first = site_array(1, vec![1, 2, 3]);
+        let other = site_array(2, vec![4]);
+        assert_ne!(first, other);
+        assert_eq!(site_array(1, vec![5]), first);
+        assert_eq!((runtime_array_length(first), runtime_array_get(first, 0)), (1, 5));
+        assert_eq!(runtime_array_get(other, 0), 4);
+    }
+}
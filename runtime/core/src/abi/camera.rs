@@ -0,0 +1,127 @@
+use super::*;
+use super::objects::object_center;
+use crate::camera::{Camera, View};
+
+thread_local! {
+    static CAMERA: RefCell<Camera> = RefCell::new(Camera::default());
+    /// Object the camera follows.
+    static TARGET: Cell<Option<i64>> = const { Cell::new(None) };
+}
+
+fn with_camera<R>(f: impl FnOnce(&mut Camera) -> R) -> R {
+    CAMERA.with(|c| f(&mut c.borrow_mut()))
+}
+
+/// Move the camera along for a frame that took `dt` seconds.
+pub(super) fn update_camera(dt: f64) {
+    // A removed target leaves the camera where it was
+    let target = TARGET.with(|t| t.get()).and_then(object_center);
+    let screen = screen_size_or_default();
+    with_camera(|c| c.update(target, screen, dt));
+}
+
+/// What the screen shows of the world this frame.
+pub(super) fn camera_view() -> View {
+    let screen = screen_size_or_default();
+    with_camera(|c| c.view(screen))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_x() -> f64 {
+    let screen = screen_size_or_default();
+    with_camera(|c| c.center(screen).0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_y() -> f64 {
+    let screen = screen_size_or_default();
+    with_camera(|c| c.center(screen).1)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_camera_x(x: f64) {
+    let screen = screen_size_or_default();
+    with_camera(|c| c.set_center(x, c.center(screen).1));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_camera_y(y: f64) {
+    let screen = screen_size_or_default();
+    with_camera(|c| c.set_center(c.center(screen).0, y));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_zoom() -> f64 {
+    with_camera(|c| c.zoom)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_camera_zoom(zoom: f64) {
+    if zoom <= 0.0 || !zoom.is_finite() {
+        runtime_error(&format!("camera zoom must be above 0, got {zoom}"));
+    }
+    with_camera(|c| c.zoom = zoom);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_smoothing() -> f64 {
+    with_camera(|c| c.smoothing)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_camera_smoothing(smoothing: f64) {
+    with_camera(|c| c.smoothing = smoothing.clamp(0.0, 1.0));
+}
+
+/// Keep `handle` on screen from now on, starting centered on it.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_follow(handle: i64) {
+    if object_center(handle).is_none() {
+        runtime_error("the camera can only follow a game object that hasn't been removed");
+    }
+    TARGET.with(|t| t.set(Some(handle)));
+    with_camera(|c| c.snap_to_target());
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_unfollow() {
+    TARGET.with(|t| t.set(None));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_deadzone(w: f64, h: f64) {
+    with_camera(|c| c.deadzone = (w.max(0.0), h.max(0.0)));
+}
+
+/// Keep the view inside a world area; an empty area removes the limit.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_bounds(x: f64, y: f64, w: f64, h: f64) {
+    with_camera(|c| c.bounds = (w > 0.0 && h > 0.0).then_some([x, y, w, h]));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_shake(strength: f64, duration: f64) {
+    with_camera(|c| c.shake(strength, duration));
+}
+
+// Screen ↔ world conversions, e.g. for the mouse
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_world_x(x: f64) -> f64 {
+    camera_view().to_world(x, 0.0).0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_world_y(y: f64) -> f64 {
+    camera_view().to_world(0.0, y).1
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_screen_x(x: f64) -> f64 {
+    camera_view().to_screen(x, 0.0).0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_camera_screen_y(y: f64) -> f64 {
+    camera_view().to_screen(0.0, y).1
+}
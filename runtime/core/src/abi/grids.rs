@@ -0,0 +1,154 @@
+use super::*;
+use super::arrays::site_array;
+use super::objects::with_objects;
+use crate::grid::Grid;
+
+thread_local! {
+    /// Every grid made, by handle. Like dynamic arrays, never freed.
+    static GRIDS: RefCell<Vec<Grid>> = const { RefCell::new(Vec::new()) };
+}
+
+fn with_grid<R>(handle: i64, f: impl FnOnce(&mut Grid) -> R) -> R {
+    let grid = HandleKind::Grid.index(handle);
+    GRIDS.with(|grids| match grid.and_then(|i| grids.borrow_mut().get_mut(i).map(f)) {
+        Some(result) => result,
+        None => runtime_error("this is not a grid; make one with Grid(w, h) or a tilemap's .grid()"),
+    })
+}
+
+fn new_grid(grid: Grid) -> i64 {
+    GRIDS.with(|grids| {
+        let mut grids = grids.borrow_mut();
+        grids.push(grid);
+        HandleKind::Grid.handle(grids.len() - 1)
+    })
+}
+
+/// Points as x and y in turn, in call site `site`'s array.
+fn point_array(site: i64, points: Vec<(i64, i64)>) -> i64 {
+    site_array(site, points.into_iter().flat_map(|(x, y)| [x, y]).collect())
+}
+
+// ─── Grids ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_new(width: i64, height: i64) -> i64 {
+    if width < 0 || height < 0 {
+        runtime_error(&format!("a grid can't be {width} by {height}"));
+    }
+    new_grid(Grid::new(width as usize, height as usize))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_width(handle: i64) -> i64 {
+    with_grid(handle, |g| g.width as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_height(handle: i64) -> i64 {
+    with_grid(handle, |g| g.height as i64)
+}
+
+/// The value at (x, y), or -1 outside the grid.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_get(handle: i64, x: i64, y: i64) -> i64 {
+    with_grid(handle, |g| g.get(x, y).unwrap_or(-1))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_set(handle: i64, x: i64, y: i64, value: i64) {
+    with_grid(handle, |g| {
+        if !g.set(x, y, value) {
+            runtime_error(&format!("({x}, {y}) is outside the {} by {} grid", g.width, g.height));
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_fill(handle: i64, value: i64) {
+    with_grid(handle, |g| g.fill(value));
+}
+
+/// The cells beside (x, y) inside the grid, as x, y pairs.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_neighbors(site: i64, handle: i64, x: i64, y: i64) -> i64 {
+    point_array(site, with_grid(handle, |g| g.neighbors(x, y)))
+}
+
+/// Paint-bucket fill from (x, y); returns how many cells changed.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_grid_flood_fill(handle: i64, x: i64, y: i64, value: i64) -> i64 {
+    with_grid(handle, |g| g.flood_fill(x, y, value) as i64)
+}
+
+/// The shortest path through a grid's 0 cells, as x, y pairs from start
+/// to goal; empty if there's none.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_path(site: i64, handle: i64, start_x: f64, start_y: f64, goal_x: f64, goal_y: f64) -> i64 {
+    let cell = |x: f64, y: f64| (x.floor() as i64, y.floor() as i64);
+    let path = with_grid(handle, |g| g.path(cell(start_x, start_y), cell(goal_x, goal_y)));
+    point_array(site, path.unwrap_or_default())
+}
+
+/// A new grid the size of a tilemap: 1 for each solid tile, 0 elsewhere.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tilemap_grid(handle: i64) -> i64 {
+    let grid = with_objects(|objs| {
+        let map = match objs.get(handle) {
+            Ok(o) => o.tilemap.as_ref(),
+            Err(e) => runtime_error(&e.to_string()),
+        };
+        let Some(map) = map else { runtime_error(".grid() only works on tilemaps") };
+        let mut grid = Grid::new(map.columns, map.rows);
+        for row in 0..map.rows as i64 {
+            for col in 0..map.columns as i64 {
+                grid.set(col, row, map.is_solid(col, row) as i64);
+            }
+        }
+        grid
+    });
+    new_grid(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::arrays::{runtime_array_get, runtime_array_length};
+    use crate::object::{GameObject, ObjectKind};
+    use crate::tilemap::Tilemap;
+
+    fn values(array: i64) -> Vec<i64> {
+        (0..runtime_array_length(array)).map(|i| runtime_array_get(array, i)).collect()
+    }
+
+    #[test]
+    fn test_grid_calls_and_path() {
+        let g = runtime_grid_new(4, 3);
+        assert_eq!((runtime_grid_width(g), runtime_grid_height(g)), (4, 3));
+        // Grids and arrays are counted separately but never share a handle
+        let a = crate::abi::arrays::runtime_array_new();
+        assert_eq!((HandleKind::Array.index(g), HandleKind::Grid.index(a)), (None, None));
+        runtime_grid_set(g, 1, 0, 1);
+        runtime_grid_set(g, 1, 1, 1);
+        assert_eq!((runtime_grid_get(g, 1, 1), runtime_grid_get(g, 4, 0)), (1, -1));
+        assert_eq!(values(runtime_grid_neighbors(1, g, 0, 0)), vec![1, 0, 0, 1]);
+        assert_eq!(values(runtime_math_path(2, g, 0.0, 0.0, 2.5, 0.9)), vec![0, 0, 0, 1, 0, 2, 1, 2, 2, 2, 2, 1, 2, 0]);
+        runtime_grid_set(g, 1, 2, 1);
+        assert_eq!(runtime_array_length(runtime_math_path(2, g, 0.0, 0.0, 2.0, 0.0)), 0);
+        assert_eq!(runtime_grid_flood_fill(g, 0, 0, 2), 3);
+        runtime_grid_fill(g, 0);
+        assert_eq!(runtime_grid_get(g, 1, 1), 0);
+    }
+
+    #[test]
+    fn test_tilemap_grid_marks_solid_tiles() {
+        let mut map = GameObject::new(ObjectKind::Tilemap(0), 0.0, 0.0);
+        let mut tiles = Tilemap::parse_csv("0,1,-1\n-1,0,1").unwrap();
+        tiles.set_solid(1, false);
+        map.tilemap = Some(Box::new(tiles));
+        let handle = with_objects(|objs| objs.insert(map));
+        let g = runtime_tilemap_grid(handle);
+        let cells: Vec<i64> = (0..2).flat_map(|y| (0..3).map(move |x| runtime_grid_get(g, x, y))).collect();
+        assert_eq!(cells, vec![1, 0, 0, 0, 1, 0]);
+    }
+}
@@ -0,0 +1,185 @@
+use super::*;
+use super::arrays::site_array;
+use super::objects::{remove_objects, with_objects};
+use crate::group;
+use crate::store::{HandleError, ObjectStore};
+
+/// Slots of the live objects tagged `tag`, oldest first.
+fn tagged(objs: &ObjectStore, tag: &str) -> Vec<usize> {
+    let mut found: Vec<usize> =
+        (0..objs.slots().len()).filter(|&i| objs.slots()[i].alive && objs.slots()[i].tag == tag).collect();
+    found.sort_by_key(|&i| objs.serial(i));
+    found
+}
+
+fn read_tag<'a>(tag: *const std::ffi::c_char) -> &'a str {
+    unsafe { read_cstr(tag) }.unwrap_or_default()
+}
+
+// ─── Tags ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_tag(handle: i64, tag: *const std::ffi::c_char) {
+    let tag = read_tag(tag);
+    let result = with_objects(|objs| objs.get_mut(handle).map(|o| o.tag = tag.to_string()));
+    if let Err(e) = result {
+        runtime_error(&e.to_string());
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_tag(handle: i64) -> *const std::ffi::c_char {
+    match with_objects(|objs| objs.get(handle).map(|o| o.tag.clone())) {
+        Ok(tag) => leak_cstr(tag),
+        Err(e) => runtime_error(&e.to_string()),
+    }
+}
+
+/// Every object tagged `tag`, oldest first, in call site `site`'s array.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_objects(site: i64, tag: *const std::ffi::c_char) -> i64 {
+    let tag = read_tag(tag);
+    let handles = with_objects(|objs| tagged(objs, tag).into_iter().filter_map(|i| objs.handle_of(i)).collect());
+    site_array(site, handles)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_count(tag: *const std::ffi::c_char) -> i64 {
+    let tag = read_tag(tag);
+    with_objects(|objs| tagged(objs, tag).len() as i64)
+}
+
+/// The object tagged `tag` whose center is closest to (x, y), or 0 if there
+/// are none.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_nearest(tag: *const std::ffi::c_char, x: f64, y: f64) -> i64 {
+    let tag = read_tag(tag);
+    with_objects(|objs| {
+        let distance = |i: usize| {
+            let (cx, cy) = objs.slots()[i].center();
+            (cx - x).hypot(cy - y)
+        };
+        let nearest = tagged(objs, tag).into_iter().min_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+        nearest.and_then(|i| objs.handle_of(i)).unwrap_or(0)
+    })
+}
+
+/// Remove every object tagged `tag`, and their children.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_remove_all(tag: *const std::ffi::c_char) {
+    let tag = read_tag(tag);
+    let handles: Vec<i64> = with_objects(|objs| tagged(objs, tag).into_iter().filter_map(|i| objs.handle_of(i)).collect());
+    remove_objects(&handles);
+}
+
+/// Whether the object touches any object tagged `tag` other than itself,
+/// counting last frame's contacts as `collides` does.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_collides_any(handle: i64, tag: *const std::ffi::c_char) -> i64 {
+    let tag = read_tag(tag);
+    let result = with_objects(|objs| -> Result<bool, HandleError> {
+        let i = objs.index(handle)?;
+        let a = &objs.slots()[i];
+        Ok(tagged(objs, tag).into_iter().any(|j| j != i && (a.overlaps(&objs.slots()[j]) || a.contacts.contains(&j))))
+    });
+    match result {
+        Ok(hit) => hit as i64,
+        Err(e) => runtime_error(&e.to_string()),
+    }
+}
+
+// ─── Parents and children ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_parent(handle: i64, parent: i64) {
+    if let Err(e) = with_objects(|objs| group::set_parent(objs, handle, Some(parent))) {
+        runtime_error(&e);
+    }
+}
+
+/// The object's parent, or 0 if it has none.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_parent(handle: i64) -> i64 {
+    match with_objects(|objs| objs.get(handle).map(|o| o.parent)) {
+        Ok(parent) => parent.unwrap_or(0),
+        Err(e) => runtime_error(&e.to_string()),
+    }
+}
+
+/// The object's children, oldest first, in call site `site`'s array.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_children(site: i64, handle: i64) -> i64 {
+    match with_objects(|objs| objs.index(handle).map(|_| group::children(objs, handle))) {
+        Ok(children) => site_array(site, children),
+        Err(e) => runtime_error(&e.to_string()),
+    }
+}
+
+/// Stop the object moving with its parent; it stays where it is.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_detach(handle: i64) {
+    if let Err(e) = with_objects(|objs| group::set_parent(objs, handle, None)) {
+        runtime_error(&e);
+    }
+}
+
+/// Move children after their parents, once everything else has moved them.
+pub(super) fn move_children() {
+    with_objects(group::follow_parents);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::arrays::{runtime_array_get, runtime_array_length};
+    use crate::abi::objects::{runtime_create_rect, runtime_get_alive, runtime_get_position_x, runtime_object_remove, runtime_set_position};
+
+    fn cstr(s: &str) -> std::ffi::CString {
+        std::ffi::CString::new(s).unwrap()
+    }
+
+    fn tagged_rect(tag: &str, x: f64, y: f64) -> i64 {
+        let r = runtime_create_rect(10.0, 10.0);
+        runtime_set_position(r, x, y);
+        runtime_set_tag(r, cstr(tag).as_ptr());
+        r
+    }
+
+    #[test]
+    fn test_tag_queries() {
+        let enemy = cstr("enemy");
+        let a = tagged_rect("enemy", 100.0, 0.0);
+        let b = tagged_rect("enemy", 300.0, 0.0);
+        let player = tagged_rect("player", 295.0, 5.0);
+        assert_eq!(runtime_screen_count(enemy.as_ptr()), 2);
+        let list = runtime_screen_objects(1, enemy.as_ptr());
+        assert_eq!((runtime_array_length(list), runtime_array_get(list, 0), runtime_array_get(list, 1)), (2, a, b));
+        assert_eq!(runtime_screen_nearest(enemy.as_ptr(), 150.0, 0.0), a);
+        assert_eq!(runtime_screen_nearest(cstr("boss").as_ptr(), 150.0, 0.0), 0);
+
+        assert_eq!(runtime_object_collides_any(player, enemy.as_ptr()), 1);
+        assert_eq!(runtime_object_collides_any(a, enemy.as_ptr()), 0);
+
+        runtime_screen_remove_all(enemy.as_ptr());
+        assert_eq!(runtime_screen_count(enemy.as_ptr()), 0);
+        assert_eq!(runtime_get_alive(player), 1);
+        assert_eq!(runtime_object_collides_any(player, enemy.as_ptr()), 0);
+    }
+
+    #[test]
+    fn test_children_move_and_go_with_their_parent() {
+        let ship = tagged_rect("", 100.0, 100.0);
+        let gun = tagged_rect("", 120.0, 90.0);
+        runtime_set_parent(gun, ship);
+        assert_eq!(runtime_get_parent(gun), ship);
+        let kids = runtime_get_children(1, ship);
+        assert_eq!((runtime_array_length(kids), runtime_array_get(kids, 0)), (1, gun));
+
+        runtime_set_position(ship, 200.0, 100.0);
+        move_children();
+        assert_eq!(runtime_get_position_x(gun), 220.0);
+
+        runtime_object_remove(ship);
+        assert_eq!(runtime_get_alive(gun), 0);
+    }
+}
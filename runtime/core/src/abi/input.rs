@@ -0,0 +1,119 @@
+use super::*;
+use super::system::frame_number;
+use crate::config::RunConfig;
+use crate::input_script::{InputRecorder, InputScript};
+
+/// Where input comes from besides the platform, per the run settings.
+enum InputMode {
+    Live,
+    /// `GBASIC_INPUT`: scripted events on top of live input.
+    Scripted(InputScript),
+    /// `GBASIC_REPLAY`: only the log's events; live input is ignored.
+    Replay(InputScript),
+    /// `GBASIC_RECORD`: live input, logged as it changes.
+    Record(InputRecorder),
+}
+
+thread_local! {
+    static MODE: RefCell<InputMode> = RefCell::new(InputMode::from_config());
+}
+
+impl InputMode {
+    fn from_config() -> Self {
+        let config = RunConfig::get();
+        let load = |path: &std::path::Path| {
+            InputScript::load(&path.to_string_lossy()).unwrap_or_else(|e| fatal(&e))
+        };
+        if let Some(path) = &config.replay {
+            InputMode::Replay(load(path))
+        } else if let Some(path) = &config.record {
+            let seed = config.seed.unwrap_or_else(clock_seed);
+            InputRecorder::create(&path.to_string_lossy(), seed)
+                .map(InputMode::Record)
+                .unwrap_or_else(|e| fatal(&format!("{}: {e}", path.display())))
+        } else if let Some(path) = &config.input_script {
+            InputMode::Scripted(load(path))
+        } else {
+            InputMode::Live
+        }
+    }
+}
+
+fn fatal(message: &str) -> ! {
+    eprintln!("[input] {message}");
+    with_platform(|p| p.exit(1))
+}
+
+/// Seed for the random number generator. A replay uses the recorded seed;
+/// otherwise `GBASIC_SEED`, then an input script's `seed` line, then the clock.
+pub(super) fn start_seed() -> u64 {
+    let config_seed = RunConfig::get().seed;
+    MODE.with(|mode| match &*mode.borrow() {
+        InputMode::Replay(script) => script.seed,
+        InputMode::Record(recorder) => Some(recorder.seed()),
+        InputMode::Scripted(script) => config_seed.or(script.seed),
+        InputMode::Live => config_seed,
+    })
+    .unwrap_or_else(clock_seed)
+}
+
+/// Seed taken from the platform clock, so each run is different.
+fn clock_seed() -> u64 {
+    let micros = with_platform(|p| p.wall_time()) * 1e6;
+    micros as u64
+}
+
+// ─── Input namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_input_poll() {
+    INPUT.with(|input| {
+        let mut input = input.borrow_mut();
+        MODE.with(|mode| match &mut *mode.borrow_mut() {
+            InputMode::Live => with_platform(|p| p.poll(&mut input)),
+            InputMode::Scripted(script) => {
+                with_platform(|p| p.poll(&mut input));
+                script.apply(frame_number(), &mut input);
+            }
+            InputMode::Replay(script) => {
+                // Keep the window responsive, but only closing it gets through
+                let mut live = InputState::default();
+                with_platform(|p| p.poll(&mut live));
+                script.apply(frame_number(), &mut input);
+                input.quit |= live.quit;
+            }
+            InputMode::Record(recorder) => {
+                with_platform(|p| p.poll(&mut input));
+                if let Err(e) = recorder.record(frame_number(), &input) {
+                    eprintln!("[input] failed to write recording: {e}");
+                }
+            }
+        });
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_input_key_pressed(key: *const std::ffi::c_char) -> i64 {
+    let name = match unsafe { read_cstr(key) } {
+        Some(s) => s.to_lowercase(),
+        None => return 0,
+    };
+    INPUT.with(|input| {
+        if *input.borrow().keys.get(&name).unwrap_or(&false) { 1 } else { 0 }
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_input_mouse_x() -> i64 {
+    INPUT.with(|input| input.borrow().mouse_x)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_input_mouse_y() -> i64 {
+    INPUT.with(|input| input.borrow().mouse_y)
+}
+
+/// Whether the user asked to close the window.
+pub(super) fn quit_requested() -> bool {
+    INPUT.with(|input| input.borrow().quit)
+}
@@ -0,0 +1,27 @@
+use super::*;
+
+// ─── IO namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_io_read_file(path: *const std::ffi::c_char) -> *const std::ffi::c_char {
+    match unsafe { read_cstr(path) }.and_then(|p| with_platform(|pl| pl.read_file(p))) {
+        Some(content) => leak_cstr(content),
+        None => std::ptr::null(),
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_io_write_file(path: *const std::ffi::c_char, data: *const std::ffi::c_char) {
+    if let (Some(p), Some(d)) = (unsafe { read_cstr(path) }, unsafe { read_cstr(data) }) {
+        with_platform(|pl| pl.write_file(p, d));
+    }
+}
+
+// ─── Asset namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_asset_load(path: *const std::ffi::c_char) -> i64 {
+    let p = unsafe { read_cstr(path) }.unwrap_or("?");
+    eprintln!("[asset] load(\"{p}\") (stub — asset caching not yet implemented)");
+    0
+}
@@ -0,0 +1,150 @@
+use super::*;
+use super::objects::draw_object;
+use super::screen::ensure_screen_init;
+use crate::camera::View;
+use crate::layer::{self, Layer};
+use crate::object::GameObject;
+use crate::platform::{Color, LayerDraw};
+use std::collections::BTreeMap;
+
+thread_local! {
+    /// Layers the program has painted on or changed; the rest are plain.
+    static LAYERS: RefCell<BTreeMap<i64, Layer>> = const { RefCell::new(BTreeMap::new()) };
+    /// Layer the next drawing command goes to, picked by `Screen.Layer(n)`.
+    static PENDING: Cell<Option<i64>> = const { Cell::new(None) };
+    /// Surface each offscreen layer is put together on before compositing.
+    static SCRATCH: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+fn with_layer<R>(n: i64, f: impl FnOnce(&mut Layer) -> R) -> R {
+    LAYERS.with(|l| f(l.borrow_mut().entry(n).or_default()))
+}
+
+fn layer(n: i64) -> Layer {
+    LAYERS.with(|l| l.borrow().get(&n).copied().unwrap_or_default())
+}
+
+/// Layers that need their own surface this frame, by number.
+pub(super) fn offscreen_layers() -> BTreeMap<i64, Layer> {
+    LAYERS.with(|l| l.borrow().iter().filter(|(_, layer)| !layer.is_plain()).map(|(&n, &l)| (n, l)).collect())
+}
+
+/// True when the next drawing command goes to a layer rather than the screen.
+pub(super) fn painting() -> bool {
+    PENDING.with(|p| p.get().is_some())
+}
+
+/// Run a drawing command on the layer picked with `Screen.Layer(n)`, if
+/// any, or on the screen.
+pub(super) fn on_layer(p: &mut dyn Platform, f: impl FnOnce(&mut dyn Platform)) {
+    let Some(n) = PENDING.with(|p| p.take()) else { return f(p) };
+    let surface = with_layer(n, |l| *l.paint.get_or_insert_with(|| p.create_surface()));
+    p.set_target(Some(surface));
+    f(p);
+    p.set_target(None);
+}
+
+/// Draw an offscreen layer: its painting, scrolled and repeated, then its
+/// objects through its own view of the camera, then the lot on screen
+/// with the layer's opacity and effects.
+pub(super) fn composite(p: &mut dyn Platform, layer: &Layer, objects: &[&GameObject], view: &View, screen: (f64, f64)) {
+    if !layer.visible || layer.opacity <= 0.0 {
+        return;
+    }
+    let view = view.parallax(layer.scroll, screen);
+    let tiles = layer.paint.map(|_| layer::tiles((view.x, view.y), screen)).unwrap_or_default();
+    // A painting that sits still needs no putting together
+    if let (Some(paint), true, [(0.0, 0.0)]) = (layer.paint, objects.is_empty(), tiles.as_slice()) {
+        return p.draw_surface(paint, &layer.draw());
+    }
+    let scratch = SCRATCH.with(|s| {
+        let id = s.get().unwrap_or_else(|| p.create_surface());
+        s.set(Some(id));
+        id
+    });
+    p.set_target(Some(scratch));
+    p.clear(Color::rgba(0, 0, 0, 0));
+    if let Some(paint) = layer.paint {
+        for (x, y) in tiles {
+            p.draw_surface(paint, &LayerDraw { x, y, ..LayerDraw::default() });
+        }
+    }
+    for obj in objects {
+        let view = if obj.ui { &View::SCREEN } else { &view };
+        draw_object(p, obj, view, screen);
+    }
+    p.set_target(None);
+    p.draw_surface(scratch, &layer.draw());
+}
+
+/// `Screen.Layer(n)`: the next drawing command paints on layer `n`, and
+/// stays there from frame to frame. Opens the default window if needed, as
+/// backgrounds are usually painted before anything else.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer(n: i64) {
+    ensure_screen_init();
+    PENDING.with(|p| p.set(Some(n)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer_opacity(n: i64) -> f64 {
+    layer(n).opacity
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_layer_opacity(n: i64, opacity: f64) {
+    with_layer(n, |l| l.opacity = opacity.clamp(0.0, 1.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer_visible(n: i64) -> i64 {
+    layer(n).visible as i64
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_layer_visible(n: i64, visible: i64) {
+    with_layer(n, |l| l.visible = visible != 0);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer_scroll(n: i64) -> f64 {
+    layer(n).scroll
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_layer_scroll(n: i64, scroll: f64) {
+    if !scroll.is_finite() {
+        runtime_error(&format!("layer scroll must be a number, got {scroll}"));
+    }
+    with_layer(n, |l| l.scroll = scroll);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer_blur(n: i64) -> f64 {
+    layer(n).blur
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_layer_blur(n: i64, blur: f64) {
+    with_layer(n, |l| l.blur = blur.max(0.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer_grayscale(n: i64) -> f64 {
+    layer(n).grayscale
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_layer_grayscale(n: i64, grayscale: f64) {
+    with_layer(n, |l| l.grayscale = grayscale.clamp(0.0, 1.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_layer_brightness(n: i64) -> f64 {
+    layer(n).brightness
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_layer_brightness(n: i64, brightness: f64) {
+    with_layer(n, |l| l.brightness = brightness.max(0.0));
+}
@@ -0,0 +1,76 @@
+use super::*;
+use super::screen::*;
+
+// ─── Printing ───
+
+fn write_stdout(text: &str) {
+    with_platform(|p| p.write_stdout(text));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print(s: *const std::ffi::c_char) {
+    let s = unsafe { read_cstr(s) }.unwrap_or("");
+    write_stdout(&format!("{s}\n"));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print_int(v: i64) {
+    write_stdout(&format!("{v}\n"));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print_float(v: f64) {
+    write_stdout(&format!("{v}\n"));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print_str_part(s: *const std::ffi::c_char) {
+    if let Some(s) = unsafe { read_cstr(s) } {
+        write_stdout(s);
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print_int_part(v: i64) {
+    write_stdout(&v.to_string());
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print_float_part(v: f64) {
+    write_stdout(&v.to_string());
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_print_newline() {
+    write_stdout("\n");
+}
+
+// ─── Legacy functions (kept for backward compat) ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_init(width: i32, height: i32) {
+    runtime_screen_init(width as i64, height as i64);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_clear_screen(r: u8, g: u8, b: u8) {
+    runtime_screen_clear(r as i64, g as i64, b as i64, 255);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_present() {
+    runtime_screen_present();
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_should_quit() -> i32 {
+    if screen_size().is_none() || super::input::quit_requested() { 1 } else { 0 }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_shutdown() {
+    SCREEN_SIZE.with(|s| s.set(None));
+    PLATFORM.with(|p| {
+        *p.borrow_mut() = None;
+    });
+}
@@ -0,0 +1,117 @@
+use super::*;
+use super::input::start_seed;
+use crate::rng::Rng;
+
+thread_local! {
+    static RNG: RefCell<Rng> = RefCell::new(Rng::new(start_seed()));
+}
+
+// ─── Math namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_sin(x: f64) -> f64 { x.sin() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_cos(x: f64) -> f64 { x.cos() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_sqrt(x: f64) -> f64 { x.sqrt() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_abs(x: f64) -> f64 { x.abs() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_floor(x: f64) -> f64 { x.floor() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_ceil(x: f64) -> f64 { x.ceil() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_pow(x: f64, y: f64) -> f64 { x.powf(y) }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_max(a: f64, b: f64) -> f64 { a.max(b) }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_min(a: f64, b: f64) -> f64 { a.min(b) }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_pi() -> f64 { std::f64::consts::PI }
+
+/// Run `f` with the program's one random stream.
+pub(super) fn with_rng<R>(f: impl FnOnce(&mut Rng) -> R) -> R {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_seed(seed: i64) {
+    with_rng(|rng| *rng = Rng::new(seed as u64));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_random() -> f64 {
+    with_rng(|rng| rng.next_f64())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_random_range(min: i64, max: i64) -> i64 {
+    with_rng(|rng| rng.range(min, max))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_random_float(min: f64, max: f64) -> f64 {
+    with_rng(|rng| rng.range_f64(min, max))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_math_chance(p: f64) -> i64 {
+    with_rng(|rng| rng.chance(p)) as i64
+}
+
+/// Shuffle `len` elements of `elem_size` bytes in place (array literals).
+///
+/// # Safety
+/// `ptr` must point to `len * elem_size` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn runtime_math_shuffle(ptr: *mut u8, len: i64, elem_size: i64) {
+    if ptr.is_null() || len < 2 || elem_size <= 0 {
+        return;
+    }
+    let (len, size) = (len as usize, elem_size as usize);
+    let bytes = unsafe { std::slice::from_raw_parts_mut(ptr, len * size) };
+    let mut order: Vec<usize> = (0..len).collect();
+    with_rng(|rng| rng.shuffle(&mut order));
+    let original = bytes.to_vec();
+    for (dst, src) in order.into_iter().enumerate() {
+        bytes[dst * size..(dst + 1) * size].copy_from_slice(&original[src * size..(src + 1) * size]);
+    }
+}
+
+/// Weighted random index into `len` Int weights, or -1 if none is positive.
+///
+/// # Safety
+/// `ptr` must point to `len` readable i64 values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn runtime_math_weighted_int(ptr: *const i64, len: i64) -> i64 {
+    if ptr.is_null() || len <= 0 {
+        return -1;
+    }
+    let weights = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
+    weighted_index(&weights.iter().map(|&w| w as f64).collect::<Vec<_>>())
+}
+
+/// Weighted random index into `len` Float weights, or -1 if none is positive.
+///
+/// # Safety
+/// `ptr` must point to `len` readable f64 values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn runtime_math_weighted_float(ptr: *const f64, len: i64) -> i64 {
+    if ptr.is_null() || len <= 0 {
+        return -1;
+    }
+    weighted_index(unsafe { std::slice::from_raw_parts(ptr, len as usize) })
+}
+
+pub(super) fn weighted_index(weights: &[f64]) -> i64 {
+    with_rng(|rng| rng.weighted(weights)).map_or(-1, |i| i as i64)
+}
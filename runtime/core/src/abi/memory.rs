@@ -0,0 +1,25 @@
+use super::*;
+use std::collections::HashMap;
+
+thread_local! {
+    static MEMORY_STORE: RefCell<HashMap<String, i64>> = RefCell::new(HashMap::new());
+}
+
+// ─── Memory namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_memory_set(key: *const std::ffi::c_char, val: i64) {
+    if let Some(s) = unsafe { read_cstr(key) } {
+        MEMORY_STORE.with(|m| {
+            m.borrow_mut().insert(s.to_string(), val);
+        });
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_memory_get(key: *const std::ffi::c_char) -> i64 {
+    match unsafe { read_cstr(key) } {
+        Some(s) => MEMORY_STORE.with(|m| *m.borrow().get(s).unwrap_or(&0)),
+        None => 0,
+    }
+}
@@ -0,0 +1,149 @@
+//! The `extern "C"` functions compiled programs call, one file per namespace.
+//!
+//! All runtime state is thread-local, like the program's single thread of
+//! execution. The backend's [`Platform`] is created on first use.
+
+use crate::platform::{InputState, Platform};
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, CString};
+
+mod arrays;
+mod camera;
+mod grids;
+mod groups;
+mod input;
+mod io;
+mod layers;
+mod legacy;
+mod math;
+mod memory;
+mod objects;
+mod queries;
+mod screen;
+mod sound;
+mod strings;
+mod system;
+mod text;
+mod tweens;
+
+unsafe extern "Rust" {
+    /// Defined by the backend crate the program is linked against.
+    fn gbasic_create_platform() -> Box<dyn Platform>;
+}
+
+thread_local! {
+    static PLATFORM: RefCell<Option<Box<dyn Platform>>> = const { RefCell::new(None) };
+    static SCREEN_SIZE: Cell<Option<(i64, i64)>> = const { Cell::new(None) };
+    static INPUT: RefCell<InputState> = RefCell::new(InputState::default());
+    /// Source line and column of the operation the program is running, for
+    /// runtime errors; (0, 0) when unknown.
+    static LOCATION: Cell<(i64, i64)> = const { Cell::new((0, 0)) };
+}
+
+/// Record where in the source the next runtime call comes from.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_location(line: i64, column: i64) {
+    LOCATION.with(|l| l.set((line, column)));
+}
+
+// ─── DRY helpers ───
+
+/// Read a C string pointer into a &str, returning None if null or invalid UTF-8.
+unsafe fn read_cstr<'a>(ptr: *const std::ffi::c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// Hand a string to the program as a C string. Intentionally leaks — no GC yet.
+fn leak_cstr(s: String) -> *const std::ffi::c_char {
+    let c = CString::new(s).unwrap_or_default();
+    c.into_raw() as *const _
+}
+
+/// Stop the program with an error in its own terms, pointing at the source
+/// line that caused it.
+fn runtime_error(message: &str) -> ! {
+    match LOCATION.with(|l| l.get()) {
+        (0, _) => eprintln!("runtime error: {message}"),
+        (line, column) => eprintln!("runtime error at line {line}, column {column}: {message}"),
+    }
+    with_platform(|p| p.exit(1))
+}
+
+/// Access the backend, creating it on first use.
+fn with_platform<R>(f: impl FnOnce(&mut dyn Platform) -> R) -> R {
+    PLATFORM.with(|p| {
+        let mut p = p.borrow_mut();
+        let platform = p.get_or_insert_with(|| unsafe { gbasic_create_platform() });
+        f(platform.as_mut())
+    })
+}
+
+/// Size of the open screen, or None before `Screen.Init`.
+fn screen_size() -> Option<(i64, i64)> {
+    SCREEN_SIZE.with(|s| s.get())
+}
+
+/// Screen size for layout and physics, assuming the default window if none is open.
+fn screen_size_or_default() -> (f64, f64) {
+    screen_size()
+        .map(|(w, h)| (w as f64, h as f64))
+        .unwrap_or((800.0, 600.0))
+}
+
+/// Draw on the screen; does nothing until one is open.
+fn with_renderer(f: impl FnOnce(&mut dyn Platform)) {
+    if screen_size().is_some() {
+        with_platform(f);
+    }
+}
+
+/// What an array or grid handle is for, kept in its top byte so one handed
+/// where the other is expected is caught instead of read from the wrong store.
+#[derive(Debug, Clone, Copy)]
+enum HandleKind {
+    Array = 0x41,
+    Grid = 0x47,
+}
+
+impl HandleKind {
+    fn handle(self, index: usize) -> i64 {
+        (self as i64) << 56 | index as i64
+    }
+
+    /// The index a handle of this kind stands for, or None for anything else.
+    fn index(self, handle: i64) -> Option<usize> {
+        (handle >> 56 == self as i64).then_some((handle & ((1 << 56) - 1)) as usize)
+    }
+}
+
+/// A function of the program's to call later, as `fun done()`, or as
+/// `fun done(obj)` to be handed the object it's about.
+#[derive(Debug, Clone, Copy)]
+struct Callback {
+    f: *const std::ffi::c_void,
+    takes_object: bool,
+}
+
+impl Callback {
+    /// None for a null function pointer.
+    fn new(f: *const std::ffi::c_void, takes_object: i64) -> Option<Self> {
+        (!f.is_null()).then_some(Self { f, takes_object: takes_object != 0 })
+    }
+
+    /// Call it; nothing of the runtime's may be borrowed, as the program
+    /// can call back into it.
+    fn call(self, object: i64) {
+        unsafe {
+            if self.takes_object {
+                let f: extern "C" fn(i64) = std::mem::transmute(self.f);
+                f(object);
+            } else {
+                let f: extern "C" fn() = std::mem::transmute(self.f);
+                f();
+            }
+        }
+    }
+}
@@ -0,0 +1,1316 @@
+use super::*;
+use super::arrays::array_values;
+use super::camera::camera_view;
+use super::layers::{composite, offscreen_layers};
+use super::math::with_rng;
+use super::screen::ensure_screen_init;
+use super::text::load_font;
+use crate::animation::{Animation, SpriteSheet};
+use crate::camera::View;
+use crate::collision::Shape;
+use crate::group;
+use crate::object::{CollisionShape, GameObject, ObjectKind};
+use crate::particles::Emitter;
+use crate::physics;
+use crate::platform::{Color, SpriteDraw};
+use crate::store::ObjectStore;
+use crate::text::Text;
+use crate::tilemap::Tilemap;
+use crate::ttf::Align;
+use std::collections::HashMap;
+
+thread_local! {
+    static OBJECTS: RefCell<ObjectStore> = const { RefCell::new(ObjectStore::new()) };
+    /// `on_removed` functions, by the handle of the object they watch.
+    static ON_REMOVED: RefCell<HashMap<i64, Callback>> = RefCell::new(HashMap::new());
+}
+
+fn with_object_mut(handle: i64, f: impl FnOnce(&mut GameObject)) {
+    OBJECTS.with(|objs| match objs.borrow_mut().get_mut(handle) {
+        Ok(obj) => f(obj),
+        Err(e) => runtime_error(&e.to_string()),
+    });
+}
+
+/// Run `f` on a live object, or return None if the handle doesn't name one.
+pub(super) fn with_live_object<R>(handle: i64, f: impl FnOnce(&mut GameObject) -> R) -> Option<R> {
+    OBJECTS.with(|objs| objs.borrow_mut().get_mut(handle).ok().map(f))
+}
+
+/// Run `f` on every object at once.
+pub(super) fn with_objects<R>(f: impl FnOnce(&mut ObjectStore) -> R) -> R {
+    OBJECTS.with(|objs| f(&mut objs.borrow_mut()))
+}
+
+fn with_object<R>(handle: i64, f: impl FnOnce(&GameObject) -> R) -> R {
+    OBJECTS.with(|objs| match objs.borrow().get(handle) {
+        Ok(obj) => f(obj),
+        Err(e) => runtime_error(&e.to_string()),
+    })
+}
+
+fn create(obj: GameObject) -> i64 {
+    ensure_screen_init();
+    OBJECTS.with(|objs| objs.borrow_mut().insert(obj))
+}
+
+// ─── Object constructors ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_rect(w: f64, h: f64) -> i64 {
+    create(GameObject::new(ObjectKind::Rect, w, h))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_circle(r: f64) -> i64 {
+    // Circles store their radius in w
+    create(GameObject::new(ObjectKind::Circle, r, r))
+}
+
+/// `sprite("hero")` also finds hero.png and hero.bmp.
+fn image_candidates(name: &str) -> Vec<String> {
+    let has_extension = std::path::Path::new(name).extension().is_some();
+    if has_extension {
+        vec![name.to_string()]
+    } else {
+        vec![format!("{name}.png"), format!("{name}.bmp")]
+    }
+}
+
+fn load_sprite_image(name: &str) -> usize {
+    ensure_screen_init();
+    let loaded = image_candidates(name).iter().find_map(|path| with_platform(|p| p.load_image(path)));
+    match loaded {
+        Some(image) => image,
+        None => runtime_error(&format!("couldn't load sprite \"{name}\"")),
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_sprite(name: *const std::ffi::c_char) -> i64 {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    let image = load_sprite_image(name);
+    // Images still loading (web) get their size when first drawn
+    let (w, h) = with_platform(|p| p.image_size(image)).unwrap_or((0, 0));
+    create(GameObject::new(ObjectKind::Sprite(image), w as f64, h as f64))
+}
+
+/// A sprite showing one `frame_w` × `frame_h` frame of a sheet image at a time.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_sprite_sheet(name: *const std::ffi::c_char, frame_w: i64, frame_h: i64) -> i64 {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    if frame_w <= 0 || frame_h <= 0 {
+        runtime_error(&format!("sprite_sheet frame size must be positive, got {frame_w}x{frame_h}"));
+    }
+    let image = load_sprite_image(name);
+    let mut obj = GameObject::new(ObjectKind::Sprite(image), frame_w as f64, frame_h as f64);
+    obj.sheet = Some(SpriteSheet { frame_w: frame_w as u32, frame_h: frame_h as u32 });
+    create(obj)
+}
+
+/// A tilemap from a CSV or Tiled `.json` map, drawn with `tile_w` × `tile_h`
+/// tiles cut from the tileset image.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_tilemap(
+    path: *const std::ffi::c_char,
+    tileset: *const std::ffi::c_char,
+    tile_w: i64,
+    tile_h: i64,
+) -> i64 {
+    let path = unsafe { read_cstr(path) }.unwrap_or_default();
+    let tileset = unsafe { read_cstr(tileset) }.unwrap_or_default();
+    if tile_w <= 0 || tile_h <= 0 {
+        runtime_error(&format!("tilemap tile size must be positive, got {tile_w}x{tile_h}"));
+    }
+    let Some(text) = with_platform(|p| p.load_text(path)) else {
+        runtime_error(&format!("couldn't read tilemap \"{path}\""));
+    };
+    let parsed = if path.to_ascii_lowercase().ends_with(".json") {
+        Tilemap::parse_tiled_json(&text)
+    } else {
+        Tilemap::parse_csv(&text)
+    };
+    let map = parsed.unwrap_or_else(|e| runtime_error(&format!("couldn't load tilemap \"{path}\": {e}")));
+    let image = load_sprite_image(tileset);
+    let (w, h) = ((map.columns as i64 * tile_w) as f64, (map.rows as i64 * tile_h) as f64);
+    let mut obj = GameObject::new(ObjectKind::Tilemap(image), w, h);
+    obj.sheet = Some(SpriteSheet { frame_w: tile_w as u32, frame_h: tile_h as u32 });
+    obj.tilemap = Some(Box::new(map));
+    obj.solid = true;
+    create(obj)
+}
+
+/// A text object showing `content` in the built-in font, left-aligned.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_text(content: *const std::ffi::c_char) -> i64 {
+    let content = unsafe { read_cstr(content) }.unwrap_or_default();
+    let text = Text::new(content.to_string(), load_font(""));
+    let (w, h) = text.measure();
+    let mut obj = GameObject::new(ObjectKind::Text, w, h);
+    obj.text = Some(Box::new(text));
+    create(obj)
+}
+
+/// A straight line from (x1, y1) to (x2, y2).
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_line(x1: f64, y1: f64, x2: f64, y2: f64) -> i64 {
+    create(GameObject::with_points(ObjectKind::Line, &[(x1, y1), (x2, y2)]))
+}
+
+/// A closed shape through `count` points, given as x, y pairs; `triangle()`
+/// is one with three.
+///
+/// # Safety
+/// `points` must point to `2 * count` readable f64 values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn runtime_create_polygon(points: *const f64, count: i64) -> i64 {
+    if points.is_null() || count < 3 {
+        runtime_error(&format!("a polygon needs at least 3 points, got {}", count.max(0)));
+    }
+    let coords = unsafe { std::slice::from_raw_parts(points, 2 * count as usize) };
+    let points: Vec<_> = coords.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+    create(GameObject::with_points(ObjectKind::Polygon, &points))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_ellipse(w: f64, h: f64) -> i64 {
+    create(GameObject::new(ObjectKind::Ellipse, w, h))
+}
+
+/// Part of a circle of radius `r`, from `start` to `end` degrees clockwise
+/// from pointing right.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_arc(r: f64, start: f64, end: f64) -> i64 {
+    let mut obj = GameObject::new(ObjectKind::Arc, r, r);
+    (obj.arc_start, obj.arc_end) = (start, end);
+    create(obj)
+}
+
+/// A particle emitter with the default settings, streaming white dots.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_create_emitter() -> i64 {
+    let mut obj = GameObject::new(ObjectKind::Emitter, 0.0, 0.0);
+    obj.emitter = Some(Box::default());
+    create(obj)
+}
+
+// ─── Property setters ───
+
+/// How far right of its left edge an object's `position.x` is: text is
+/// placed by its left edge, center or right edge, as it's aligned.
+pub(super) fn anchor_offset(o: &GameObject) -> f64 {
+    o.text.as_ref().map_or(0.0, |t| o.scaled_size().0 * t.align.factor())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_position(handle: i64, x: f64, y: f64) {
+    with_object_mut(handle, |o| { o.x = x - anchor_offset(o); o.y = y; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_position_x(handle: i64, x: f64) {
+    with_object_mut(handle, |o| { o.x = x - anchor_offset(o); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_position_y(handle: i64, y: f64) {
+    with_object_mut(handle, |o| { o.y = y; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_color(handle: i64, r: i64, g: i64, b: i64, a: i64) {
+    with_object_mut(handle, |o| { o.color = Color::from_rgba_i64(r, g, b, a); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_visible(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.visible = v != 0; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_velocity(handle: i64, vx: f64, vy: f64) {
+    with_object_mut(handle, |o| { o.vx = vx; o.vy = vy; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_velocity_x(handle: i64, vx: f64) {
+    with_object_mut(handle, |o| { o.vx = vx; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_velocity_y(handle: i64, vy: f64) {
+    with_object_mut(handle, |o| { o.vy = vy; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_gravity(handle: i64, g: f64) {
+    with_object_mut(handle, |o| { o.gravity = g; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_solid(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.solid = v != 0; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_bounces(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.bounces = v != 0; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_bounciness(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.bounciness = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_friction(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.friction = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_mass(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.mass = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_collision_shape(handle: i64, name: *const std::ffi::c_char) {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    let Some(shape) = CollisionShape::parse(name) else {
+        runtime_error(&format!("unknown collision_shape \"{name}\"; use \"auto\", \"box\" or \"circle\""));
+    };
+    with_object_mut(handle, |o| { o.collision_shape = shape; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_rotation(handle: i64, degrees: f64) {
+    with_object_mut(handle, |o| { o.rotation = degrees; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_scale(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.scale_x = v.max(0.0); o.scale_y = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_scale_x(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.scale_x = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_scale_y(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.scale_y = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_opacity(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.opacity = v.clamp(0.0, 1.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_ui(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.ui = v != 0; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_flip_x(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.flip_x = v != 0; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_flip_y(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.flip_y = v != 0; });
+}
+
+/// Show a sheet frame, stopping the animation.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_frame(handle: i64, frame: i64) {
+    with_object_mut(handle, |o| o.animation.set_frame(frame.max(0) as usize));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_layer(handle: i64, l: i64) {
+    with_object_mut(handle, |o| { o.layer = l; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_fill(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.fill = v != 0; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_thickness(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.thickness = v.max(0.0); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_wraps(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.wraps = v != 0; });
+}
+
+/// Seconds until the object is removed, 0 for never.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_lifetime(handle: i64, v: f64) {
+    with_object_mut(handle, |o| { o.lifetime = (v > 0.0).then_some(v); });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_remove_offscreen(handle: i64, v: i64) {
+    with_object_mut(handle, |o| { o.remove_offscreen = v != 0; });
+}
+
+fn with_arc_mut(handle: i64, property: &str, f: impl FnOnce(&mut GameObject)) {
+    with_object_mut(handle, |o| {
+        if o.kind != ObjectKind::Arc {
+            runtime_error(&format!(".{property} only works on arc objects"));
+        }
+        f(o);
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_start_angle(handle: i64, degrees: f64) {
+    with_arc_mut(handle, "start_angle", |o| { o.arc_start = degrees; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_end_angle(handle: i64, degrees: f64) {
+    with_arc_mut(handle, "end_angle", |o| { o.arc_end = degrees; });
+}
+
+// ─── Property getters ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_position_x(handle: i64) -> f64 {
+    with_object(handle, |o| o.x + anchor_offset(o))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_position_y(handle: i64) -> f64 {
+    with_object(handle, |o| o.y)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_velocity_x(handle: i64) -> f64 {
+    with_object(handle, |o| o.vx)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_velocity_y(handle: i64) -> f64 {
+    with_object(handle, |o| o.vy)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_gravity(handle: i64) -> f64 {
+    with_object(handle, |o| o.gravity)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_bounciness(handle: i64) -> f64 {
+    with_object(handle, |o| o.bounciness)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_friction(handle: i64) -> f64 {
+    with_object(handle, |o| o.friction)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_mass(handle: i64) -> f64 {
+    with_object(handle, |o| o.mass)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_on_ground(handle: i64) -> i64 {
+    with_object(handle, |o| o.on_ground as i64)
+}
+
+/// False once the object is removed; never an error.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_alive(handle: i64) -> i64 {
+    OBJECTS.with(|objs| objs.borrow().is_alive(handle) as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_rotation(handle: i64) -> f64 {
+    with_object(handle, |o| o.rotation)
+}
+
+/// Reading `scale` after setting `scale_x` and `scale_y` apart gives `scale_x`.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_scale(handle: i64) -> f64 {
+    with_object(handle, |o| o.scale_x)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_scale_x(handle: i64) -> f64 {
+    with_object(handle, |o| o.scale_x)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_scale_y(handle: i64) -> f64 {
+    with_object(handle, |o| o.scale_y)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_opacity(handle: i64) -> f64 {
+    with_object(handle, |o| o.opacity)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_ui(handle: i64) -> i64 {
+    with_object(handle, |o| o.ui as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_flip_x(handle: i64) -> i64 {
+    with_object(handle, |o| o.flip_x as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_flip_y(handle: i64) -> i64 {
+    with_object(handle, |o| o.flip_y as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_frame(handle: i64) -> i64 {
+    with_object(handle, |o| o.animation.frame() as i64)
+}
+
+/// Name of the animation playing, or "".
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_animation(handle: i64) -> *const std::ffi::c_char {
+    leak_cstr(with_object(handle, |o| o.animation.current_name().to_string()))
+}
+
+/// True for one frame after a one-shot animation ends.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_animation_finished(handle: i64) -> i64 {
+    with_object(handle, |o| o.animation.finished() as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_columns(handle: i64) -> i64 {
+    with_object(handle, |o| o.tilemap.as_ref().map_or(0, |m| m.columns as i64))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_rows(handle: i64) -> i64 {
+    with_object(handle, |o| o.tilemap.as_ref().map_or(0, |m| m.rows as i64))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_size_width(handle: i64) -> f64 {
+    with_object(handle, |o| o.w)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_size_height(handle: i64) -> f64 {
+    with_object(handle, |o| o.h)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_fill(handle: i64) -> i64 {
+    with_object(handle, |o| o.fill as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_wraps(handle: i64) -> i64 {
+    with_object(handle, |o| o.wraps as i64)
+}
+
+/// Seconds left before the object is removed, 0 for never.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_lifetime(handle: i64) -> f64 {
+    with_object(handle, |o| o.lifetime.unwrap_or(0.0))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_remove_offscreen(handle: i64) -> i64 {
+    with_object(handle, |o| o.remove_offscreen as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_thickness(handle: i64) -> f64 {
+    with_object(handle, |o| o.thickness)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_start_angle(handle: i64) -> f64 {
+    with_object(handle, |o| o.arc_start)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_end_angle(handle: i64) -> f64 {
+    with_object(handle, |o| o.arc_end)
+}
+
+// ─── Object methods ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_move(handle: i64, dx: f64, dy: f64) {
+    with_object_mut(handle, |o| { o.x += dx; o.y += dy; });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_collides(h1: i64, h2: i64) -> i64 {
+    OBJECTS.with(|objs| {
+        let objs = objs.borrow();
+        let (i, j) = match (objs.index(h1), objs.index(h2)) {
+            (Ok(i), Ok(j)) => (i, j),
+            (Err(e), _) | (_, Err(e)) => runtime_error(&e.to_string()),
+        };
+        let (a, b) = (&objs.slots()[i], &objs.slots()[j]);
+        // Physics pushes touching objects apart, so count last frame's contacts too
+        (a.overlaps(b) || a.contacts.contains(&j)) as i64
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_contains(handle: i64, x: f64, y: f64) -> i64 {
+    with_object(handle, |o| o.contains(x, y) as i64)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_remove(handle: i64) {
+    if let Err(e) = with_objects(|objs| objs.index(handle)) {
+        runtime_error(&e.to_string());
+    }
+    remove_objects(&[handle]);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_on_removed(handle: i64, f: *const std::ffi::c_void, takes_object: i64) {
+    with_object(handle, |_| ());
+    ON_REMOVED.with(|c| match Callback::new(f, takes_object) {
+        Some(callback) => c.borrow_mut().insert(handle, callback),
+        None => c.borrow_mut().remove(&handle),
+    });
+}
+
+/// Remove objects along with their children, first calling the `on_removed`
+/// function of each that has one, while it's still there to look at.
+/// Handles that don't name a live object are skipped.
+pub(super) fn remove_objects(handles: &[i64]) {
+    let doomed: Vec<i64> = with_objects(|objs| handles.iter().flat_map(|&h| group::family(objs, h)).collect());
+    for &handle in &doomed {
+        // Taken out first, so removing the object from its own hook doesn't
+        // call it again
+        if let Some(callback) = ON_REMOVED.with(|c| c.borrow_mut().remove(&handle)) {
+            callback.call(handle);
+        }
+    }
+    with_objects(|objs| {
+        for &handle in handles {
+            let _ = group::remove(objs, handle);
+        }
+    });
+}
+
+// ─── Animation ───
+
+fn add_animation(handle: i64, name: *const std::ffi::c_char, frames: &[i64], fps: f64, looping: i64) {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    if frames.is_empty() {
+        runtime_error(&format!("animation \"{name}\" has no frames"));
+    }
+    if let Some(&frame) = frames.iter().find(|&&f| f < 0) {
+        runtime_error(&format!("animation \"{name}\" has a negative frame ({frame})"));
+    }
+    let animation = Animation {
+        name: name.to_string(),
+        frames: frames.iter().map(|&f| f as usize).collect(),
+        fps,
+        looping: looping != 0,
+    };
+    with_object_mut(handle, |o| {
+        if o.sheet.is_none() {
+            runtime_error("only sprite_sheet() objects have animations");
+        }
+        o.animation.add(animation);
+    });
+}
+
+/// `hero.animations.add("run", [0, 1, 2, 3], fps: 10)` with a `[..]` literal.
+///
+/// # Safety
+/// `frames` must point to `len` readable i64 values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn runtime_object_add_animation(
+    handle: i64,
+    name: *const std::ffi::c_char,
+    frames: *const i64,
+    len: i64,
+    fps: f64,
+    looping: i64,
+) {
+    let frames = if frames.is_null() || len <= 0 { &[] } else { unsafe { std::slice::from_raw_parts(frames, len as usize) } };
+    add_animation(handle, name, frames, fps, looping);
+}
+
+/// Like [`runtime_object_add_animation`], with the frames in a dynamic array.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_add_animation_list(
+    handle: i64,
+    name: *const std::ffi::c_char,
+    frames: i64,
+    fps: f64,
+    looping: i64,
+) {
+    add_animation(handle, name, &array_values(frames).unwrap_or_default(), fps, looping);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_play(handle: i64, name: *const std::ffi::c_char) {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    with_object_mut(handle, |o| {
+        if !o.animation.play(name) {
+            runtime_error(&format!("no animation named \"{name}\""));
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_stop(handle: i64) {
+    with_object_mut(handle, |o| o.animation.stop());
+}
+
+/// Center of a live object, or None if the handle doesn't name one.
+pub(super) fn object_center(handle: i64) -> Option<(f64, f64)> {
+    OBJECTS.with(|objs| objs.borrow().get(handle).ok().map(GameObject::center))
+}
+
+/// Move every animation `dt` seconds forward.
+pub(super) fn advance_animations(dt: f64) {
+    OBJECTS.with(|objs| {
+        for o in objs.borrow_mut().slots_mut().iter_mut().filter(|o| o.alive && o.sheet.is_some()) {
+            o.animation.advance(dt);
+        }
+    });
+}
+
+// ─── Tilemaps ───
+
+fn with_tilemap_mut<R>(handle: i64, method: &str, f: impl FnOnce(&mut GameObject) -> R) -> R {
+    OBJECTS.with(|objs| match objs.borrow_mut().get_mut(handle) {
+        Ok(obj) if obj.tilemap.is_some() => f(obj),
+        Ok(_) => runtime_error(&format!(".{method}() only works on tilemaps")),
+        Err(e) => runtime_error(&e.to_string()),
+    })
+}
+
+/// Tile under a screen position, or -1 for none.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tilemap_tile_at(handle: i64, x: f64, y: f64) -> i64 {
+    with_tilemap_mut(handle, "tile_at", |o| match (o.cell_at(x, y), &o.tilemap) {
+        (Some((col, row)), Some(map)) => map.tile(col, row),
+        _ => -1,
+    })
+}
+
+/// Put a tile (-1 to clear) in the cell under a screen position.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tilemap_set_tile(handle: i64, x: f64, y: f64, tile: i64) {
+    with_tilemap_mut(handle, "set_tile", |o| {
+        if let (Some((col, row)), Some(map)) = (o.cell_at(x, y), &mut o.tilemap) {
+            map.set_tile(col, row, tile);
+        }
+    });
+}
+
+/// Make a tileset tile solid or passable everywhere on the map.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tilemap_set_solid(handle: i64, tile: i64, solid: i64) {
+    with_tilemap_mut(handle, "set_solid", |o| {
+        if let Some(map) = &mut o.tilemap {
+            map.set_solid(tile, solid != 0);
+        }
+    });
+}
+
+// ─── Text ───
+
+fn with_text_mut(handle: i64, property: &str, f: impl FnOnce(&mut Text)) {
+    with_object_mut(handle, |o| {
+        if o.text.is_none() {
+            runtime_error(&format!(".{property} only works on text objects"));
+        }
+        restyle_text(o, f);
+    });
+}
+
+/// Change how a text object looks, keeping its `position` where it was.
+pub(super) fn restyle_text(o: &mut GameObject, f: impl FnOnce(&mut Text)) {
+    let anchor = o.x + anchor_offset(o);
+    let Some(text) = &mut o.text else { return };
+    f(text);
+    text.rendered = None;
+    (o.w, o.h) = text.measure();
+    o.x = anchor - anchor_offset(o);
+}
+
+fn with_text<R>(handle: i64, property: &str, f: impl FnOnce(&Text) -> R) -> R {
+    with_object(handle, |o| match &o.text {
+        Some(text) => f(text),
+        None => runtime_error(&format!(".{property} only works on text objects")),
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_content(handle: i64, content: *const std::ffi::c_char) {
+    let content = unsafe { read_cstr(content) }.unwrap_or_default();
+    with_text_mut(handle, "content", |t| t.content = content.to_string());
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_content(handle: i64) -> *const std::ffi::c_char {
+    leak_cstr(with_text(handle, "content", |t| t.content.clone()))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_text_size(handle: i64, size: f64) {
+    if size <= 0.0 || !size.is_finite() {
+        runtime_error(&format!("text size must be above 0, got {size}"));
+    }
+    with_text_mut(handle, "size", |t| t.size = size);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_text_size(handle: i64) -> f64 {
+    with_text(handle, "size", |t| t.size)
+}
+
+/// Draw with the TrueType font in a file; "" goes back to the built-in font.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_font(handle: i64, path: *const std::ffi::c_char) {
+    let path = unsafe { read_cstr(path) }.unwrap_or_default();
+    with_text(handle, "font", |_| ());
+    let font = load_font(path);
+    with_text_mut(handle, "font", |t| {
+        t.font = font;
+        t.font_path = path.to_string();
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_font(handle: i64) -> *const std::ffi::c_char {
+    leak_cstr(with_text(handle, "font", |t| t.font_path.clone()))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_align(handle: i64, name: *const std::ffi::c_char) {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    let Some(align) = Align::parse(name) else {
+        runtime_error(&format!("unknown align \"{name}\"; use \"left\", \"center\" or \"right\""));
+    };
+    with_text_mut(handle, "align", |t| t.align = align);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_align(handle: i64) -> *const std::ffi::c_char {
+    leak_cstr(with_text(handle, "align", |t| t.align.name().to_string()))
+}
+
+// ─── Emitters ───
+
+fn with_emitter_mut(handle: i64, property: &str, f: impl FnOnce(&mut Emitter)) {
+    with_object_mut(handle, |o| match &mut o.emitter {
+        Some(emitter) => f(emitter),
+        None => runtime_error(&format!(".{property} only works on emitters")),
+    });
+}
+
+fn with_emitter<R>(handle: i64, property: &str, f: impl FnOnce(&Emitter) -> R) -> R {
+    with_object(handle, |o| match &o.emitter {
+        Some(emitter) => f(emitter),
+        None => runtime_error(&format!(".{property} only works on emitters")),
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_rate(handle: i64, v: f64) {
+    if !v.is_finite() {
+        runtime_error(&format!("an emitter's rate can't be {v}"));
+    }
+    with_emitter_mut(handle, "rate", |e| e.rate = v.max(0.0));
+}
+
+/// Seconds each particle lives, apart from the emitter's own `lifetime`.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_particle_lifetime(handle: i64, v: f64) {
+    with_emitter_mut(handle, "particle_lifetime", |e| e.lifetime = v.max(0.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_speed(handle: i64, v: f64) {
+    with_emitter_mut(handle, "speed", |e| e.speed = v);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_speed_spread(handle: i64, v: f64) {
+    with_emitter_mut(handle, "speed_spread", |e| e.speed_spread = v.abs());
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_angle(handle: i64, degrees: f64) {
+    with_emitter_mut(handle, "angle", |e| e.angle = degrees);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_spread(handle: i64, degrees: f64) {
+    with_emitter_mut(handle, "spread", |e| e.spread = degrees.clamp(0.0, 360.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_start_size(handle: i64, v: f64) {
+    with_emitter_mut(handle, "start_size", |e| e.start_size = v.max(0.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_end_size(handle: i64, v: f64) {
+    with_emitter_mut(handle, "end_size", |e| e.end_size = v.max(0.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_set_emitter_end_color(handle: i64, r: i64, g: i64, b: i64, a: i64) {
+    with_emitter_mut(handle, "end_color", |e| e.end_color = Some(Color::from_rgba_i64(r, g, b, a)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_rate(handle: i64) -> f64 {
+    with_emitter(handle, "rate", |e| e.rate)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_particle_lifetime(handle: i64) -> f64 {
+    with_emitter(handle, "particle_lifetime", |e| e.lifetime)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_speed(handle: i64) -> f64 {
+    with_emitter(handle, "speed", |e| e.speed)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_speed_spread(handle: i64) -> f64 {
+    with_emitter(handle, "speed_spread", |e| e.speed_spread)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_angle(handle: i64) -> f64 {
+    with_emitter(handle, "angle", |e| e.angle)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_spread(handle: i64) -> f64 {
+    with_emitter(handle, "spread", |e| e.spread)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_start_size(handle: i64) -> f64 {
+    with_emitter(handle, "start_size", |e| e.start_size)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_end_size(handle: i64) -> f64 {
+    with_emitter(handle, "end_size", |e| e.end_size)
+}
+
+/// Particles alive right now.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_get_emitter_count(handle: i64) -> i64 {
+    with_emitter(handle, "count", |e| e.particles.len() as i64)
+}
+
+/// Emit `n` particles at once, whatever the rate.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_emitter_burst(handle: i64, n: i64) {
+    with_object_mut(handle, |o| {
+        let origin = (o.x, o.y);
+        let Some(emitter) = &mut o.emitter else {
+            runtime_error(".burst() only works on emitters");
+        };
+        with_rng(|rng| emitter.burst(n.max(0) as usize, origin, rng));
+    });
+}
+
+/// Move every emitter's particles `dt` seconds on and emit new ones. Hidden
+/// emitters stand still, as hidden objects do in physics.
+pub(super) fn advance_emitters(dt: f64) {
+    OBJECTS.with(|objs| {
+        for o in objs.borrow_mut().slots_mut().iter_mut().filter(|o| o.alive && o.visible) {
+            let (origin, gravity) = ((o.x, o.y), o.gravity);
+            if let Some(emitter) = &mut o.emitter {
+                with_rng(|rng| emitter.update(dt, origin, gravity, rng));
+            }
+        }
+    });
+}
+
+// ─── Physics step ───
+
+/// Move everything `dt` seconds on, then remove the objects that
+/// `remove_offscreen` and left the screen.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_physics_step(dt: f64) {
+    let screen = screen_size_or_default();
+    let world = camera_view().area(screen);
+    let expired = with_objects(|objs| {
+        physics::step(objs.slots_mut(), dt, screen.0, screen.1);
+        let due = physics::offscreen(objs.slots_mut(), world, screen);
+        due.into_iter().filter_map(|i| objs.handle_of(i)).collect::<Vec<_>>()
+    });
+    if !expired.is_empty() {
+        remove_objects(&expired);
+    }
+}
+
+/// Count every `lifetime` down by `dt` seconds of frame time, once a frame
+/// whatever the physics did, and remove the objects whose lifetime ran out.
+pub(super) fn advance_lifetimes(dt: f64) {
+    let expired = with_objects(|objs| {
+        let due = physics::count_down(objs.slots_mut(), dt);
+        due.into_iter().filter_map(|i| objs.handle_of(i)).collect::<Vec<_>>()
+    });
+    if !expired.is_empty() {
+        remove_objects(&expired);
+    }
+}
+
+/// Start a frame's physics: contacts from the previous frame no longer count,
+/// and slots of objects removed since then can be reused.
+pub(super) fn physics_begin_frame() {
+    OBJECTS.with(|objs| {
+        let mut objs = objs.borrow_mut();
+        physics::clear_contacts(objs.slots_mut());
+        objs.recycle();
+    });
+}
+
+// ─── Auto-draw ───
+
+/// Give sprites whose image finished loading after they were created their size.
+fn size_pending_sprites(objs: &mut [GameObject]) {
+    for o in objs.iter_mut().filter(|o| o.alive && o.w == 0.0) {
+        if let ObjectKind::Sprite(image) = o.kind
+            && let Some((w, h)) = with_platform(|p| p.image_size(image))
+        {
+            o.w = w as f64;
+            o.h = h as f64;
+        }
+    }
+}
+
+/// Render text whose content, look or color changed since it was last drawn.
+fn render_pending_text(objs: &mut [GameObject]) {
+    for o in objs.iter_mut().filter(|o| o.alive && o.visible) {
+        let color = o.color;
+        if let Some(text) = &mut o.text
+            && text.needs_render(color)
+        {
+            let pixels = text.render(color);
+            text.image = Some(with_platform(|p| p.upload_image(text.image, pixels)));
+            text.rendered = Some(color);
+        }
+    }
+}
+
+/// The tiles of a tilemap that fall on a `screen_w` × `screen_h` screen
+/// showing `view`, placed on the screen.
+fn visible_tiles(o: &GameObject, image_w: u32, image_h: u32, view: &View, (screen_w, screen_h): (f64, f64)) -> Vec<SpriteDraw> {
+    let (Some(map), Some(sheet)) = (&o.tilemap, o.sheet) else { return Vec::new() };
+    let (cw, ch) = o.cell_size();
+    let ((x1, y1), (x2, y2)) = (view.to_world(0.0, 0.0), view.to_world(screen_w, screen_h));
+    let (Some((c1, r1)), Some((c2, r2))) = (o.cell_at(x1, y1), o.cell_at(x2, y2)) else {
+        return Vec::new();
+    };
+    let mut tiles = Vec::new();
+    for row in r1.max(0)..=r2.min(map.rows as i64 - 1) {
+        for col in c1.max(0)..=c2.min(map.columns as i64 - 1) {
+            let tile = map.tile(col, row);
+            if tile < 0 {
+                continue;
+            }
+            let (x, y) = view.to_screen(o.x + (col as f64 + 0.5) * cw, o.y + (row as f64 + 0.5) * ch);
+            tiles.push(SpriteDraw {
+                x,
+                y,
+                w: cw * view.zoom,
+                h: ch * view.zoom,
+                rotation: 0.0,
+                opacity: o.opacity,
+                flip_x: false,
+                flip_y: false,
+                source: Some(sheet.frame_rect(tile as usize, image_w, image_h)),
+            });
+        }
+    }
+    tiles
+}
+
+/// Draw an object as `view` shows it on a screen of size `screen`.
+pub(super) fn draw_object(p: &mut dyn Platform, o: &GameObject, view: &View, screen: (f64, f64)) {
+    let (w, h) = o.scaled_size();
+    let (x, y) = view.to_screen(o.x, o.y);
+    let (w, h) = (w * view.zoom, h * view.zoom);
+    let color = o.color.faded(o.opacity);
+    match o.kind {
+        ObjectKind::Rect | ObjectKind::Circle if !o.fill => draw_shape(p, o, view),
+        ObjectKind::Rect if o.rotation == 0.0 => {
+            p.fill_rect(x as i64, y as i64, w as i64, h as i64, color);
+        }
+        ObjectKind::Rect => {
+            let Shape::Box { x, y, hw, hh, angle } = o.outline() else { return };
+            let (sin, cos) = angle.sin_cos();
+            let corners = [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+                .map(|(cx, cy)| view.to_screen(x + cx * cos - cy * sin, y + cx * sin + cy * cos));
+            p.fill_polygon(&corners, color);
+        }
+        ObjectKind::Circle => {
+            p.fill_circle(x as i64, y as i64, w as i64, color);
+        }
+        ObjectKind::Sprite(image) => {
+            let source = match (o.sheet, p.image_size(image)) {
+                (Some(sheet), Some((image_w, image_h))) => Some(sheet.frame_rect(o.animation.frame(), image_w, image_h)),
+                (Some(_), None) => return,
+                (None, _) => None,
+            };
+            let sprite = SpriteDraw {
+                x: x + w / 2.0,
+                y: y + h / 2.0,
+                w,
+                h,
+                rotation: o.rotation,
+                opacity: o.opacity,
+                flip_x: o.flip_x,
+                flip_y: o.flip_y,
+                source,
+            };
+            p.draw_sprite(image, &sprite);
+        }
+        ObjectKind::Tilemap(image) => {
+            if let Some((image_w, image_h)) = p.image_size(image) {
+                let tiles = visible_tiles(o, image_w, image_h, view, screen);
+                p.draw_sprites(image, &tiles);
+            }
+        }
+        ObjectKind::Text => {
+            let Some(image) = o.text.as_ref().and_then(|t| t.image) else { return };
+            let sprite = SpriteDraw {
+                x: x + w / 2.0,
+                y: y + h / 2.0,
+                w,
+                h,
+                rotation: o.rotation,
+                opacity: o.opacity,
+                flip_x: o.flip_x,
+                flip_y: o.flip_y,
+                source: None,
+            };
+            p.draw_sprite(image, &sprite);
+        }
+        ObjectKind::Line | ObjectKind::Polygon | ObjectKind::Ellipse | ObjectKind::Arc => draw_shape(p, o, view),
+        ObjectKind::Emitter => draw_particles(p, o, view),
+    }
+}
+
+/// An emitter's particles, oldest first, each a dot sized and colored for
+/// how far through its life it is.
+fn draw_particles(p: &mut dyn Platform, o: &GameObject, view: &View) {
+    let Some(emitter) = &o.emitter else { return };
+    for particle in &emitter.particles {
+        let radius = emitter.size(particle) * view.zoom / 2.0;
+        let color = emitter.color(particle, o.color).faded(o.opacity);
+        if radius < 0.25 || color.a == 0 {
+            continue;
+        }
+        let (x, y) = view.to_screen(particle.x, particle.y);
+        p.fill_circle(x.round() as i64, y.round() as i64, radius.round() as i64, color);
+    }
+}
+
+/// A shape drawn from its corners: filled, or outlined `thickness` wide.
+fn draw_shape(p: &mut dyn Platform, o: &GameObject, view: &View) {
+    let corners: Vec<_> = o.corners().into_iter().map(|(x, y)| view.to_screen(x, y)).collect();
+    let color = o.color.faded(o.opacity);
+    if o.fill && !o.is_open() {
+        p.fill_polygon(&corners, color);
+    } else {
+        p.stroke_polygon(&corners, !o.is_open(), o.thickness * view.zoom, color);
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_auto_draw() {
+    OBJECTS.with(|objs| {
+        let mut store = objs.borrow_mut();
+        size_pending_sprites(store.slots_mut());
+        render_pending_text(store.slots_mut());
+        let objs = store.slots();
+        // The world through the camera, then the UI on top; each by layer,
+        // then creation order. A layer with its own surface is drawn all at
+        // once, UI and all, in the world's place for its number.
+        let offscreen = offscreen_layers();
+        let mut indices: Vec<usize> = (0..objs.len())
+            .filter(|&i| objs[i].alive && objs[i].visible)
+            .collect();
+        indices.sort_by_key(|&i| (objs[i].ui, objs[i].layer, store.serial(i)));
+        let mut steps: Vec<(bool, i64, Option<usize>)> = indices
+            .iter()
+            .filter(|&&i| !offscreen.contains_key(&objs[i].layer))
+            .map(|&i| (objs[i].ui, objs[i].layer, Some(i)))
+            .collect();
+        // Stable, so objects keep their order around the layers slotted in
+        steps.extend(offscreen.keys().map(|&n| (false, n, None)));
+        steps.sort_by_key(|&(ui, n, _)| (ui, n));
+
+        let screen = screen_size_or_default();
+        let view = camera_view();
+        with_renderer(|p| {
+            for (_, n, object) in steps {
+                let Some(i) = object else {
+                    let on_layer: Vec<&GameObject> =
+                        indices.iter().filter(|&&i| objs[i].layer == n).map(|&i| &objs[i]).collect();
+                    composite(p, &offscreen[&n], &on_layer, &view, screen);
+                    continue;
+                };
+                let view = if objs[i].ui { &View::SCREEN } else { &view };
+                draw_object(p, &objs[i], view, screen);
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::text::runtime_measure_text;
+
+    #[test]
+    fn test_removed_object_is_not_alive_and_its_slot_is_reused() {
+        let a = runtime_create_rect(10.0, 10.0);
+        let b = runtime_create_circle(5.0);
+        runtime_set_position(a, 3.0, 4.0);
+        runtime_set_position(b, 8.0, 8.0);
+        assert_eq!(runtime_get_position_x(a), 3.0);
+        assert_eq!(runtime_object_collides(a, b), 1);
+        runtime_object_remove(b);
+        assert_eq!(runtime_get_alive(b), 0);
+        assert_eq!(runtime_get_alive(a), 1);
+
+        physics_begin_frame();
+        let c = runtime_create_rect(1.0, 1.0);
+        assert_ne!(c, b);
+        assert_eq!(c & 0xFFFF_FFFF, b & 0xFFFF_FFFF);
+        assert_eq!(runtime_get_alive(b), 0);
+        assert_eq!(runtime_get_size_width(c), 1.0);
+    }
+
+    #[test]
+    fn test_sheet_animation_advances_with_frames() {
+        let mut obj = GameObject::new(ObjectKind::Sprite(0), 16.0, 16.0);
+        obj.sheet = Some(SpriteSheet { frame_w: 16, frame_h: 16 });
+        let hero = create(obj);
+        let name = std::ffi::CString::new("wave").unwrap();
+        let frames = [3, 4];
+        unsafe { runtime_object_add_animation(hero, name.as_ptr(), frames.as_ptr(), 2, 10.0, 0) };
+        runtime_object_play(hero, name.as_ptr());
+        assert_eq!(runtime_get_frame(hero), 3);
+        advance_animations(0.1);
+        assert_eq!(runtime_get_frame(hero), 4);
+        advance_animations(0.1);
+        assert_eq!(runtime_get_animation_finished(hero), 1);
+        advance_animations(0.1);
+        assert_eq!(runtime_get_animation_finished(hero), 0);
+        runtime_set_frame(hero, 1);
+        assert_eq!(runtime_get_frame(hero), 1);
+    }
+
+    #[test]
+    fn test_aligned_text_keeps_its_position_when_it_changes() {
+        let cstr = |s: &str| std::ffi::CString::new(s).unwrap();
+        let label = runtime_create_text(cstr("Hi").as_ptr());
+        let width = runtime_get_size_width(label);
+        assert_eq!(width, runtime_measure_text(cstr("Hi").as_ptr(), 20.0));
+
+        runtime_set_align(label, cstr("center").as_ptr());
+        runtime_set_position(label, 400.0, 10.0);
+        assert_eq!(with_object(label, |o| o.x), 400.0 - width / 2.0);
+
+        runtime_set_content(label, cstr("Hello there").as_ptr());
+        runtime_set_text_size(label, 40.0);
+        let wider = runtime_get_size_width(label);
+        assert!(wider > width * 4.0);
+        assert_eq!(runtime_get_position_x(label), 400.0);
+        assert_eq!(with_object(label, |o| o.x), 400.0 - wider / 2.0);
+
+        runtime_set_align(label, cstr("right").as_ptr());
+        assert_eq!(with_object(label, |o| o.x + o.w), 400.0);
+    }
+
+    #[test]
+    fn test_emitter_gravity_pulls_its_particles_not_itself() {
+        let sparks = runtime_create_emitter();
+        runtime_set_position(sparks, 100.0, 100.0);
+        runtime_set_gravity(sparks, 200.0);
+        runtime_set_emitter_rate(sparks, 0.0);
+        runtime_set_emitter_speed(sparks, 0.0);
+        runtime_emitter_burst(sparks, 5);
+        assert_eq!(runtime_get_emitter_count(sparks), 5);
+
+        runtime_physics_step(0.5);
+        advance_emitters(0.5);
+        assert_eq!((runtime_get_position_y(sparks), runtime_get_velocity_y(sparks)), (100.0, 0.0));
+        assert!(with_emitter(sparks, "count", |e| e.particles.iter().all(|p| p.y == 150.0)));
+
+        runtime_set_emitter_rate(sparks, 10.0);
+        advance_emitters(0.6);
+        // The burst has lived out its second; the stream has begun
+        assert_eq!(runtime_get_emitter_count(sparks), 6);
+    }
+
+    thread_local! {
+        static GONE: RefCell<Vec<(i64, f64)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "C" fn note_removal(object: i64) {
+        // Still there to look at, and removing it again is harmless
+        GONE.with(|g| g.borrow_mut().push((object, runtime_get_position_x(object))));
+        runtime_object_remove(object);
+    }
+
+    #[test]
+    fn test_lifetime_and_offscreen_remove_with_on_removed() {
+        let hook = note_removal as *const std::ffi::c_void;
+        let shot = runtime_create_rect(4.0, 4.0);
+        runtime_set_position(shot, 100.0, 100.0);
+        runtime_set_lifetime(shot, 0.5);
+        runtime_set_on_removed(shot, hook, 1);
+        let bullet = runtime_create_rect(4.0, 4.0);
+        runtime_set_position(bullet, 200.0, 10.0);
+        runtime_set_velocity(bullet, 0.0, -40.0);
+        runtime_set_remove_offscreen(bullet, 1);
+        let trail = runtime_create_rect(2.0, 2.0);
+        crate::abi::groups::runtime_set_parent(trail, bullet);
+        runtime_set_on_removed(trail, hook, 1);
+
+        runtime_physics_step(0.25);
+        advance_lifetimes(0.25);
+        assert!(runtime_get_alive(shot) == 1 && runtime_get_alive(bullet) == 1);
+        assert_eq!(runtime_get_lifetime(shot), 0.25);
+        runtime_physics_step(0.25);
+        advance_lifetimes(0.25);
+        assert_eq!(runtime_get_alive(shot), 0);
+        assert_eq!(runtime_get_alive(bullet), 0);
+        assert_eq!(runtime_get_alive(trail), 0);
+        assert_eq!(GONE.with(|g| g.borrow().clone()), vec![(trail, 0.0), (shot, 100.0)]);
+
+        // An emitter's lifetime is its own, like any object's; its particles'
+        // is separate
+        let sparks = runtime_create_emitter();
+        runtime_set_emitter_particle_lifetime(sparks, 2.0);
+        runtime_set_lifetime(sparks, 1.0);
+        assert_eq!((runtime_get_emitter_particle_lifetime(sparks), runtime_get_lifetime(sparks)), (2.0, 1.0));
+        advance_lifetimes(1.0);
+        assert_eq!(runtime_get_alive(sparks), 0);
+    }
+}
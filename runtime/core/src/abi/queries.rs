@@ -0,0 +1,118 @@
+use super::*;
+use super::arrays::site_array;
+use super::objects::with_objects;
+use crate::query;
+use crate::store::{HandleError, ObjectStore};
+
+thread_local! {
+    /// Where the last raycast stopped, as (x, y, distance): the hit, or the
+    /// end of the ray when it missed.
+    static HIT: Cell<(f64, f64, f64)> = const { Cell::new((0.0, 0.0, 0.0)) };
+}
+
+fn handles(objs: &ObjectStore, slots: Vec<usize>) -> Vec<i64> {
+    slots.into_iter().filter_map(|i| objs.handle_of(i)).collect()
+}
+
+/// The first object a ray from (x, y) toward (dx, dy) meets within `max`
+/// pixels, or 0 for none; `Screen.hit` says where it stopped.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_raycast(x: f64, y: f64, dx: f64, dy: f64, max: f64) -> i64 {
+    let hit = with_objects(|objs| query::raycast(objs, (x, y), (dx, dy), max).map(|h| (objs.handle_of(h.index), h)));
+    let (handle, stop) = match hit {
+        Some((handle, h)) => (handle.unwrap_or(0), (h.x, h.y, h.distance)),
+        None => {
+            let length = dx.hypot(dy);
+            let reach = if length > 0.0 { max.max(0.0) / length } else { 0.0 };
+            (0, (x + dx * reach, y + dy * reach, max.max(0.0)))
+        }
+    };
+    HIT.with(|h| h.set(stop));
+    handle
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_hit_x() -> f64 {
+    HIT.with(|h| h.get().0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_hit_y() -> f64 {
+    HIT.with(|h| h.get().1)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_hit_distance() -> f64 {
+    HIT.with(|h| h.get().2)
+}
+
+/// Every object overlapping the `area` object, oldest first, in call site
+/// `site`'s array.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_objects_in(site: i64, area: i64) -> i64 {
+    let found = with_objects(|objs| -> Result<Vec<i64>, HandleError> {
+        let found = query::objects_in(objs, objs.index(area)?);
+        Ok(handles(objs, found))
+    });
+    match found {
+        Ok(found) => site_array(site, found),
+        Err(e) => runtime_error(&e.to_string()),
+    }
+}
+
+/// Every object within `radius` of (x, y), nearest first, in call site
+/// `site`'s array.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_objects_near(site: i64, x: f64, y: f64, radius: f64) -> i64 {
+    let found = with_objects(|objs| {
+        let found = query::objects_near(objs, (x, y), radius);
+        handles(objs, found)
+    });
+    site_array(site, found)
+}
+
+/// Whether nothing solid stands between the two objects' centers.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_can_see(handle: i64, other: i64) -> i64 {
+    let result = with_objects(|objs| -> Result<bool, HandleError> {
+        Ok(query::line_of_sight(objs, objs.index(handle)?, objs.index(other)?))
+    });
+    match result {
+        Ok(seen) => seen as i64,
+        Err(e) => runtime_error(&e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::arrays::{runtime_array_get, runtime_array_length};
+    use crate::abi::objects::{runtime_create_rect, runtime_set_position, runtime_set_solid};
+
+    fn rect_at(x: f64, y: f64) -> i64 {
+        let r = runtime_create_rect(20.0, 20.0);
+        runtime_set_position(r, x, y);
+        r
+    }
+
+    #[test]
+    fn test_raycast_and_area_queries() {
+        let guard = rect_at(0.0, 0.0);
+        let wall = rect_at(100.0, 0.0);
+        let player = rect_at(200.0, 0.0);
+        assert_eq!(runtime_screen_raycast(10.0, 10.0, 1.0, 0.0, 500.0), wall);
+        assert_eq!((runtime_screen_hit_x(), runtime_screen_hit_y(), runtime_screen_hit_distance()), (100.0, 10.0, 90.0));
+        assert_eq!(runtime_screen_raycast(10.0, 10.0, 0.0, 2.0, 50.0), 0);
+        assert_eq!((runtime_screen_hit_x(), runtime_screen_hit_y(), runtime_screen_hit_distance()), (10.0, 60.0, 50.0));
+
+        assert_eq!(runtime_object_can_see(guard, player), 1);
+        runtime_set_solid(wall, 1);
+        assert_eq!(runtime_object_can_see(guard, player), 0);
+
+        let near = runtime_screen_objects_near(1, 205.0, 10.0, 100.0);
+        assert_eq!((runtime_array_length(near), runtime_array_get(near, 0), runtime_array_get(near, 1)), (2, player, wall));
+        let zone = runtime_create_rect(150.0, 30.0);
+        let inside = runtime_screen_objects_in(2, zone);
+        assert_eq!((runtime_array_length(inside), runtime_array_get(inside, 0), runtime_array_get(inside, 1)), (2, guard, wall));
+    }
+}
@@ -0,0 +1,291 @@
+use super::*;
+use super::layers::{on_layer, painting};
+use crate::font;
+use crate::platform::{BlendMode, Color, SpriteDraw};
+
+thread_local! {
+    static SPRITE_HANDLES: RefCell<Vec<SpriteInfo>> = const { RefCell::new(Vec::new()) };
+    static SCREEN_AUTO_INIT: Cell<bool> = const { Cell::new(false) };
+    /// `Screen.blend`, `Screen.thickness` and `Screen.antialias`: how the
+    /// drawing commands below draw.
+    static BLEND: Cell<BlendMode> = const { Cell::new(BlendMode::Normal) };
+    static THICKNESS: Cell<f64> = const { Cell::new(1.0) };
+    static ANTIALIAS: Cell<bool> = const { Cell::new(false) };
+}
+
+struct SpriteInfo {
+    image: usize,
+    x: f64,
+    y: f64,
+    scale: f64,
+}
+
+// ─── Screen namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_init(width: i64, height: i64) {
+    with_platform(|p| p.open_screen(width, height));
+    SCREEN_SIZE.with(|s| s.set(Some((width, height))));
+}
+
+/// Clear the screen to an opaque color, or a layer picked with
+/// `Screen.Layer(n)` to exactly `color` — see-through wipes it.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_clear(r: i64, g: i64, b: i64, a: i64) {
+    let color = if painting() { Color::from_rgba_i64(r, g, b, a) } else { Color::from_i64(r, g, b) };
+    with_renderer(|p| on_layer(p, |p| p.clear(color)));
+}
+
+/// Run a drawing command in the program's blend mode, on the layer picked
+/// with `Screen.Layer(n)` if any, then go back to normal so objects drawn
+/// at the end of the frame aren't affected.
+fn draw(f: impl FnOnce(&mut dyn Platform)) {
+    let blend = BLEND.with(|b| b.get());
+    with_renderer(|p| {
+        on_layer(p, |p| {
+            if blend == BlendMode::Normal {
+                return f(p);
+            }
+            p.set_blend(blend);
+            f(p);
+            p.set_blend(BlendMode::Normal);
+        })
+    });
+}
+
+/// Outline width in whole pixels, for shapes drawn on the pixel grid.
+fn thickness_px() -> i64 {
+    THICKNESS.with(|t| t.get()).round().max(1.0) as i64
+}
+
+/// Middle of pixel (x, y), where smooth shapes are centered.
+fn center(x: i64, y: i64) -> (f64, f64) {
+    (x as f64 + 0.5, y as f64 + 0.5)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_pixel(x: i64, y: i64, r: i64, g: i64, b: i64, a: i64) {
+    draw(|p| p.set_pixel(x, y, Color::from_rgba_i64(r, g, b, a)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_draw_rect(x: i64, y: i64, w: i64, h: i64, r: i64, g: i64, b: i64, a: i64) {
+    draw(|p| p.fill_rect(x, y, w, h, Color::from_rgba_i64(r, g, b, a)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_draw_rect_outline(x: i64, y: i64, w: i64, h: i64, r: i64, g: i64, b: i64, a: i64) {
+    let thickness = thickness_px();
+    draw(|p| p.stroke_rect(x, y, w, h, thickness, Color::from_rgba_i64(r, g, b, a)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_draw_line(x1: i64, y1: i64, x2: i64, y2: i64, r: i64, g: i64, b: i64, a: i64) {
+    let color = Color::from_rgba_i64(r, g, b, a);
+    let thickness = THICKNESS.with(|t| t.get());
+    let antialias = ANTIALIAS.with(|aa| aa.get());
+    if thickness == 1.0 && !antialias {
+        draw(|p| p.draw_line(x1, y1, x2, y2, color));
+    } else {
+        draw(|p| p.draw_thick_line(center(x1, y1), center(x2, y2), thickness, antialias, color));
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_draw_circle(cx: i64, cy: i64, radius: i64, r: i64, g: i64, b: i64, a: i64) {
+    let color = Color::from_rgba_i64(r, g, b, a);
+    if ANTIALIAS.with(|aa| aa.get()) {
+        draw(|p| p.fill_circle_smooth(center(cx, cy), radius as f64 + 0.5, true, color));
+    } else {
+        draw(|p| p.fill_circle(cx, cy, radius, color));
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_draw_circle_outline(cx: i64, cy: i64, radius: i64, r: i64, g: i64, b: i64, a: i64) {
+    let color = Color::from_rgba_i64(r, g, b, a);
+    let thickness = THICKNESS.with(|t| t.get());
+    let antialias = ANTIALIAS.with(|aa| aa.get());
+    draw(|p| p.stroke_circle(center(cx, cy), radius as f64 + 0.5, thickness, antialias, color));
+}
+
+/// Rectangle shading from the first color to the second, toward `direction`:
+/// "down", "up", "right" or "left".
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_draw_gradient(
+    x: i64, y: i64, w: i64, h: i64,
+    r1: i64, g1: i64, b1: i64, a1: i64,
+    r2: i64, g2: i64, b2: i64, a2: i64,
+    direction: *const std::ffi::c_char,
+) {
+    let (from, to) = (Color::from_rgba_i64(r1, g1, b1, a1), Color::from_rgba_i64(r2, g2, b2, a2));
+    let direction = unsafe { read_cstr(direction) }.unwrap_or_default();
+    let (from, to, vertical) = match direction.to_ascii_lowercase().as_str() {
+        "down" => (from, to, true),
+        "up" => (to, from, true),
+        "right" => (from, to, false),
+        "left" => (to, from, false),
+        _ => runtime_error(&format!("unknown gradient direction \"{direction}\"; use \"down\", \"up\", \"right\" or \"left\"")),
+    };
+    draw(|p| p.fill_gradient((x, y, w, h), from, to, vertical));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_blend() -> *const std::ffi::c_char {
+    leak_cstr(BLEND.with(|b| b.get()).name().to_string())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_blend(name: *const std::ffi::c_char) {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    let Some(mode) = BlendMode::parse(name) else {
+        runtime_error(&format!("unknown blend \"{name}\"; use \"normal\", \"add\", \"multiply\" or \"replace\""));
+    };
+    BLEND.with(|b| b.set(mode));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_thickness() -> f64 {
+    THICKNESS.with(|t| t.get())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_thickness(thickness: f64) {
+    THICKNESS.with(|t| t.set(thickness.max(0.0)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_antialias() -> i64 {
+    ANTIALIAS.with(|aa| aa.get()) as i64
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_set_antialias(on: i64) {
+    ANTIALIAS.with(|aa| aa.set(on != 0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_present() {
+    with_renderer(|p| p.present());
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_width() -> i64 {
+    screen_size().map(|(w, _)| w).unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_height() -> i64 {
+    screen_size().map(|(_, h)| h).unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_center_x() -> f64 {
+    screen_size_or_default().0 / 2.0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_center_y() -> f64 {
+    screen_size_or_default().1 / 2.0
+}
+
+/// `clear(color)` shortcut: opens the default window if needed.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_clear_color(r: i64, g: i64, b: i64) {
+    ensure_screen_init();
+    runtime_screen_clear(r, g, b, 255);
+}
+
+// ─── Auto-init ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn ensure_screen_init() {
+    SCREEN_AUTO_INIT.with(|init| {
+        if !init.get() {
+            init.set(true);
+            if screen_size().is_none() {
+                runtime_screen_init(800, 600);
+            }
+        }
+    });
+}
+
+// ─── Sprite functions ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_sprite_load(path: *const std::ffi::c_char) -> i64 {
+    let p = match unsafe { read_cstr(path) } {
+        Some(s) => s,
+        None => return -1,
+    };
+    let Some(image) = with_platform(|pl| pl.load_image(p)) else {
+        return -1;
+    };
+    SPRITE_HANDLES.with(|sprites| {
+        let mut sprites = sprites.borrow_mut();
+        let handle = sprites.len() as i64;
+        sprites.push(SpriteInfo {
+            image,
+            x: 0.0,
+            y: 0.0,
+            scale: 1.0,
+        });
+        handle
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_sprite_at(handle: i64, x: f64, y: f64) -> i64 {
+    SPRITE_HANDLES.with(|sprites| {
+        if let Some(s) = sprites.borrow_mut().get_mut(handle as usize) {
+            s.x = x;
+            s.y = y;
+        }
+    });
+    handle
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_sprite_scale(handle: i64, scale: f64) -> i64 {
+    SPRITE_HANDLES.with(|sprites| {
+        if let Some(s) = sprites.borrow_mut().get_mut(handle as usize) {
+            s.scale = scale;
+        }
+    });
+    handle
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_screen_sprite_draw(handle: i64) {
+    SPRITE_HANDLES.with(|sprites| {
+        if let Some(s) = sprites.borrow().get(handle as usize) {
+            with_renderer(|p| {
+                let Some((w, h)) = p.image_size(s.image) else { return };
+                let (w, h) = (w as f64 * s.scale, h as f64 * s.scale);
+                let sprite = SpriteDraw {
+                    x: s.x + w / 2.0,
+                    y: s.y + h / 2.0,
+                    w,
+                    h,
+                    rotation: 0.0,
+                    opacity: 1.0,
+                    flip_x: false,
+                    flip_y: false,
+                    source: None,
+                };
+                on_layer(p, |p| p.draw_sprite(s.image, &sprite));
+            });
+        }
+    });
+}
+
+// ─── Text drawing (simple bitmap font) ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_draw_text(text: *const std::ffi::c_char, x: i64, y: i64, r: i64, g: i64, b: i64) {
+    let s = match unsafe { read_cstr(text) } {
+        Some(s) => s,
+        None => return,
+    };
+    with_renderer(|p| on_layer(p, |p| font::draw_text(p, s, x, y, Color::from_i64(r, g, b))));
+}
@@ -0,0 +1,30 @@
+use super::*;
+
+// ─── Sound namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_sound_beep(freq: i64, dur: i64) {
+    with_platform(|p| p.beep(freq, dur));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_sound_effect_load(path: *const std::ffi::c_char) -> i64 {
+    match unsafe { read_cstr(path) } {
+        Some(p) if with_platform(|pl| pl.load_effect(p)) => 1,
+        _ => 0,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_sound_effect_play(path: *const std::ffi::c_char) {
+    if let Some(p) = unsafe { read_cstr(path) } {
+        with_platform(|pl| pl.play_effect(p));
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_sound_effect_volume(path: *const std::ffi::c_char, volume: f64) {
+    if let Some(p) = unsafe { read_cstr(path) } {
+        with_platform(|pl| pl.set_effect_volume(p, volume));
+    }
+}
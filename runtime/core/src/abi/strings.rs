@@ -0,0 +1,25 @@
+use super::*;
+
+// ─── String runtime ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_string_concat(
+    a: *const std::ffi::c_char,
+    b: *const std::ffi::c_char,
+) -> *const std::ffi::c_char {
+    let sa = unsafe { read_cstr(a) }.unwrap_or("");
+    let sb = unsafe { read_cstr(b) }.unwrap_or("");
+    leak_cstr(format!("{sa}{sb}"))
+}
+
+// ─── Int/Float to string conversion ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_int_to_str(v: i64) -> *const std::ffi::c_char {
+    leak_cstr(format!("{v}"))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_float_to_str(v: f64) -> *const std::ffi::c_char {
+    leak_cstr(format!("{v}"))
+}
@@ -0,0 +1,256 @@
+use super::*;
+use super::input::{quit_requested, runtime_input_poll};
+use super::camera::update_camera;
+use super::groups::move_children;
+use super::tweens::advance_tweens;
+use super::objects::{
+    advance_animations, advance_emitters, advance_lifetimes, physics_begin_frame, runtime_auto_draw, runtime_physics_step,
+};
+use super::screen::runtime_screen_present;
+use crate::config::RunConfig;
+use std::path::Path;
+
+/// Frame rate used until the program sets `System.fps`.
+const DEFAULT_FPS: i64 = 60;
+
+/// Length of one physics step; physics runs at 60 Hz whatever the frame rate.
+const PHYSICS_STEP: f64 = 1.0 / 60.0;
+
+/// Most time the physics accumulator will try to catch up on after a stall.
+const MAX_CATCH_UP: f64 = 0.25;
+
+thread_local! {
+    static FRAME: RefCell<FrameClock> = const { RefCell::new(FrameClock {
+        start: None, delta: 0.0, waited: false, count: 0,
+        fps: DEFAULT_FPS, accumulator: 0.0, frame_units: false,
+    }) };
+}
+
+struct FrameClock {
+    start: Option<f64>,
+    /// Length of the last frame in seconds, waiting included.
+    delta: f64,
+    /// `System.FrameEnd()` already paced this frame, so the implicit
+    /// frame end of a `while true` loop doesn't wait a second time.
+    waited: bool,
+    /// Frames completed so far.
+    count: u64,
+    /// Target frame rate (`System.fps`).
+    fps: i64,
+    /// Elapsed time not yet consumed by physics steps.
+    accumulator: f64,
+    /// `System.frame_units`: one physics step per frame, with velocity in
+    /// pixels per frame and gravity in pixels per frame².
+    frame_units: bool,
+}
+
+impl FrameClock {
+    fn frame_secs(&self) -> f64 {
+        1.0 / self.fps as f64
+    }
+}
+
+fn frame_start(now: f64) -> f64 {
+    FRAME.with(|f| *f.borrow_mut().start.get_or_insert(now))
+}
+
+/// Wait out the rest of the frame and record how long it took.
+fn pace_frame() {
+    let start = frame_start(with_platform(|p| p.now()));
+    let frame_secs = FRAME.with(|f| f.borrow().frame_secs());
+    with_platform(|p| p.wait_until(start + frame_secs));
+    let now = with_platform(|p| p.now());
+    let delta = if RunConfig::get().deterministic() { frame_secs } else { now - start };
+    let count = FRAME.with(|f| {
+        let mut f = f.borrow_mut();
+        f.start = Some(now);
+        f.delta = delta;
+        f.accumulator = (f.accumulator + delta).min(MAX_CATCH_UP);
+        f.count += 1;
+        f.count
+    });
+    after_frame(count);
+}
+
+/// Run the physics steps owed for the time since the last frame.
+fn step_physics() {
+    physics_begin_frame();
+    let (steps, dt) = FRAME.with(|f| {
+        let mut f = f.borrow_mut();
+        if f.frame_units {
+            f.accumulator = 0.0;
+            return (1, 1.0);
+        }
+        let mut steps = 0;
+        // Tolerate rounding so a 60 FPS frame is exactly one step
+        while f.accumulator >= PHYSICS_STEP - 1e-9 {
+            f.accumulator -= PHYSICS_STEP;
+            steps += 1;
+        }
+        (steps, PHYSICS_STEP)
+    });
+    for _ in 0..steps {
+        runtime_physics_step(dt);
+    }
+}
+
+/// Move the world on by the time since the last frame.
+fn update_world() {
+    // 1. Physics, in fixed steps covering the time since the last frame
+    step_physics();
+    // 2. Lifetimes, tweens and sprite animations, by the length of the last frame
+    let delta = FRAME.with(|f| f.borrow().delta);
+    advance_lifetimes(delta);
+    advance_tweens(delta);
+    advance_animations(delta);
+    // 3. Children, by however far their parents moved
+    move_children();
+    // 4. Particles, from where their emitters ended up
+    advance_emitters(delta);
+    // 5. Camera, after everything it might follow has moved
+    update_camera(delta);
+}
+
+/// The frame in progress, counting from 1.
+pub(super) fn frame_number() -> u64 {
+    FRAME.with(|f| f.borrow().count) + 1
+}
+
+/// Apply the `GBASIC_SCREENSHOT_EVERY` and `GBASIC_FRAMES` run settings.
+fn after_frame(count: u64) {
+    let config = RunConfig::get();
+    if config.captures(count) {
+        save_screenshot(&config.screenshot_dir.join(format!("frame_{count:05}.png")));
+    }
+    if config.max_frames.is_some_and(|n| count >= n) {
+        runtime_system_exit(0);
+    }
+}
+
+fn save_screenshot(path: &Path) {
+    match with_platform(|p| p.snapshot()) {
+        Some(fb) => {
+            if let Err(e) = fb.save_png(path) {
+                eprintln!("[screenshot] failed to write \"{}\": {e}", path.display());
+            }
+        }
+        None => eprintln!("[screenshot] nothing to capture (no screen open)"),
+    }
+}
+
+// ─── System namespace ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_time() -> f64 {
+    with_platform(|p| p.wall_time())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_sleep(ms: i64) {
+    with_platform(|p| p.sleep_ms(ms));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_exit(code: i64) {
+    with_platform(|p| p.exit(code as i32));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_frame_begin() {
+    let now = with_platform(|p| p.now());
+    FRAME.with(|f| {
+        let mut f = f.borrow_mut();
+        f.start = Some(now);
+        f.waited = false;
+    });
+    // Also poll events
+    runtime_input_poll();
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_frame_end() {
+    pace_frame();
+    FRAME.with(|f| f.borrow_mut().waited = true);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_frame_time() -> f64 {
+    FRAME.with(|f| f.borrow().delta)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_fps() -> i64 {
+    FRAME.with(|f| f.borrow().fps)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_set_fps(fps: i64) {
+    FRAME.with(|f| f.borrow_mut().fps = fps.clamp(1, 1000));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_frame_units() -> i64 {
+    FRAME.with(|f| f.borrow().frame_units) as i64
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_set_frame_units(on: i64) {
+    FRAME.with(|f| f.borrow_mut().frame_units = on != 0);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_system_screenshot(path: *const std::ffi::c_char) {
+    if let Some(p) = unsafe { read_cstr(path) } {
+        save_screenshot(Path::new(p));
+    }
+}
+
+// ─── Frame auto (implicit game loop) ───
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_frame_auto() {
+    // 1. Poll input
+    runtime_input_poll();
+    // 2. Check for quit
+    if quit_requested() {
+        runtime_system_exit(0);
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_frame_auto_end() {
+    // 1-5. Everything that moves on its own
+    update_world();
+    // 6. Auto-draw all objects
+    runtime_auto_draw();
+    // 7. Present
+    runtime_screen_present();
+    // 8. Frame timing (System.fps), unless System.FrameEnd() already did it
+    let waited = FRAME.with(|f| std::mem::replace(&mut f.borrow_mut().waited, false));
+    if !waited {
+        pace_frame();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::objects::{runtime_create_rect, runtime_get_alive, runtime_get_lifetime, runtime_set_lifetime};
+
+    #[test]
+    fn test_lifetime_counts_frame_time_in_frame_units() {
+        runtime_system_set_frame_units(1);
+        let shot = runtime_create_rect(4.0, 4.0);
+        runtime_set_lifetime(shot, 0.5);
+        // A physics step counts as a whole frame here, but the lifetime goes
+        // by the frame's length: half a second is four eighth-second frames
+        let mut frames = 0;
+        while runtime_get_alive(shot) == 1 {
+            FRAME.with(|f| f.borrow_mut().delta = 0.125);
+            update_world();
+            frames += 1;
+            assert!(frames <= 4, "lifetime left: {}", runtime_get_lifetime(shot));
+        }
+        assert_eq!(frames, 4);
+    }
+}
@@ -0,0 +1,35 @@
+use super::*;
+use crate::ttf::Font;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+thread_local! {
+    /// Fonts by file, "" being the built-in one; each is read once.
+    static FONTS: RefCell<HashMap<String, Rc<Font>>> = RefCell::new(HashMap::new());
+}
+
+/// The font in `path`, or the built-in font for "".
+pub(super) fn load_font(path: &str) -> Rc<Font> {
+    if let Some(font) = FONTS.with(|f| f.borrow().get(path).cloned()) {
+        return font;
+    }
+    let font = if path.is_empty() {
+        Font::default_font()
+    } else {
+        let Some(data) = with_platform(|p| p.load_data(path)) else {
+            runtime_error(&format!("couldn't read font \"{path}\""));
+        };
+        Font::parse(data).unwrap_or_else(|e| runtime_error(&format!("couldn't load font \"{path}\": {e}")))
+    };
+    let font = Rc::new(font);
+    FONTS.with(|f| f.borrow_mut().insert(path.to_string(), font.clone()));
+    font
+}
+
+/// Width of `content` in the built-in font at `size` pixels, its widest line
+/// if it has several; the same as a `text()` object's `size.width`.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_measure_text(content: *const std::ffi::c_char, size: f64) -> f64 {
+    let content = unsafe { read_cstr(content) }.unwrap_or_default();
+    load_font("").measure(content, size.max(0.0)).0.ceil()
+}
@@ -0,0 +1,424 @@
+use super::*;
+use super::objects::{anchor_offset, restyle_text, with_live_object};
+use crate::object::GameObject;
+use crate::platform::Color;
+use crate::tween::{Ease, Progress, Tween};
+
+/// An object property a tween can move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Property {
+    Position,
+    Velocity,
+    Color,
+    X,
+    Y,
+    VelocityX,
+    VelocityY,
+    Rotation,
+    Scale,
+    ScaleX,
+    ScaleY,
+    Opacity,
+    Thickness,
+    StartAngle,
+    EndAngle,
+    Gravity,
+    TextSize,
+}
+
+impl Property {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "position" => Self::Position,
+            "velocity" => Self::Velocity,
+            "color" => Self::Color,
+            "position.x" | "x" => Self::X,
+            "position.y" | "y" => Self::Y,
+            "velocity.x" => Self::VelocityX,
+            "velocity.y" => Self::VelocityY,
+            "rotation" => Self::Rotation,
+            "scale" => Self::Scale,
+            "scale_x" => Self::ScaleX,
+            "scale_y" => Self::ScaleY,
+            "opacity" => Self::Opacity,
+            "thickness" => Self::Thickness,
+            "start_angle" => Self::StartAngle,
+            "end_angle" => Self::EndAngle,
+            "gravity" => Self::Gravity,
+            "size" => Self::TextSize,
+            _ => return None,
+        })
+    }
+
+    /// Numbers in its value: 2 for points, 4 for colors, else 1.
+    fn parts(self) -> usize {
+        match self {
+            Self::Position | Self::Velocity => 2,
+            Self::Color => 4,
+            _ => 1,
+        }
+    }
+
+    fn read(self, o: &GameObject) -> [f64; 4] {
+        let x = o.x + anchor_offset(o);
+        let c = o.color;
+        let one = |v: f64| [v, 0.0, 0.0, 0.0];
+        match self {
+            Self::Position => [x, o.y, 0.0, 0.0],
+            Self::Velocity => [o.vx, o.vy, 0.0, 0.0],
+            Self::Color => [c.r, c.g, c.b, c.a].map(f64::from),
+            Self::X => one(x),
+            Self::Y => one(o.y),
+            Self::VelocityX => one(o.vx),
+            Self::VelocityY => one(o.vy),
+            Self::Rotation => one(o.rotation),
+            Self::Scale | Self::ScaleX => one(o.scale_x),
+            Self::ScaleY => one(o.scale_y),
+            Self::Opacity => one(o.opacity),
+            Self::Thickness => one(o.thickness),
+            Self::StartAngle => one(o.arc_start),
+            Self::EndAngle => one(o.arc_end),
+            Self::Gravity => one(o.gravity),
+            Self::TextSize => one(o.text.as_ref().map_or(0.0, |t| t.size)),
+        }
+    }
+
+    /// Set the property, within the limits its setter keeps to.
+    fn write(self, o: &mut GameObject, [a, b, c, d]: [f64; 4]) {
+        match self {
+            Self::Position => (o.x, o.y) = (a - anchor_offset(o), b),
+            Self::Velocity => (o.vx, o.vy) = (a, b),
+            Self::Color => o.color = Color::from_rgba_i64(a.round() as i64, b.round() as i64, c.round() as i64, d.round() as i64),
+            Self::X => o.x = a - anchor_offset(o),
+            Self::Y => o.y = a,
+            Self::VelocityX => o.vx = a,
+            Self::VelocityY => o.vy = a,
+            Self::Rotation => o.rotation = a,
+            Self::Scale => (o.scale_x, o.scale_y) = (a.max(0.0), a.max(0.0)),
+            Self::ScaleX => o.scale_x = a.max(0.0),
+            Self::ScaleY => o.scale_y = a.max(0.0),
+            Self::Opacity => o.opacity = a.clamp(0.0, 1.0),
+            Self::Thickness => o.thickness = a.max(0.0),
+            Self::StartAngle => o.arc_start = a,
+            Self::EndAngle => o.arc_end = a,
+            Self::Gravity => o.gravity = a,
+            Self::TextSize => restyle_text(o, |t| t.size = a.max(1.0)),
+        }
+    }
+}
+
+/// A tween under way on an object.
+struct Running {
+    id: i64,
+    object: i64,
+    property: Property,
+    /// Start value, taken when the tween starts moving.
+    from: Option<[f64; 4]>,
+    to: [f64; 4],
+    tween: Tween,
+    /// Tween or group that has to end before this one starts.
+    after: Option<i64>,
+    group: Option<i64>,
+    on_done: Option<Callback>,
+}
+
+/// Tweens that start and end together: none of them starts before the
+/// group's `after` has ended, and the group ends when the last of them does.
+struct Group {
+    id: i64,
+    after: Option<i64>,
+    /// Whether any tween has joined, so an emptied group can be let go.
+    joined: bool,
+}
+
+thread_local! {
+    static TWEENS: RefCell<Vec<Running>> = const { RefCell::new(Vec::new()) };
+    static GROUPS: RefCell<Vec<Group>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<i64> = const { Cell::new(1) };
+}
+
+fn start(handle: i64, property: *const std::ffi::c_char, to: &[f64], duration: f64) -> i64 {
+    let name = unsafe { read_cstr(property) }.unwrap_or_default();
+    let Some(property) = Property::parse(name) else {
+        runtime_error(&format!("can't tween .{name}; try position, color, opacity, rotation or scale"));
+    };
+    if property.parts() != to.len() {
+        let wanted = match property.parts() {
+            2 => "a Point",
+            4 => "a Color",
+            _ => "a number",
+        };
+        runtime_error(&format!("tweening .{name} needs {wanted} to go to"));
+    }
+    let is_text = with_live_object(handle, |o| o.text.is_some()).unwrap_or_else(|| runtime_error("tween on an object that was removed"));
+    if property == Property::TextSize && !is_text {
+        runtime_error(".size only works on text objects");
+    }
+    let id = next_id();
+    let mut target = [0.0; 4];
+    target[..to.len()].copy_from_slice(to);
+    TWEENS.with(|t| {
+        t.borrow_mut().push(Running {
+            id,
+            object: handle,
+            property,
+            from: None,
+            to: target,
+            tween: Tween::new(duration, Ease::Linear),
+            after: None,
+            group: None,
+            on_done: None,
+        })
+    });
+    id
+}
+
+fn next_id() -> i64 {
+    NEXT_ID.with(|n| n.replace(n.get() + 1))
+}
+
+fn with_tween(id: i64, f: impl FnOnce(&mut Running)) {
+    // A tween that has already ended takes no more settings
+    TWEENS.with(|t| {
+        if let Some(running) = t.borrow_mut().iter_mut().find(|r| r.id == id) {
+            f(running);
+        }
+    });
+}
+
+/// `obj.tween(property, to, duration)` for a number property. Returns the
+/// tween's id, for its settings and for `after:`.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_tween(handle: i64, property: *const std::ffi::c_char, to: f64, duration: f64) -> i64 {
+    start(handle, property, &[to], duration)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_tween_point(
+    handle: i64,
+    property: *const std::ffi::c_char,
+    x: f64,
+    y: f64,
+    duration: f64,
+) -> i64 {
+    start(handle, property, &[x, y], duration)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_tween_color(
+    handle: i64,
+    property: *const std::ffi::c_char,
+    r: i64,
+    g: i64,
+    b: i64,
+    a: i64,
+    duration: f64,
+) -> i64 {
+    let c = Color::from_rgba_i64(r, g, b, a);
+    start(handle, property, &[c.r, c.g, c.b, c.a].map(f64::from), duration)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_ease(id: i64, name: *const std::ffi::c_char) {
+    let name = unsafe { read_cstr(name) }.unwrap_or_default();
+    let Some(ease) = Ease::parse(name) else {
+        runtime_error(&format!("unknown ease \"{name}\"; try \"linear\", \"in_quad\", \"out_bounce\" or \"in_out_sine\""));
+    };
+    with_tween(id, |r| r.tween.ease = ease);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_delay(id: i64, seconds: f64) {
+    with_tween(id, |r| r.tween.delay = seconds.max(0.0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_loop(id: i64, looping: i64) {
+    with_tween(id, |r| r.tween.looping = looping != 0);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_yoyo(id: i64, yoyo: i64) {
+    with_tween(id, |r| r.tween.yoyo = yoyo != 0);
+}
+
+/// Hold the tween back until tween `other` has ended.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_after(id: i64, other: i64) {
+    with_tween(id, |r| r.after = Some(other).filter(|&o| o != id));
+}
+
+/// `tween_group()`: a new, empty group of tweens. Its id works with
+/// `after:` like a tween's.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_group() -> i64 {
+    let id = next_id();
+    GROUPS.with(|g| g.borrow_mut().push(Group { id, after: None, joined: false }));
+    id
+}
+
+/// `tween_group(other)`: hold back every tween in the group until tween or
+/// group `other` has ended.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_group_set_after(group: i64, other: i64) {
+    GROUPS.with(|g| {
+        if let Some(g) = g.borrow_mut().iter_mut().find(|g| g.id == group) {
+            g.after = Some(other).filter(|&o| o != group);
+        }
+    });
+}
+
+/// `group: g`: the tween starts and ends with group `g`.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_group(id: i64, group: i64) {
+    let known = GROUPS.with(|g| {
+        g.borrow_mut().iter_mut().find(|g| g.id == group).map(|g| g.joined = true).is_some()
+    });
+    if !known {
+        runtime_error(&format!("{group} is not a tween group; make one with tween_group()"));
+    }
+    with_tween(id, |r| r.group = Some(group));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_tween_set_on_done(id: i64, f: *const std::ffi::c_void, takes_object: i64) {
+    with_tween(id, |r| r.on_done = Callback::new(f, takes_object));
+}
+
+/// `obj.stop_tweens()`: everything moving the object stops where it is.
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_object_stop_tweens(handle: i64) {
+    TWEENS.with(|t| t.borrow_mut().retain(|r| r.object != handle));
+}
+
+/// Move every tween `dt` seconds on, then call back the program for the
+/// ones that ended. Tweens on removed objects are dropped.
+pub(super) fn advance_tweens(dt: f64) {
+    let group_after: Vec<(i64, i64)> =
+        GROUPS.with(|g| g.borrow().iter().filter_map(|g| g.after.map(|a| (g.id, a))).collect());
+    let done = TWEENS.with(|t| {
+        let mut tweens = t.borrow_mut();
+        // Tweens, and groups with a tween in them, that haven't ended
+        let unfinished: Vec<i64> = tweens.iter().flat_map(|r| [Some(r.id), r.group]).flatten().collect();
+        let mut done = Vec::new();
+        tweens.retain_mut(|r| {
+            // Tweens that ended this frame still hold back the ones after
+            // them until the next
+            let group_waits = group_after.iter().any(|&(g, a)| r.group == Some(g) && unfinished.contains(&a));
+            if group_waits || r.after.is_some_and(|a| unfinished.contains(&a)) {
+                return true;
+            }
+            let progress = r.tween.advance(dt);
+            let share = match progress {
+                Progress::Waiting => return true,
+                Progress::Running(share) | Progress::Done(share) => share,
+            };
+            let alive = with_live_object(r.object, |o| {
+                let from = *r.from.get_or_insert_with(|| r.property.read(o));
+                let value = std::array::from_fn(|i| from[i] + (r.to[i] - from[i]) * share);
+                r.property.write(o, value);
+            });
+            match (alive, progress) {
+                (None, _) => false,
+                (Some(()), Progress::Done(_)) => {
+                    done.extend(r.on_done.map(|f| (f, r.object)));
+                    false
+                }
+                _ => true,
+            }
+        });
+        GROUPS.with(|g| g.borrow_mut().retain(|g| !g.joined || tweens.iter().any(|r| r.group == Some(g.id))));
+        done
+    });
+    for (callback, object) in done {
+        callback.call(object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::objects::{
+        runtime_create_rect, runtime_get_opacity, runtime_get_position_x, runtime_get_scale, runtime_set_position,
+    };
+
+    thread_local! {
+        static DONE: Cell<i64> = const { Cell::new(0) };
+    }
+
+    extern "C" fn finished(object: i64) {
+        DONE.with(|d| d.set(object));
+    }
+
+    fn cstr(s: &str) -> std::ffi::CString {
+        std::ffi::CString::new(s).unwrap()
+    }
+
+    #[test]
+    fn test_tweens_run_in_sequence_and_call_back() {
+        let ball = runtime_create_rect(10.0, 10.0);
+        runtime_set_position(ball, 100.0, 50.0);
+        let slide = runtime_object_tween_point(ball, cstr("position").as_ptr(), 300.0, 50.0, 1.0);
+        runtime_tween_set_ease(slide, cstr("in_quad").as_ptr());
+        let fade = runtime_object_tween(ball, cstr("opacity").as_ptr(), 0.0, 0.5);
+        runtime_tween_set_after(fade, slide);
+        runtime_tween_set_on_done(fade, finished as *const std::ffi::c_void, 1);
+
+        advance_tweens(0.5);
+        assert_eq!(runtime_get_position_x(ball), 150.0);
+        assert_eq!(runtime_get_opacity(ball), 1.0);
+        // The fade starts the frame after the slide ends
+        advance_tweens(0.5);
+        assert_eq!(runtime_get_position_x(ball), 300.0);
+        assert_eq!(runtime_get_opacity(ball), 1.0);
+        advance_tweens(0.25);
+        assert_eq!(runtime_get_opacity(ball), 0.5);
+        assert_eq!(DONE.with(|d| d.get()), 0);
+        advance_tweens(0.25);
+        assert_eq!(runtime_get_opacity(ball), 0.0);
+        assert_eq!(DONE.with(|d| d.get()), ball);
+    }
+
+    #[test]
+    fn test_groups_run_side_by_side_and_wait_for_each_other() {
+        let ball = runtime_create_rect(10.0, 10.0);
+        runtime_set_position(ball, 0.0, 0.0);
+        // Slide and fade together, then grow once both have ended
+        let together = runtime_tween_group();
+        let slide = runtime_object_tween(ball, cstr("x").as_ptr(), 100.0, 1.0);
+        runtime_tween_set_group(slide, together);
+        let fade = runtime_object_tween(ball, cstr("opacity").as_ptr(), 0.5, 0.5);
+        runtime_tween_set_group(fade, together);
+        let then = runtime_tween_group();
+        runtime_tween_group_set_after(then, together);
+        let grow = runtime_object_tween(ball, cstr("scale").as_ptr(), 2.0, 0.5);
+        runtime_tween_set_group(grow, then);
+
+        advance_tweens(0.5);
+        assert_eq!((runtime_get_position_x(ball), runtime_get_opacity(ball)), (50.0, 0.5));
+        // The fade is over, but the group isn't until the slide ends
+        advance_tweens(0.5);
+        assert_eq!(runtime_get_position_x(ball), 100.0);
+        assert_eq!(runtime_get_scale(ball), 1.0);
+        advance_tweens(0.25);
+        assert_eq!(runtime_get_scale(ball), 1.5);
+        advance_tweens(0.25);
+        assert_eq!(runtime_get_scale(ball), 2.0);
+        assert!(GROUPS.with(|g| g.borrow().iter().all(|g| g.id != together && g.id != then)));
+    }
+
+    #[test]
+    fn test_looping_yoyo_keeps_going_until_stopped() {
+        let ball = runtime_create_rect(10.0, 10.0);
+        let pulse = runtime_object_tween(ball, cstr("x").as_ptr(), 100.0, 1.0);
+        runtime_tween_set_yoyo(pulse, 1);
+        runtime_tween_set_loop(pulse, 1);
+        advance_tweens(1.5);
+        assert_eq!(runtime_get_position_x(ball), 50.0);
+        advance_tweens(1.0);
+        assert_eq!(runtime_get_position_x(ball), 50.0);
+        runtime_object_stop_tweens(ball);
+        advance_tweens(0.25);
+        assert_eq!(runtime_get_position_x(ball), 50.0);
+    }
+}
@@ -0,0 +1,201 @@
+//! Sprite sheets and the frame animations played on them.
+
+/// How a sheet image is cut into frames: left to right, then top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteSheet {
+    pub frame_w: u32,
+    pub frame_h: u32,
+}
+
+impl SpriteSheet {
+    /// The part of an `image_w` × `image_h` sheet showing `frame`, as
+    /// [x, y, w, h]. Frames past the end wrap around to the start.
+    pub fn frame_rect(&self, frame: usize, image_w: u32, image_h: u32) -> [u32; 4] {
+        let columns = (image_w / self.frame_w).max(1) as usize;
+        let rows = (image_h / self.frame_h).max(1) as usize;
+        let frame = frame % (columns * rows);
+        let (col, row) = ((frame % columns) as u32, (frame / columns) as u32);
+        [col * self.frame_w, row * self.frame_h, self.frame_w, self.frame_h]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub name: String,
+    /// Sheet frames in the order they're shown.
+    pub frames: Vec<usize>,
+    /// Frames shown per second.
+    pub fps: f64,
+    /// Start over after the last frame; otherwise stop on it.
+    pub looping: bool,
+}
+
+/// An object's animations and which one is playing.
+#[derive(Debug, Clone, Default)]
+pub struct Animator {
+    animations: Vec<Animation>,
+    current: Option<usize>,
+    /// Position in the current animation's frame list.
+    step: usize,
+    /// Time spent on the current step.
+    elapsed: f64,
+    /// Sheet frame on screen.
+    frame: usize,
+    /// A one-shot animation reached its end.
+    done: bool,
+    /// `done` became true during the last [`Animator::advance`].
+    finished: bool,
+}
+
+impl Animator {
+    /// Add an animation, replacing any with the same name.
+    pub fn add(&mut self, animation: Animation) {
+        match self.animations.iter().position(|a| a.name == animation.name) {
+            Some(i) => {
+                self.animations[i] = animation;
+                if self.current == Some(i) {
+                    self.restart();
+                }
+            }
+            None => self.animations.push(animation),
+        }
+    }
+
+    /// Start the named animation from its first frame. Playing the one
+    /// already running keeps it going, unless it's a one-shot that ended.
+    /// Returns false if there's no animation by that name.
+    pub fn play(&mut self, name: &str) -> bool {
+        let Some(i) = self.animations.iter().position(|a| a.name == name) else {
+            return false;
+        };
+        if self.current != Some(i) || self.done {
+            self.current = Some(i);
+            self.restart();
+        }
+        true
+    }
+
+    fn restart(&mut self) {
+        self.step = 0;
+        self.elapsed = 0.0;
+        self.done = false;
+        if let Some(&frame) = self.playing().and_then(|a| a.frames.first()) {
+            self.frame = frame;
+        }
+    }
+
+    fn playing(&self) -> Option<&Animation> {
+        self.current.map(|i| &self.animations[i])
+    }
+
+    /// Stop animating, keeping the frame on screen.
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.done = false;
+    }
+
+    /// Move `dt` seconds forward.
+    pub fn advance(&mut self, dt: f64) {
+        self.finished = false;
+        let Some(anim) = self.playing() else { return };
+        if self.done || anim.frames.is_empty() || anim.fps <= 0.0 {
+            return;
+        }
+        let (len, looping, step_time) = (anim.frames.len(), anim.looping, 1.0 / anim.fps);
+        self.elapsed += dt;
+        while self.elapsed >= step_time {
+            self.elapsed -= step_time;
+            if self.step + 1 < len {
+                self.step += 1;
+            } else if looping {
+                self.step = 0;
+            } else {
+                self.done = true;
+                self.finished = true;
+                self.elapsed = 0.0;
+                break;
+            }
+        }
+        self.frame = self.playing().unwrap().frames[self.step];
+    }
+
+    /// Sheet frame to draw.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Show a sheet frame, stopping any animation.
+    pub fn set_frame(&mut self, frame: usize) {
+        self.stop();
+        self.frame = frame;
+    }
+
+    /// Name of the animation playing, or "" if none is.
+    pub fn current_name(&self) -> &str {
+        self.playing().map_or("", |a| a.name.as_str())
+    }
+
+    /// A one-shot animation ended during the last [`Animator::advance`].
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anim(name: &str, frames: &[usize], looping: bool) -> Animation {
+        Animation { name: name.into(), frames: frames.to_vec(), fps: 10.0, looping }
+    }
+
+    #[test]
+    fn test_frame_rect_walks_rows() {
+        let sheet = SpriteSheet { frame_w: 16, frame_h: 8 };
+        assert_eq!(sheet.frame_rect(0, 64, 16), [0, 0, 16, 8]);
+        assert_eq!(sheet.frame_rect(5, 64, 16), [16, 8, 16, 8]);
+        assert_eq!(sheet.frame_rect(8, 64, 16), [0, 0, 16, 8]);
+    }
+
+    #[test]
+    fn test_looping_animation_wraps() {
+        let mut a = Animator::default();
+        a.add(anim("run", &[4, 5, 6], true));
+        assert!(a.play("run"));
+        assert_eq!(a.frame(), 4);
+        a.advance(0.25);
+        assert_eq!(a.frame(), 6);
+        a.advance(0.1);
+        assert_eq!(a.frame(), 4);
+        assert!(!a.finished());
+        // Playing it again doesn't restart it
+        a.play("run");
+        assert_eq!(a.frame(), 4);
+        a.advance(0.1);
+        assert_eq!(a.frame(), 5);
+        assert!(!a.play("fly"));
+    }
+
+    #[test]
+    fn test_one_shot_finishes_once() {
+        let mut a = Animator::default();
+        a.add(anim("jump", &[1, 2], false));
+        a.play("jump");
+        a.advance(0.1);
+        assert_eq!(a.frame(), 2);
+        assert!(!a.finished());
+        a.advance(0.1);
+        assert!(a.finished());
+        assert_eq!(a.frame(), 2);
+        assert_eq!(a.current_name(), "jump");
+        a.advance(0.1);
+        assert!(!a.finished());
+
+        // A finished one-shot plays again from the start
+        a.play("jump");
+        assert_eq!(a.frame(), 1);
+        a.set_frame(7);
+        a.advance(1.0);
+        assert_eq!((a.frame(), a.current_name()), (7, ""));
+    }
+}
@@ -0,0 +1,189 @@
+//! Uniform-grid spatial hash, so collision checks and area queries only look
+//! at objects that are near each other instead of every pair.
+
+use crate::object::GameObject;
+use std::collections::HashMap;
+
+/// Smallest and largest cell edge in pixels.
+const MIN_CELL: f64 = 16.0;
+const MAX_CELL: f64 = 512.0;
+
+type Cell = (i64, i64);
+
+/// Live objects other than tilemaps, bucketed by the grid cells their bounding boxes cover.
+#[derive(Debug, Default)]
+pub struct SpatialHash {
+    cell: f64,
+    cells: HashMap<Cell, Vec<usize>>,
+    /// Per object: bounding box and first cell, if inserted.
+    entries: Vec<Option<Entry>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    bounds: (f64, f64, f64, f64),
+    min: Cell,
+}
+
+impl SpatialHash {
+    /// Bucket every live object, with cells sized to about twice the
+    /// typical object so most objects cover one to four cells.
+    pub fn build(objects: &[GameObject]) -> Self {
+        let mut hash = Self::default();
+        hash.rebuild(objects);
+        hash
+    }
+
+    /// Like [`SpatialHash::build`], reusing this hash's allocations.
+    pub fn rebuild(&mut self, objects: &[GameObject]) {
+        // Keep the buckets used last time, drop cells nothing visits anymore
+        self.cells.retain(|_, bucket| {
+            let used = !bucket.is_empty();
+            bucket.clear();
+            used
+        });
+        self.entries.clear();
+
+        let live = objects.iter().filter(|o| Self::hashed(o));
+        let (count, total) = live.fold((0, 0.0), |(n, sum), o| {
+            let (x1, y1, x2, y2) = o.bounds();
+            (n + 1, sum + (x2 - x1).max(y2 - y1))
+        });
+        self.cell = if count == 0 { MAX_CELL } else { (2.0 * total / count as f64).clamp(MIN_CELL, MAX_CELL) };
+
+        for obj in objects {
+            if !Self::hashed(obj) {
+                self.entries.push(None);
+                continue;
+            }
+            let bounds = obj.bounds();
+            let (min, max) = self.cell_range(bounds);
+            let index = self.entries.len();
+            for cx in min.0..=max.0 {
+                for cy in min.1..=max.1 {
+                    self.cells.entry((cx, cy)).or_default().push(index);
+                }
+            }
+            self.entries.push(Some(Entry { bounds, min }));
+        }
+    }
+
+    /// Tilemaps are left out: they can be far bigger than everything else,
+    /// and physics checks their tiles directly. Emitters don't collide.
+    fn hashed(obj: &GameObject) -> bool {
+        obj.alive && obj.tilemap.is_none() && obj.emitter.is_none()
+    }
+
+    fn cell_range(&self, (x1, y1, x2, y2): (f64, f64, f64, f64)) -> (Cell, Cell) {
+        let at = |v: f64| (v / self.cell).floor() as i64;
+        ((at(x1), at(y1)), (at(x2), at(y2)))
+    }
+
+    /// Every pair `(i, j)` with `i < j` whose bounding boxes overlap, each
+    /// reported once.
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (&cell, bucket) in &self.cells {
+            for (n, &i) in bucket.iter().enumerate() {
+                let a = self.entries[i].unwrap();
+                for &j in &bucket[n + 1..] {
+                    let b = self.entries[j].unwrap();
+                    // A pair sharing several cells is reported only from the
+                    // first cell of the region both cover
+                    if cell != (a.min.0.max(b.min.0), a.min.1.max(b.min.1)) {
+                        continue;
+                    }
+                    if boxes_overlap(a.bounds, b.bounds) {
+                        pairs.push((i.min(j), i.max(j)));
+                    }
+                }
+            }
+        }
+        // Cells come out of the map in arbitrary order; keep physics deterministic
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Objects whose bounding boxes overlap the area, in index order.
+    pub fn query(&self, area: (f64, f64, f64, f64)) -> Vec<usize> {
+        let (min, max) = self.cell_range(area);
+        let cells = (max.0 as f64 - min.0 as f64 + 1.0) * (max.1 as f64 - min.1 as f64 + 1.0);
+        if cells > self.entries.len() as f64 {
+            // Visiting every cell would cost more than checking every object
+            return (0..self.entries.len())
+                .filter(|&i| self.entries[i].is_some_and(|e| boxes_overlap(e.bounds, area)))
+                .collect();
+        }
+        let mut found = Vec::new();
+        for cx in min.0..=max.0 {
+            for cy in min.1..=max.1 {
+                let Some(bucket) = self.cells.get(&(cx, cy)) else { continue };
+                for &i in bucket {
+                    let e = self.entries[i].unwrap();
+                    // Report each object from the first queried cell it covers
+                    if (cx, cy) == (e.min.0.max(min.0), e.min.1.max(min.1)) && boxes_overlap(e.bounds, area) {
+                        found.push(i);
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+fn boxes_overlap(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> bool {
+    a.0 < b.2 && a.2 > b.0 && a.1 < b.3 && a.3 > b.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::ObjectKind;
+    use crate::rng::Rng;
+
+    fn rect_at(x: f64, y: f64, w: f64, h: f64) -> GameObject {
+        let mut o = GameObject::new(ObjectKind::Rect, w, h);
+        o.x = x;
+        o.y = y;
+        o
+    }
+
+    #[test]
+    fn test_pairs_match_brute_force() {
+        let mut rng = Rng::new(9);
+        let mut objs: Vec<GameObject> = (0..400)
+            .map(|_| {
+                let size = rng.range_f64(2.0, 40.0);
+                rect_at(rng.range_f64(-50.0, 800.0), rng.range_f64(-50.0, 600.0), size, size)
+            })
+            .collect();
+        objs.push(rect_at(0.0, 550.0, 800.0, 50.0));
+        objs[7].alive = false;
+
+        let mut expected = Vec::new();
+        for i in 0..objs.len() {
+            for j in i + 1..objs.len() {
+                if objs[i].alive && objs[j].alive && boxes_overlap(objs[i].bounds(), objs[j].bounds()) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert!(expected.len() > 50);
+        assert_eq!(SpatialHash::build(&objs).pairs(), expected);
+    }
+
+    #[test]
+    fn test_query() {
+        let objs = vec![
+            rect_at(0.0, 0.0, 10.0, 10.0),
+            rect_at(100.0, 100.0, 10.0, 10.0),
+            rect_at(0.0, 90.0, 300.0, 5.0),
+        ];
+        let hash = SpatialHash::build(&objs);
+        assert_eq!(hash.query((-5.0, -5.0, 5.0, 5.0)), vec![0]);
+        assert_eq!(hash.query((50.0, 50.0, 105.0, 105.0)), vec![1, 2]);
+        assert!(hash.query((500.0, 500.0, 600.0, 600.0)).is_empty());
+        assert_eq!(hash.query((-1e12, -1e12, 1e12, 1e12)), vec![0, 1, 2]);
+    }
+}
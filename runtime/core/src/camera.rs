@@ -0,0 +1,217 @@
+//! The camera: which part of the world the screen shows.
+
+/// How world coordinates map to the screen for one frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    /// World point at the screen's top-left corner.
+    pub x: f64,
+    pub y: f64,
+    /// Screen pixels per world pixel.
+    pub zoom: f64,
+}
+
+impl View {
+    /// World and screen coordinates are the same.
+    pub const SCREEN: View = View { x: 0.0, y: 0.0, zoom: 1.0 };
+
+    pub fn to_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.x) * self.zoom, (y - self.y) * self.zoom)
+    }
+
+    pub fn to_world(&self, x: f64, y: f64) -> (f64, f64) {
+        (x / self.zoom + self.x, y / self.zoom + self.y)
+    }
+
+    /// The world area shown on a screen this big, as (x1, y1, x2, y2).
+    pub fn area(&self, (screen_w, screen_h): (f64, f64)) -> (f64, f64, f64, f64) {
+        (self.x, self.y, self.x + screen_w / self.zoom, self.y + screen_h / self.zoom)
+    }
+
+    /// The view for a layer that moves `factor` times as far as the camera,
+    /// zoom included: 0 is the untouched screen, 1 is this view.
+    pub fn parallax(&self, factor: f64, (screen_w, screen_h): (f64, f64)) -> View {
+        let zoom = 1.0 + (self.zoom - 1.0) * factor;
+        let (hw, hh) = (screen_w / 2.0, screen_h / 2.0);
+        let cx = hw + (self.x + hw / self.zoom - hw) * factor;
+        let cy = hh + (self.y + hh / self.zoom - hh) * factor;
+        View { x: cx - hw / zoom, y: cy - hh / zoom, zoom }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Shake {
+    strength: f64,
+    duration: f64,
+    left: f64,
+    time: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Camera {
+    /// World point at the screen's center; until the camera moves it's the
+    /// screen's own center, so world and screen coordinates match.
+    center: Option<(f64, f64)>,
+    pub zoom: f64,
+    /// Share of the way to its target the camera still has left after each
+    /// 1/60 s: 0 keeps the target exactly centered, 0.9 drifts after it.
+    pub smoothing: f64,
+    /// Size of the box around the screen's center the target can move in
+    /// without the camera following.
+    pub deadzone: (f64, f64),
+    /// World area (x, y, w, h) the camera doesn't show past.
+    pub bounds: Option<[f64; 4]>,
+    shake: Shake,
+    /// Jump straight to the target on the next update.
+    snap: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self { center: None, zoom: 1.0, smoothing: 0.0, deadzone: (0.0, 0.0), bounds: None, shake: Shake::default(), snap: false }
+    }
+}
+
+impl Camera {
+    pub fn center(&self, (screen_w, screen_h): (f64, f64)) -> (f64, f64) {
+        self.center.unwrap_or((screen_w / 2.0, screen_h / 2.0))
+    }
+
+    pub fn set_center(&mut self, x: f64, y: f64) {
+        self.center = Some((x, y));
+    }
+
+    /// Center on the next target passed to [`Camera::update`] rather than
+    /// gliding over to it.
+    pub fn snap_to_target(&mut self) {
+        self.snap = true;
+    }
+
+    /// Shake the view up to `strength` pixels, fading out over `duration`
+    /// seconds.
+    pub fn shake(&mut self, strength: f64, duration: f64) {
+        self.shake = Shake { strength, duration, left: duration, time: 0.0 };
+    }
+
+    /// Move `dt` seconds forward, following `target` (a world point) if
+    /// there is one and staying inside the bounds.
+    pub fn update(&mut self, target: Option<(f64, f64)>, screen: (f64, f64), dt: f64) {
+        if self.shake.left > 0.0 {
+            self.shake.left -= dt;
+            self.shake.time += dt;
+        }
+        if target.is_none() && self.bounds.is_none() {
+            return;
+        }
+        let (mut cx, mut cy) = self.center(screen);
+        if let Some((tx, ty)) = target {
+            if std::mem::take(&mut self.snap) {
+                (cx, cy) = (tx, ty);
+            } else {
+                let (hw, hh) = (self.deadzone.0 / 2.0, self.deadzone.1 / 2.0);
+                let goal_x = cx + tx - tx.clamp(cx - hw, cx + hw);
+                let goal_y = cy + ty - ty.clamp(cy - hh, cy + hh);
+                let keep = self.smoothing.clamp(0.0, 0.999).powf(dt * 60.0);
+                cx = goal_x + (cx - goal_x) * keep;
+                cy = goal_y + (cy - goal_y) * keep;
+            }
+        }
+        if let Some([bx, by, bw, bh]) = self.bounds {
+            cx = clamp_span(cx, bx, bw, screen.0 / 2.0 / self.zoom);
+            cy = clamp_span(cy, by, bh, screen.1 / 2.0 / self.zoom);
+        }
+        self.center = Some((cx, cy));
+    }
+
+    /// The world area on screen this frame.
+    pub fn view(&self, screen: (f64, f64)) -> View {
+        let (mut cx, mut cy) = self.center(screen);
+        let s = self.shake;
+        if s.left > 0.0 && s.duration > 0.0 {
+            // Two out-of-step waves look random but replay exactly
+            let amount = s.strength * s.left / s.duration;
+            cx += amount * (s.time * 83.0).sin();
+            cy += amount * (s.time * 67.0 + 1.3).sin();
+        }
+        View { x: cx - screen.0 / 2.0 / self.zoom, y: cy - screen.1 / 2.0 / self.zoom, zoom: self.zoom }
+    }
+}
+
+/// Keep a view `half` wide on each side of `center` inside `start..start + len`,
+/// or centered on it when the view is wider.
+fn clamp_span(center: f64, start: f64, len: f64, half: f64) -> f64 {
+    if len <= half * 2.0 {
+        start + len / 2.0
+    } else {
+        center.clamp(start + half, start + len - half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: (f64, f64) = (800.0, 600.0);
+
+    #[test]
+    fn test_untouched_camera_shows_screen_coordinates() {
+        let mut cam = Camera::default();
+        cam.update(None, SCREEN, 1.0 / 60.0);
+        assert_eq!(cam.view(SCREEN), View::SCREEN);
+        cam.zoom = 2.0;
+        let view = cam.view(SCREEN);
+        assert_eq!((view.x, view.y), (200.0, 150.0));
+        assert_eq!(view.to_screen(400.0, 300.0), (400.0, 300.0));
+        assert_eq!(view.to_world(0.0, 0.0), (200.0, 150.0));
+    }
+
+    #[test]
+    fn test_follow_with_deadzone_and_smoothing() {
+        let mut cam = Camera::default();
+        cam.snap_to_target();
+        cam.update(Some((1000.0, 300.0)), SCREEN, 0.0);
+        assert_eq!(cam.center(SCREEN), (1000.0, 300.0));
+
+        // Inside the deadzone nothing moves; past it the camera keeps the
+        // target on its edge
+        cam.deadzone = (100.0, 100.0);
+        cam.update(Some((1040.0, 300.0)), SCREEN, 1.0 / 60.0);
+        assert_eq!(cam.center(SCREEN), (1000.0, 300.0));
+        cam.update(Some((1080.0, 300.0)), SCREEN, 1.0 / 60.0);
+        assert_eq!(cam.center(SCREEN), (1030.0, 300.0));
+
+        cam.deadzone = (0.0, 0.0);
+        cam.smoothing = 0.5;
+        cam.update(Some((1130.0, 300.0)), SCREEN, 1.0 / 60.0);
+        assert_eq!(cam.center(SCREEN), (1080.0, 300.0));
+    }
+
+    #[test]
+    fn test_bounds_and_shake() {
+        let mut cam = Camera { bounds: Some([0.0, 0.0, 2000.0, 500.0]), ..Camera::default() };
+        cam.update(Some((-50.0, 250.0)), SCREEN, 1.0 / 60.0);
+        // Left edge stops the camera; the world is shorter than the screen
+        assert_eq!(cam.center(SCREEN), (400.0, 250.0));
+        cam.update(Some((1990.0, 250.0)), SCREEN, 1.0 / 60.0);
+        assert_eq!(cam.center(SCREEN), (1600.0, 250.0));
+
+        cam.shake(10.0, 0.5);
+        cam.update(Some((1990.0, 250.0)), SCREEN, 0.1);
+        let view = cam.view(SCREEN);
+        assert!(view.x != 1200.0 && (view.x - 1200.0).abs() <= 10.0);
+        cam.update(Some((1990.0, 250.0)), SCREEN, 0.5);
+        assert_eq!(cam.view(SCREEN).x, 1200.0);
+    }
+
+    #[test]
+    fn test_parallax_moves_a_share_of_the_way() {
+        let view = View { x: 600.0, y: 100.0, zoom: 1.0 };
+        assert_eq!(view.parallax(1.0, SCREEN), view);
+        assert_eq!(view.parallax(0.0, SCREEN), View::SCREEN);
+        assert_eq!(view.parallax(0.5, SCREEN), View { x: 300.0, y: 50.0, zoom: 1.0 });
+        // Zoomed in 2× around (1000, 400): half as far and half as much zoom
+        let zoomed = View { x: 800.0, y: 250.0, zoom: 2.0 };
+        let half = zoomed.parallax(0.5, SCREEN);
+        assert_eq!(half.zoom, 1.5);
+        assert_eq!(half.to_world(400.0, 300.0), (700.0, 350.0));
+    }
+}
@@ -0,0 +1,442 @@
+//! Exact overlap tests and ray casts against object shapes.
+//!
+//! Circles are tested as circles; rectangles as (possibly rotated) boxes and
+//! other shapes as convex polygons, using the separating axis theorem. Every hit comes with a contact normal
+//! and depth, which the physics step uses to push objects apart.
+
+/// Collision geometry of one object, in screen coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle { x: f64, y: f64, r: f64 },
+    /// Box centered on (x, y) with half extents, rotated by `angle` radians.
+    Box { x: f64, y: f64, hw: f64, hh: f64, angle: f64 },
+    /// Convex polygon, its corners in order (see `convex_hull`).
+    Polygon(Vec<(f64, f64)>),
+}
+
+/// How two shapes overlap. Moving the first shape `depth` pixels along the
+/// unit normal (`nx`, `ny`) separates them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub nx: f64,
+    pub ny: f64,
+    pub depth: f64,
+}
+
+impl Contact {
+    fn flipped(self) -> Self {
+        Self { nx: -self.nx, ny: -self.ny, depth: self.depth }
+    }
+}
+
+/// Contact between `a` and `b`, or None if they don't overlap. Shapes that
+/// only touch don't count.
+pub fn contact(a: &Shape, b: &Shape) -> Option<Contact> {
+    match (a, b) {
+        (&Shape::Circle { x: ax, y: ay, r: ar }, &Shape::Circle { x: bx, y: by, r: br }) => {
+            let (dx, dy) = (ax - bx, ay - by);
+            let dist = dx.hypot(dy);
+            let depth = ar + br - dist;
+            if depth <= 0.0 {
+                return None;
+            }
+            // Concentric circles: push straight up
+            let (nx, ny) = if dist > 0.0 { (dx / dist, dy / dist) } else { (0.0, -1.0) };
+            Some(Contact { nx, ny, depth })
+        }
+        (&Shape::Circle { x, y, r }, Shape::Box { .. }) => circle_box(x, y, r, b),
+        (Shape::Box { .. }, &Shape::Circle { x, y, r }) => circle_box(x, y, r, a).map(Contact::flipped),
+        (Shape::Box { .. }, Shape::Box { .. }) => box_box(a, b),
+        (&Shape::Circle { x, y, r }, _) => circle_polygon(x, y, r, &corners(b)),
+        (_, &Shape::Circle { x, y, r }) => circle_polygon(x, y, r, &corners(a)).map(Contact::flipped),
+        _ => polygon_polygon(&corners(a), &corners(b)),
+    }
+}
+
+/// Point-in-shape test, edges included.
+pub fn contains(shape: &Shape, px: f64, py: f64) -> bool {
+    match *shape {
+        Shape::Circle { x, y, r } => (px - x).hypot(py - y) <= r,
+        Shape::Box { x, y, hw, hh, angle } => {
+            let (lx, ly) = rotate(px - x, py - y, -angle);
+            lx.abs() <= hw && ly.abs() <= hh
+        }
+        Shape::Polygon(ref points) => {
+            // Inside a convex polygon: on the same side of every edge
+            let mut sides = edges(points).map(|((x1, y1), (x2, y2))| (x2 - x1) * (py - y1) - (y2 - y1) * (px - x1));
+            !points.is_empty() && (sides.clone().all(|s| s >= 0.0) || sides.all(|s| s <= 0.0))
+        }
+    }
+}
+
+/// How far along a ray from `from` in the unit direction `dir` it first
+/// meets the shape, or None if it doesn't within `max`. A ray starting
+/// inside the shape meets it at 0.
+pub fn ray(shape: &Shape, (ox, oy): (f64, f64), (dx, dy): (f64, f64), max: f64) -> Option<f64> {
+    let enter = match *shape {
+        Shape::Circle { x, y, r } => {
+            let (fx, fy) = (ox - x, oy - y);
+            let b = fx * dx + fy * dy;
+            let c = fx * fx + fy * fy - r * r;
+            if c <= 0.0 {
+                return Some(0.0);
+            }
+            let disc = b * b - c;
+            if disc < 0.0 {
+                return None;
+            }
+            -b - disc.sqrt()
+        }
+        _ => ray_polygon(&corners(shape), (ox, oy), (dx, dy))?.max(0.0),
+    };
+    (0.0..=max).contains(&enter).then_some(enter)
+}
+
+/// Where a ray enters a convex polygon (negative if it starts inside), or
+/// None if it misses or the polygon is behind it; clips the ray against
+/// each edge in turn.
+fn ray_polygon(points: &[(f64, f64)], (ox, oy): (f64, f64), (dx, dy): (f64, f64)) -> Option<f64> {
+    if points.is_empty() {
+        return None;
+    }
+    let (cx, cy) = centroid(points);
+    let (mut enter, mut exit) = (f64::NEG_INFINITY, f64::INFINITY);
+    for ((x1, y1), (x2, y2)) in edges(points) {
+        // Outward normal: away from the middle, whichever way the corners go
+        let (mut nx, mut ny) = (y1 - y2, x2 - x1);
+        if nx * (cx - x1) + ny * (cy - y1) > 0.0 {
+            (nx, ny) = (-nx, -ny);
+        }
+        // Inside this edge while n · (from + t·dir - corner) <= 0
+        let toward = nx * dx + ny * dy;
+        let room = nx * (x1 - ox) + ny * (y1 - oy);
+        if toward == 0.0 {
+            if room < 0.0 {
+                return None;
+            }
+        } else if toward > 0.0 {
+            exit = exit.min(room / toward);
+        } else {
+            enter = enter.max(room / toward);
+        }
+    }
+    (enter <= exit && exit >= 0.0).then_some(enter)
+}
+
+/// Axis-aligned bounding box of a shape as (x1, y1, x2, y2).
+pub fn bounds(shape: &Shape) -> (f64, f64, f64, f64) {
+    match *shape {
+        Shape::Circle { x, y, r } => (x - r, y - r, x + r, y + r),
+        Shape::Box { x, y, hw, hh, angle } => {
+            let (sin, cos) = angle.sin_cos();
+            let ex = hw * cos.abs() + hh * sin.abs();
+            let ey = hw * sin.abs() + hh * cos.abs();
+            (x - ex, y - ey, x + ex, y + ey)
+        }
+        Shape::Polygon(ref points) => points.iter().fold(
+            (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            |(x1, y1, x2, y2), &(x, y)| (x1.min(x), y1.min(y), x2.max(x), y2.max(y)),
+        ),
+    }
+}
+
+/// The convex polygon around `points`, like a rubber band stretched over
+/// them, in clockwise order on screen.
+pub fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    // Andrew's monotone chain: the lower half, then the upper half
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(sorted.len() + 1);
+    for pass in [&sorted[..], &sorted.iter().rev().copied().collect::<Vec<_>>()[..]] {
+        let start = hull.len();
+        for &p in pass {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+fn rotate(x: f64, y: f64, angle: f64) -> (f64, f64) {
+    let (sin, cos) = angle.sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+/// Corners of a box or polygon.
+fn corners(shape: &Shape) -> Vec<(f64, f64)> {
+    match *shape {
+        Shape::Circle { x, y, .. } => vec![(x, y)],
+        Shape::Box { x, y, hw, hh, angle } => [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+            .map(|(cx, cy)| {
+                let (rx, ry) = rotate(cx, cy, angle);
+                (x + rx, y + ry)
+            })
+            .to_vec(),
+        Shape::Polygon(ref points) => points.clone(),
+    }
+}
+
+/// Each edge of a closed polygon as (start, end).
+fn edges(points: &[(f64, f64)]) -> impl Iterator<Item = ((f64, f64), (f64, f64))> + Clone + '_ {
+    points.iter().enumerate().map(|(i, &p)| (p, points[(i + 1) % points.len()]))
+}
+
+/// Unit axes to test a polygon on: its edge normals, plus the direction of a
+/// polygon flattened into a segment.
+fn axes(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut axes = Vec::new();
+    for ((x1, y1), (x2, y2)) in edges(points) {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let len = dx.hypot(dy);
+        if len > 0.0 {
+            axes.push((-dy / len, dx / len));
+            if points.len() == 2 {
+                axes.push((dx / len, dy / len));
+            }
+        }
+    }
+    axes
+}
+
+/// Lowest and highest projection of `points` on an axis.
+fn project(points: &[(f64, f64)], (ux, uy): (f64, f64)) -> (f64, f64) {
+    points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(x, y)| {
+        let d = x * ux + y * uy;
+        (lo.min(d), hi.max(d))
+    })
+}
+
+/// The least overlap over `axes` between two projected shapes, each given
+/// by its projection function and center; None if any axis separates them.
+fn least_overlap(
+    axes: impl IntoIterator<Item = (f64, f64)>,
+    a: impl Fn((f64, f64)) -> (f64, f64),
+    b: impl Fn((f64, f64)) -> (f64, f64),
+    (dx, dy): (f64, f64),
+) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+    for (ux, uy) in axes {
+        let ((alo, ahi), (blo, bhi)) = (a((ux, uy)), b((ux, uy)));
+        let overlap = ahi.min(bhi) - alo.max(blo);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if best.is_none_or(|c| overlap < c.depth) {
+            let sign = if dx * ux + dy * uy < 0.0 { -1.0 } else { 1.0 };
+            best = Some(Contact { nx: ux * sign, ny: uy * sign, depth: overlap });
+        }
+    }
+    best
+}
+
+fn centroid(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len().max(1) as f64;
+    let (x, y) = points.iter().fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+    (x / n, y / n)
+}
+
+/// Separating axis test for two convex polygons, with the normal pointing
+/// from `b` to `a`.
+fn polygon_polygon(a: &[(f64, f64)], b: &[(f64, f64)]) -> Option<Contact> {
+    let ((ax, ay), (bx, by)) = (centroid(a), centroid(b));
+    least_overlap(axes(a).into_iter().chain(axes(b)), |u| project(a, u), |u| project(b, u), (ax - bx, ay - by))
+}
+
+/// Circle against a convex polygon, with the normal pointing from the
+/// polygon to the circle.
+fn circle_polygon(cx: f64, cy: f64, r: f64, points: &[(f64, f64)]) -> Option<Contact> {
+    let mut axes = axes(points);
+    // The circle's own axis: toward the polygon's nearest corner
+    let nearest = points.iter().min_by(|a, b| (a.0 - cx).hypot(a.1 - cy).total_cmp(&(b.0 - cx).hypot(b.1 - cy)))?;
+    let (dx, dy) = (cx - nearest.0, cy - nearest.1);
+    let dist = dx.hypot(dy);
+    if dist > 0.0 {
+        axes.push((dx / dist, dy / dist));
+    }
+    let (px, py) = centroid(points);
+    let circle = |(ux, uy): (f64, f64)| {
+        let d = cx * ux + cy * uy;
+        (d - r, d + r)
+    };
+    least_overlap(axes, circle, |u| project(points, u), (cx - px, cy - py))
+}
+
+/// Circle against a box, with the normal pointing from the box to the circle.
+fn circle_box(cx: f64, cy: f64, r: f64, bx: &Shape) -> Option<Contact> {
+    let Shape::Box { x, y, hw, hh, angle } = *bx else {
+        return None;
+    };
+    // Work in the box's frame, where it is axis-aligned at the origin
+    let (lx, ly) = rotate(cx - x, cy - y, -angle);
+    let (qx, qy) = (lx.clamp(-hw, hw), ly.clamp(-hh, hh));
+    let (nx, ny, depth) = if (qx, qy) != (lx, ly) {
+        let (dx, dy) = (lx - qx, ly - qy);
+        let dist = dx.hypot(dy);
+        if dist >= r {
+            return None;
+        }
+        (dx / dist, dy / dist, r - dist)
+    } else {
+        // Center inside the box: leave through the nearest side
+        let (ex, ey) = (hw - lx.abs(), hh - ly.abs());
+        if ex < ey {
+            (if lx < 0.0 { -1.0 } else { 1.0 }, 0.0, ex + r)
+        } else {
+            (0.0, if ly < 0.0 { -1.0 } else { 1.0 }, ey + r)
+        }
+    };
+    let (nx, ny) = rotate(nx, ny, angle);
+    Some(Contact { nx, ny, depth })
+}
+
+/// Separating axis test for two boxes; the contact normal is the axis of
+/// least overlap, pointing from `b` to `a`.
+fn box_box(a: &Shape, b: &Shape) -> Option<Contact> {
+    let (&Shape::Box { x: ax, y: ay, hw: ahw, hh: ahh, angle: aa },
+         &Shape::Box { x: bx, y: by, hw: bhw, hh: bhh, angle: ba }) = (a, b) else {
+        return None;
+    };
+    let axes_of = |angle: f64| {
+        let (sin, cos) = angle.sin_cos();
+        // Vertical axis first, so ties resolve up/down (objects land on floors)
+        [(-sin, cos), (cos, sin)]
+    };
+    let (dx, dy) = (ax - bx, ay - by);
+    let mut best: Option<Contact> = None;
+    for (ux, uy) in axes_of(aa).into_iter().chain(axes_of(ba)) {
+        let radius = |hw: f64, hh: f64, angle: f64| {
+            let [(vx, vy), (hx, hy)] = axes_of(angle);
+            hw * (hx * ux + hy * uy).abs() + hh * (vx * ux + vy * uy).abs()
+        };
+        let dist = dx * ux + dy * uy;
+        let overlap = radius(ahw, ahh, aa) + radius(bhw, bhh, ba) - dist.abs();
+        if overlap <= 0.0 {
+            return None;
+        }
+        if best.is_none_or(|c| overlap < c.depth) {
+            let sign = if dist < 0.0 { -1.0 } else { 1.0 };
+            best = Some(Contact { nx: ux * sign, ny: uy * sign, depth: overlap });
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(x: f64, y: f64, hw: f64, hh: f64, degrees: f64) -> Shape {
+        Shape::Box { x, y, hw, hh, angle: degrees.to_radians() }
+    }
+
+    #[test]
+    fn test_circles() {
+        let a = Shape::Circle { x: 0.0, y: 0.0, r: 10.0 };
+        let b = Shape::Circle { x: 15.0, y: 0.0, r: 10.0 };
+        let c = contact(&a, &b).unwrap();
+        assert_eq!((c.nx, c.ny, c.depth), (-1.0, 0.0, 5.0));
+        assert!(contact(&a, &Shape::Circle { x: 20.0, y: 0.0, r: 10.0 }).is_none());
+    }
+
+    #[test]
+    fn test_circle_misses_box_corner() {
+        // Bounding boxes overlap at the corner, the shapes don't
+        let ball = Shape::Circle { x: 2.0, y: 2.0, r: 10.0 };
+        let paddle = boxed(20.0, 20.0, 10.0, 10.0, 0.0);
+        assert!(contact(&ball, &paddle).is_none());
+        let ball = Shape::Circle { x: 5.0, y: 20.0, r: 10.0 };
+        let c = contact(&ball, &paddle).unwrap();
+        assert_eq!((c.nx, c.ny, c.depth), (-1.0, 0.0, 5.0));
+        let c = contact(&paddle, &ball).unwrap();
+        assert_eq!((c.nx, c.ny), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_axis_aligned_boxes_prefer_vertical_normal() {
+        let block = boxed(0.0, 0.0, 10.0, 10.0, 0.0);
+        let floor = boxed(0.0, 15.0, 10.0, 10.0, 0.0);
+        let c = contact(&block, &floor).unwrap();
+        assert_eq!((c.nx, c.ny, c.depth), (0.0, -1.0, 5.0));
+        assert!(contact(&block, &boxed(20.0, 0.0, 10.0, 10.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        // An arrowhead: the notch at (5, 5) isn't part of the hull
+        let hull = convex_hull(&[(0.0, 0.0), (20.0, 5.0), (0.0, 10.0), (5.0, 5.0), (0.0, 0.0)]);
+        assert_eq!(hull, vec![(0.0, 0.0), (20.0, 5.0), (0.0, 10.0)]);
+        let ship = Shape::Polygon(hull);
+        assert_eq!(bounds(&ship), (0.0, 0.0, 20.0, 10.0));
+        assert!(contains(&ship, 5.0, 5.0) && contains(&ship, 20.0, 5.0));
+        assert!(!contains(&ship, 15.0, 1.0));
+    }
+
+    #[test]
+    fn test_polygons() {
+        let triangle = Shape::Polygon(vec![(0.0, 0.0), (20.0, 0.0), (0.0, 20.0)]);
+        // The box is inside the triangle's bounding box, past its long edge
+        assert!(contact(&triangle, &boxed(17.0, 17.0, 2.0, 2.0, 0.0)).is_none());
+        let c = contact(&boxed(5.0, -3.0, 5.0, 5.0, 0.0), &triangle).unwrap();
+        assert_eq!((c.nx, c.ny, c.depth), (0.0, -1.0, 2.0));
+        let ball = Shape::Circle { x: 14.0, y: 14.0, r: 5.0 };
+        assert!(contact(&ball, &triangle).is_none());
+        let ball = Shape::Circle { x: 12.0, y: 12.0, r: 5.0 };
+        let c = contact(&ball, &triangle).unwrap();
+        let s = 0.5f64.sqrt();
+        assert!((c.nx - s).abs() < 1e-9 && (c.ny - s).abs() < 1e-9, "{c:?}");
+        let c = contact(&triangle, &ball).unwrap();
+        assert!((c.nx + s).abs() < 1e-9 && (c.ny + s).abs() < 1e-9, "{c:?}");
+    }
+
+    #[test]
+    fn test_rays() {
+        let ball = Shape::Circle { x: 50.0, y: 0.0, r: 10.0 };
+        assert_eq!(ray(&ball, (0.0, 0.0), (1.0, 0.0), 100.0), Some(40.0));
+        assert_eq!(ray(&ball, (0.0, 0.0), (1.0, 0.0), 30.0), None);
+        assert_eq!(ray(&ball, (0.0, 0.0), (-1.0, 0.0), 100.0), None);
+        assert_eq!(ray(&ball, (0.0, 20.0), (1.0, 0.0), 100.0), None);
+        assert_eq!(ray(&ball, (45.0, 0.0), (1.0, 0.0), 100.0), Some(0.0));
+
+        let wall = boxed(50.0, 0.0, 5.0, 20.0, 0.0);
+        assert_eq!(ray(&wall, (0.0, 10.0), (1.0, 0.0), 100.0), Some(45.0));
+        assert_eq!(ray(&wall, (100.0, 0.0), (-1.0, 0.0), 100.0), Some(45.0));
+        assert_eq!(ray(&wall, (0.0, 30.0), (1.0, 0.0), 100.0), None);
+        assert_eq!(ray(&wall, (50.0, 0.0), (0.0, 1.0), 100.0), Some(0.0));
+        // Along the diagonal, into a diamond's corner
+        let diamond = boxed(50.0, 0.0, 10.0, 10.0, 45.0);
+        let t = ray(&diamond, (0.0, 0.0), (1.0, 0.0), 100.0).unwrap();
+        assert!((t - (50.0 - 200f64.sqrt())).abs() < 1e-9, "{t}");
+
+        let triangle = Shape::Polygon(vec![(0.0, 0.0), (20.0, 0.0), (0.0, 20.0)]);
+        let s = 0.5f64.sqrt();
+        let t = ray(&triangle, (20.0, 20.0), (-s, -s), 100.0).unwrap();
+        assert!((t - 200f64.sqrt()).abs() < 1e-9, "{t}");
+        assert_eq!(ray(&triangle, (30.0, 30.0), (s, s), 100.0), None);
+    }
+
+    #[test]
+    fn test_rotated_box() {
+        // A 45° diamond: the bounding box reaches x = ±14.1 but the edges are diagonal
+        let diamond = boxed(0.0, 0.0, 10.0, 10.0, 45.0);
+        let (x1, _, x2, _) = bounds(&diamond);
+        assert!((x2 - 200f64.sqrt()).abs() < 1e-9 && (x1 + x2).abs() < 1e-9);
+        assert!(contact(&diamond, &boxed(22.0, 10.0, 10.0, 1.0, 0.0)).is_none());
+        assert!(contact(&diamond, &boxed(22.0, 0.0, 10.0, 1.0, 0.0)).is_some());
+        assert!(!contains(&diamond, 12.0, 10.0));
+        assert!(contains(&diamond, 13.0, 0.0));
+        let ball = Shape::Circle { x: 12.0, y: 12.0, r: 4.0 };
+        assert!(contact(&ball, &diamond).is_none());
+        let ball = Shape::Circle { x: 9.0, y: 9.0, r: 4.0 };
+        let c = contact(&ball, &diamond).unwrap();
+        let s = 0.5f64.sqrt();
+        assert!((c.nx - s).abs() < 1e-9 && (c.ny - s).abs() < 1e-9, "{c:?}");
+    }
+}
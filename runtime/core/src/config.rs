@@ -0,0 +1,64 @@
+//! Run settings read from the environment, so `gbasic --run` or a test
+//! harness can steer a compiled program without recompiling it.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    /// `GBASIC_HEADLESS=1`: use the headless backend even in a desktop build.
+    pub headless: bool,
+    /// `GBASIC_FRAMES=N`: exit after N frames.
+    pub max_frames: Option<u64>,
+    /// `GBASIC_SCREENSHOT_EVERY=N`: save every Nth frame as `frame_NNNNN.png`.
+    pub screenshot_every: Option<u64>,
+    /// `GBASIC_SCREENSHOT_FRAMES=30,60,120`: also save these frames.
+    pub screenshot_frames: Vec<u64>,
+    /// `GBASIC_SCREENSHOT_DIR`: where those screenshots go (default: current dir).
+    pub screenshot_dir: PathBuf,
+    /// `GBASIC_INPUT=path`: drive input from an [`InputScript`](crate::input_script::InputScript).
+    pub input_script: Option<PathBuf>,
+    /// `GBASIC_RECORD=path`: log live input (and the random seed) to a file.
+    pub record: Option<PathBuf>,
+    /// `GBASIC_REPLAY=path`: play back a recorded log instead of live input.
+    pub replay: Option<PathBuf>,
+    /// `GBASIC_SEED=N`: random seed instead of one taken from the clock.
+    pub seed: Option<u64>,
+}
+
+impl RunConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let count = |name: &str| var(name).and_then(|v| v.parse::<u64>().ok()).filter(|&n| n > 0);
+        Self {
+            headless: var("GBASIC_HEADLESS").is_some_and(|v| v != "0"),
+            max_frames: count("GBASIC_FRAMES"),
+            screenshot_every: count("GBASIC_SCREENSHOT_EVERY"),
+            screenshot_frames: var("GBASIC_SCREENSHOT_FRAMES")
+                .map(|v| v.split(',').filter_map(|n| n.trim().parse().ok()).collect())
+                .unwrap_or_default(),
+            screenshot_dir: var("GBASIC_SCREENSHOT_DIR").map(PathBuf::from).unwrap_or_default(),
+            input_script: var("GBASIC_INPUT").map(PathBuf::from),
+            record: var("GBASIC_RECORD").map(PathBuf::from),
+            replay: var("GBASIC_REPLAY").map(PathBuf::from),
+            seed: var("GBASIC_SEED").and_then(|v| v.parse().ok()),
+        }
+    }
+
+    /// Whether frame `count` should be saved as a screenshot.
+    pub fn captures(&self, count: u64) -> bool {
+        self.screenshot_every.is_some_and(|n| count.is_multiple_of(n))
+            || self.screenshot_frames.contains(&count)
+    }
+
+    /// Recording or replaying: frame time is fixed so both runs match.
+    pub fn deterministic(&self) -> bool {
+        self.record.is_some() || self.replay.is_some()
+    }
+
+    /// The settings for this process, read once.
+    pub fn get() -> &'static RunConfig {
+        static CONFIG: OnceLock<RunConfig> = OnceLock::new();
+        CONFIG.get_or_init(Self::from_env)
+    }
+}
@@ -0,0 +1,548 @@
+//! An in-memory RGBA image: the headless screen, screenshots and loaded images.
+
+use crate::platform::{BlendMode, Color, LayerDraw, SpriteDraw};
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    /// Row-major RGBA, 4 bytes per pixel.
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    /// A new opaque black image.
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        for px in pixels.chunks_exact_mut(4) {
+            px[3] = 255;
+        }
+        Self { width, height, pixels }
+    }
+
+    /// A new fully see-through image, such as an empty layer.
+    pub fn transparent(width: u32, height: u32) -> Self {
+        Self { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    /// Wrap existing RGBA data; `pixels` must hold `width * height * 4` bytes.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+        (pixels.len() == width as usize * height as usize * 4).then_some(Self { width, height, pixels })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// RGBA at (x, y), or None outside the image.
+    pub fn pixel(&self, x: i64, y: i64) -> Option<[u8; 4]> {
+        let i = self.index(x, y)?;
+        let p = &self.pixels[i..i + 4];
+        Some([p[0], p[1], p[2], p[3]])
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return None;
+        }
+        Some((y as usize * self.width as usize + x as usize) * 4)
+    }
+
+    /// Set every pixel to `color`, alpha included.
+    pub fn clear(&mut self, color: Color) {
+        for px in self.pixels.chunks_exact_mut(4) {
+            px.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    /// Fill a rectangle, clipped to the image, blending see-through colors
+    /// over what's there.
+    pub fn fill_rect(&mut self, x: i64, y: i64, w: i64, h: i64, color: Color) {
+        self.blend_rect(x, y, w, h, color, BlendMode::Normal);
+    }
+
+    /// Fill a rectangle, clipped to the image, combined with what's there
+    /// as `mode` says.
+    pub fn blend_rect(&mut self, x: i64, y: i64, w: i64, h: i64, color: Color, mode: BlendMode) {
+        let x1 = x.max(0);
+        let y1 = y.max(0);
+        let x2 = (x + w).min(self.width as i64);
+        let y2 = (y + h).min(self.height as i64);
+        let opaque = mode == BlendMode::Replace || mode == BlendMode::Normal && color.a == 255;
+        if !opaque && color.a == 0 {
+            return;
+        }
+        for py in y1..y2 {
+            for px in x1..x2 {
+                let i = (py as usize * self.width as usize + px as usize) * 4;
+                let px = &mut self.pixels[i..i + 4];
+                if opaque {
+                    px.copy_from_slice(&[color.r, color.g, color.b, 255]);
+                    continue;
+                }
+                let blended = mode.blend(color, [px[0], px[1], px[2], px[3]]);
+                px.copy_from_slice(&blended);
+            }
+        }
+    }
+
+    /// Draw `src` with its top-left at (x, y), scaled with nearest-neighbour
+    /// sampling. Fully transparent source pixels are skipped.
+    pub fn blit(&mut self, src: &Framebuffer, x: i64, y: i64, scale: f64) {
+        if scale <= 0.0 {
+            return;
+        }
+        let w = (src.width as f64 * scale) as i64;
+        let h = (src.height as f64 * scale) as i64;
+        for dy in 0..h {
+            for dx in 0..w {
+                let Some(di) = self.index(x + dx, y + dy) else { continue };
+                let sx = ((dx as f64 / scale) as i64).min(src.width as i64 - 1);
+                let sy = ((dy as f64 / scale) as i64).min(src.height as i64 - 1);
+                let Some(si) = src.index(sx, sy) else { continue };
+                if src.pixels[si + 3] == 0 {
+                    continue;
+                }
+                self.pixels[di..di + 3].copy_from_slice(&src.pixels[si..si + 3]);
+                self.pixels[di + 3] = 255;
+            }
+        }
+    }
+
+    /// Draw `src` as `sprite` describes, with nearest-neighbour sampling.
+    /// Source alpha times `opacity` blends each pixel over the image.
+    pub fn draw_sprite(&mut self, src: &Framebuffer, sprite: &SpriteDraw) {
+        let SpriteDraw { x, y, w, h, rotation, opacity, flip_x, flip_y, source } = *sprite;
+        let [src_x, src_y, src_w, src_h] = source.unwrap_or([0, 0, src.width, src.height]);
+        let src_w = src_w.min(src.width.saturating_sub(src_x));
+        let src_h = src_h.min(src.height.saturating_sub(src_y));
+        if w <= 0.0 || h <= 0.0 || opacity <= 0.0 || src_w == 0 || src_h == 0 {
+            return;
+        }
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let (hw, hh) = (w / 2.0, h / 2.0);
+        // Screen area the rotated image can cover
+        let ex = hw * cos.abs() + hh * sin.abs();
+        let ey = hw * sin.abs() + hh * cos.abs();
+        let x1 = ((x - ex).floor() as i64).max(0);
+        let y1 = ((y - ey).floor() as i64).max(0);
+        let x2 = ((x + ex).ceil() as i64).min(self.width as i64);
+        let y2 = ((y + ey).ceil() as i64).min(self.height as i64);
+        let opacity = opacity.min(1.0);
+        for py in y1..y2 {
+            for px in x1..x2 {
+                // Pixel center, turned back into the unrotated image
+                let (dx, dy) = (px as f64 + 0.5 - x, py as f64 + 0.5 - y);
+                let lx = dx * cos + dy * sin + hw;
+                let ly = -dx * sin + dy * cos + hh;
+                if lx < 0.0 || ly < 0.0 || lx >= w || ly >= h {
+                    continue;
+                }
+                let (mut u, mut v) = (lx / w, ly / h);
+                if flip_x {
+                    u = 1.0 - u;
+                }
+                if flip_y {
+                    v = 1.0 - v;
+                }
+                let sx = src_x as i64 + ((u * src_w as f64) as i64).clamp(0, src_w as i64 - 1);
+                let sy = src_y as i64 + ((v * src_h as f64) as i64).clamp(0, src_h as i64 - 1);
+                let (Some(si), Some(di)) = (src.index(sx, sy), self.index(px, py)) else { continue };
+                let a = src.pixels[si + 3] as f64 / 255.0 * opacity;
+                if a <= 0.0 {
+                    continue;
+                }
+                self.blend_pixel(di, &src.pixels[si..si + 4], a);
+            }
+        }
+    }
+
+    /// Draw a layer's surface on this image as `layer` says: effects first,
+    /// then moved to (x, y) and blended over what's there by its opacity.
+    pub fn draw_surface(&mut self, src: &Framebuffer, layer: &LayerDraw) {
+        if layer.opacity <= 0.0 {
+            return;
+        }
+        let filtered;
+        let src = if layer.has_effects() {
+            filtered = src.filtered(layer);
+            &filtered
+        } else {
+            src
+        };
+        let (ox, oy) = (layer.x.round() as i64, layer.y.round() as i64);
+        let opacity = layer.opacity.min(1.0);
+        for sy in 0..src.height as i64 {
+            for sx in 0..src.width as i64 {
+                let Some(di) = self.index(sx + ox, sy + oy) else { continue };
+                let si = (sy as usize * src.width as usize + sx as usize) * 4;
+                let a = src.pixels[si + 3] as f64 / 255.0 * opacity;
+                if a > 0.0 {
+                    self.blend_pixel(di, &src.pixels[si..si + 4], a);
+                }
+            }
+        }
+    }
+
+    fn blend_pixel(&mut self, i: usize, src: &[u8], a: f64) {
+        let dst = &mut self.pixels[i..i + 4];
+        let blended = BlendMode::Normal.blend_rgb([src[0], src[1], src[2]], a, [dst[0], dst[1], dst[2], dst[3]]);
+        dst.copy_from_slice(&blended);
+    }
+
+    /// A copy with the layer's blur, grayscale and brightness applied.
+    fn filtered(&self, layer: &LayerDraw) -> Framebuffer {
+        let mut out = self.blurred(layer.blur.round().max(0.0) as usize);
+        let gray = layer.grayscale.clamp(0.0, 1.0);
+        let bright = layer.brightness.max(0.0);
+        for px in out.pixels.chunks_exact_mut(4) {
+            let luma = 0.2126 * px[0] as f64 + 0.7152 * px[1] as f64 + 0.0722 * px[2] as f64;
+            for c in &mut px[..3] {
+                let v = *c as f64;
+                *c = ((v + (luma - v) * gray) * bright).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        out
+    }
+
+    /// A box blur `radius` pixels each way, run across then down. Colors
+    /// are weighted by alpha so see-through pixels don't darken the edges.
+    fn blurred(&self, radius: usize) -> Framebuffer {
+        if radius == 0 {
+            return self.clone();
+        }
+        let (w, h) = (self.width as usize, self.height as usize);
+        let mut sums: Vec<[f32; 4]> = self
+            .pixels
+            .chunks_exact(4)
+            .map(|p| {
+                let a = p[3] as f32 / 255.0;
+                [p[0] as f32 * a, p[1] as f32 * a, p[2] as f32 * a, p[3] as f32]
+            })
+            .collect();
+        let span = (2 * radius + 1) as f32;
+        // One pass along lines of `len` pixels, `step` apart in `sums`
+        let pass = |sums: &mut Vec<[f32; 4]>, lines: usize, len: usize, start: &dyn Fn(usize) -> usize, step: usize| {
+            let mut line = vec![[0.0f32; 4]; len];
+            for l in 0..lines {
+                let at = |i: usize| start(l) + i * step;
+                let mut acc = [0.0f32; 4];
+                for i in 0..radius.min(len) {
+                    add(&mut acc, sums[at(i)], 1.0);
+                }
+                for (i, out) in line.iter_mut().enumerate() {
+                    if i + radius < len {
+                        add(&mut acc, sums[at(i + radius)], 1.0);
+                    }
+                    if i > radius {
+                        add(&mut acc, sums[at(i - radius - 1)], -1.0);
+                    }
+                    *out = acc.map(|v| v / span);
+                }
+                for (i, v) in line.iter().enumerate() {
+                    sums[at(i)] = *v;
+                }
+            }
+        };
+        pass(&mut sums, h, w, &|row| row * w, 1);
+        pass(&mut sums, w, h, &|col| col, w);
+        let mut out = Framebuffer::transparent(self.width, self.height);
+        for (px, s) in out.pixels.chunks_exact_mut(4).zip(&sums) {
+            let a = s[3] / 255.0;
+            if a > 0.0 {
+                for c in 0..3 {
+                    px[c] = (s[c] / a).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            px[3] = s[3].round().clamp(0.0, 255.0) as u8;
+        }
+        out
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&self.pixels).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(io::BufReader::new(std::fs::File::open(path)?));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(io::Error::other)?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(io::Error::other)?;
+        buf.truncate(info.buffer_size());
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buf,
+            png::ColorType::Rgb => buf.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buf.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            png::ColorType::Indexed => return Err(io::Error::other("unexpanded indexed PNG")),
+        };
+        Ok(Self { width: info.width, height: info.height, pixels })
+    }
+
+    /// Load an uncompressed 24- or 32-bit BMP, the format `Screen.SpriteLoad` documents.
+    pub fn load_bmp(path: impl AsRef<Path>) -> io::Result<Self> {
+        let data = std::fs::read(path)?;
+        let bad = || io::Error::new(io::ErrorKind::InvalidData, "unsupported BMP");
+        let u32_at = |i: usize| data.get(i..i + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        let u16_at = |i: usize| data.get(i..i + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        if data.get(0..2) != Some(b"BM") {
+            return Err(bad());
+        }
+        let offset = u32_at(10).ok_or_else(bad)? as usize;
+        let width = u32_at(18).ok_or_else(bad)? as i32;
+        let height = u32_at(22).ok_or_else(bad)? as i32;
+        let bpp = u16_at(28).ok_or_else(bad)?;
+        let compression = u32_at(30).ok_or_else(bad)?;
+        // BI_RGB, or BI_BITFIELDS with the usual BGRA masks
+        if !(bpp == 24 && compression == 0 || bpp == 32 && (compression == 0 || compression == 3)) {
+            return Err(bad());
+        }
+        let (w, h) = (width.unsigned_abs(), height.unsigned_abs());
+        let bytes_pp = bpp as usize / 8;
+        let stride = (w as usize * bytes_pp).div_ceil(4) * 4;
+        let mut fb = Self::new(w, h);
+        for row in 0..h as usize {
+            // Positive height means rows are stored bottom-up
+            let src_row = if height > 0 { h as usize - 1 - row } else { row };
+            let start = offset + src_row * stride;
+            let line = data.get(start..start + w as usize * bytes_pp).ok_or_else(bad)?;
+            for (col, p) in line.chunks_exact(bytes_pp).enumerate() {
+                let a = if bytes_pp == 4 { p[3] } else { 255 };
+                let i = (row * w as usize + col) * 4;
+                fb.pixels[i..i + 4].copy_from_slice(&[p[2], p[1], p[0], a]);
+            }
+        }
+        Ok(fb)
+    }
+
+    /// Compare against `expected`. A pixel counts as different when its
+    /// perceptual distance (YIQ, 0 = identical, 1 = black vs white) is above
+    /// `tolerance`. None if the sizes differ.
+    pub fn compare(&self, expected: &Framebuffer, tolerance: f64) -> Option<ImageDiff> {
+        if (self.width, self.height) != (expected.width, expected.height) {
+            return None;
+        }
+        let mut image = Framebuffer::new(self.width, self.height);
+        let mut differing = 0;
+        let pairs = self.pixels.chunks_exact(4).zip(expected.pixels.chunks_exact(4));
+        for ((a, e), out) in pairs.zip(image.pixels.chunks_exact_mut(4)) {
+            if color_distance(a, e) > tolerance {
+                differing += 1;
+                out.copy_from_slice(&[255, 0, 0, 255]);
+            } else {
+                // Faded copy of the expected image, so the red stands out
+                let y = (luma(e) * 0.1 + 255.0 * 0.9) as u8;
+                out.copy_from_slice(&[y, y, y, 255]);
+            }
+        }
+        Some(ImageDiff { differing, total: self.pixels.len() / 4, image })
+    }
+
+    /// Load a PNG or BMP, picked by the file's magic bytes.
+    pub fn load_image(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut magic = [0u8; 2];
+        io::Read::read_exact(&mut std::fs::File::open(path)?, &mut magic)?;
+        if &magic == b"BM" { Self::load_bmp(path) } else { Self::load_png(path) }
+    }
+}
+
+/// Result of [`Framebuffer::compare`].
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// Pixels over the tolerance.
+    pub differing: usize,
+    pub total: usize,
+    /// Differing pixels in red over a faded copy of the expected image.
+    pub image: Framebuffer,
+}
+
+fn luma(p: &[u8]) -> f64 {
+    0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64
+}
+
+/// Perceptual distance between two RGBA pixels in YIQ space, scaled so that
+/// black vs white is 1.0 (the metric pixelmatch uses).
+fn color_distance(a: &[u8], b: &[u8]) -> f64 {
+    let yiq = |p: &[u8]| {
+        let (r, g, b) = (p[0] as f64, p[1] as f64, p[2] as f64);
+        (
+            luma(p),
+            0.596 * r - 0.274 * g - 0.322 * b,
+            0.211 * r - 0.523 * g + 0.312 * b,
+        )
+    };
+    let (ya, ia, qa) = yiq(a);
+    let (yb, ib, qb) = yiq(b);
+    let (dy, di, dq) = (ya - yb, ia - ib, qa - qb);
+    ((0.5053 * dy * dy + 0.299 * di * di + 0.1957 * dq * dq) / 35215.0).sqrt()
+}
+
+fn add(acc: &mut [f32; 4], v: [f32; 4], sign: f32) {
+    for (a, v) in acc.iter_mut().zip(v) {
+        *a += v * sign;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_rect_is_clipped() {
+        let mut fb = Framebuffer::new(4, 4);
+        fb.fill_rect(-2, 2, 4, 10, Color::rgb(255, 0, 0));
+        assert_eq!(fb.pixel(0, 2), Some([255, 0, 0, 255]));
+        assert_eq!(fb.pixel(1, 3), Some([255, 0, 0, 255]));
+        assert_eq!(fb.pixel(2, 2), Some([0, 0, 0, 255]));
+        assert_eq!(fb.pixel(0, 1), Some([0, 0, 0, 255]));
+        assert_eq!(fb.pixel(4, 0), None);
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let mut fb = Framebuffer::new(4, 1);
+        fb.clear(Color::rgb(100, 100, 100));
+        let half_red = Color::rgba(255, 0, 0, 128);
+        fb.blend_rect(0, 0, 1, 1, half_red, BlendMode::Normal);
+        fb.blend_rect(1, 0, 1, 1, half_red, BlendMode::Add);
+        fb.blend_rect(2, 0, 1, 1, half_red, BlendMode::Multiply);
+        fb.blend_rect(3, 0, 1, 1, half_red, BlendMode::Replace);
+        assert_eq!(fb.pixel(0, 0), Some([178, 50, 50, 255]));
+        assert_eq!(fb.pixel(1, 0), Some([228, 100, 100, 255]));
+        assert_eq!(fb.pixel(2, 0), Some([100, 50, 50, 255]));
+        assert_eq!(fb.pixel(3, 0), Some([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_layer_composites_like_drawing_directly() {
+        let half_red = Color::rgba(255, 0, 0, 128);
+        let half_blue = Color::rgba(0, 0, 255, 100);
+        let mut direct = Framebuffer::new(3, 1);
+        direct.clear(Color::rgb(100, 100, 100));
+        let mut screen = direct.clone();
+        let mut layer = Framebuffer::transparent(3, 1);
+        for fb in [&mut direct, &mut layer] {
+            fb.fill_rect(0, 0, 2, 1, half_red);
+            fb.fill_rect(1, 0, 2, 1, half_blue);
+        }
+        assert_eq!(layer.pixel(0, 0), Some([255, 0, 0, 128]));
+        screen.draw_surface(&layer, &LayerDraw::default());
+        for x in 0..3 {
+            let (a, b) = (screen.pixel(x, 0).unwrap(), direct.pixel(x, 0).unwrap());
+            assert!(a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 1), "{x}: {a:?} vs {b:?}");
+        }
+    }
+
+    #[test]
+    fn test_draw_surface_offsets_fades_and_filters() {
+        let mut layer = Framebuffer::transparent(5, 1);
+        layer.fill_rect(2, 0, 1, 1, Color::rgb(200, 100, 0));
+        let at = |draw: LayerDraw| {
+            let mut screen = Framebuffer::new(5, 1);
+            screen.draw_surface(&layer, &draw);
+            screen
+        };
+        let moved = at(LayerDraw { x: 1.0, opacity: 0.5, ..LayerDraw::default() });
+        assert_eq!(moved.pixel(3, 0), Some([100, 50, 0, 255]));
+        assert_eq!(moved.pixel(2, 0), Some([0, 0, 0, 255]));
+        let gray = at(LayerDraw { grayscale: 1.0, brightness: 0.5, ..LayerDraw::default() });
+        let [r, g, b, _] = gray.pixel(2, 0).unwrap();
+        assert!(r == g && g == b && r == 57, "{r} {g} {b}");
+        // A one-pixel blur shares the dot with its 3×3 box, empty rows
+        // above and below included, keeping its hue
+        let blurred = at(LayerDraw { blur: 1.0, ..LayerDraw::default() });
+        assert_eq!(blurred.pixel(1, 0), Some([22, 11, 0, 255]));
+        assert_eq!(blurred.pixel(3, 0), Some([22, 11, 0, 255]));
+        assert_eq!(blurred.pixel(0, 0), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let mut fb = Framebuffer::new(3, 2);
+        fb.fill_rect(1, 0, 1, 2, Color::rgb(10, 200, 30));
+        let path = std::env::temp_dir().join(format!("gbasic_fb_{}.png", std::process::id()));
+        fb.save_png(&path).unwrap();
+        let loaded = Framebuffer::load_image(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded, fb);
+    }
+
+    #[test]
+    fn test_blit_scales_and_skips_transparent() {
+        let mut src = Framebuffer::from_rgba(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 0]).unwrap();
+        src.pixels[4..8].copy_from_slice(&[0, 0, 255, 0]);
+        let mut dst = Framebuffer::new(4, 2);
+        dst.blit(&src, 0, 0, 2.0);
+        assert_eq!(dst.pixel(1, 1), Some([255, 0, 0, 255]));
+        assert_eq!(dst.pixel(2, 0), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_draw_sprite_flips_rotates_and_fades() {
+        // Red on the left, blue on the right
+        let src = Framebuffer::from_rgba(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+        let at = |rotation: f64, opacity: f64, flip_x: bool| {
+            let mut dst = Framebuffer::new(4, 4);
+            let sprite = SpriteDraw { x: 2.0, y: 2.0, w: 4.0, h: 2.0, rotation, opacity, flip_x, flip_y: false, source: None };
+            dst.draw_sprite(&src, &sprite);
+            dst
+        };
+        let plain = at(0.0, 1.0, false);
+        assert_eq!(plain.pixel(0, 1), Some([255, 0, 0, 255]));
+        assert_eq!(plain.pixel(3, 2), Some([0, 0, 255, 255]));
+        assert_eq!(plain.pixel(0, 0), Some([0, 0, 0, 255]));
+        assert_eq!(at(0.0, 1.0, true).pixel(0, 1), Some([0, 0, 255, 255]));
+        // Quarter turn clockwise: the left (red) half ends up on top
+        let turned = at(90.0, 1.0, false);
+        assert_eq!(turned.pixel(1, 0), Some([255, 0, 0, 255]));
+        assert_eq!(turned.pixel(2, 3), Some([0, 0, 255, 255]));
+        assert_eq!(turned.pixel(0, 1), Some([0, 0, 0, 255]));
+        assert_eq!(at(0.0, 0.5, false).pixel(0, 1), Some([128, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_draw_sprite_source_rect() {
+        // Two 1×1 frames: red, then blue
+        let src = Framebuffer::from_rgba(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+        let mut dst = Framebuffer::new(2, 2);
+        let sprite = SpriteDraw {
+            x: 1.0, y: 1.0, w: 2.0, h: 2.0, rotation: 0.0, opacity: 1.0,
+            flip_x: false, flip_y: false, source: Some([1, 0, 1, 1]),
+        };
+        dst.draw_sprite(&src, &sprite);
+        assert_eq!(dst.pixel(0, 0), Some([0, 0, 255, 255]));
+        assert_eq!(dst.pixel(1, 1), Some([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_compare_tolerance() {
+        let mut a = Framebuffer::new(4, 4);
+        let mut b = a.clone();
+        a.fill_rect(0, 0, 1, 1, Color::rgb(250, 250, 250));
+        b.fill_rect(0, 0, 1, 1, Color::rgb(255, 255, 255));
+        b.fill_rect(3, 3, 1, 1, Color::rgb(255, 0, 0));
+        let diff = a.compare(&b, 0.05).unwrap();
+        assert_eq!((diff.differing, diff.total), (1, 16));
+        assert_eq!(diff.image.pixel(3, 3), Some([255, 0, 0, 255]));
+        assert_eq!(a.compare(&b, 1.0).unwrap().differing, 0);
+        assert!(a.compare(&Framebuffer::new(2, 2), 0.0).is_none());
+    }
+}
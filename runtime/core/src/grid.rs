@@ -0,0 +1,182 @@
+//! 2D grids of Ints for board and maze games, with flood fill and A*
+//! pathfinding.
+//!
+//! Cells are numbered from (0, 0) at the top left, `x` across and `y`
+//! down. Paths move between side-by-side cells, never diagonally, and go
+//! only through cells holding 0.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<i64>,
+}
+
+/// Offsets of the cells next to one: up, right, down, left.
+const SIDES: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl Grid {
+    /// A `width` × `height` grid of zeros.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, cells: vec![0; width * height] }
+    }
+
+    fn slot(&self, x: i64, y: i64) -> Option<usize> {
+        let inside = (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y);
+        inside.then(|| y as usize * self.width + x as usize)
+    }
+
+    /// The value at (x, y), or None outside the grid.
+    pub fn get(&self, x: i64, y: i64) -> Option<i64> {
+        self.slot(x, y).map(|i| self.cells[i])
+    }
+
+    /// Change the value at (x, y); false, changing nothing, outside the grid.
+    pub fn set(&mut self, x: i64, y: i64, value: i64) -> bool {
+        self.slot(x, y).map(|i| self.cells[i] = value).is_some()
+    }
+
+    pub fn fill(&mut self, value: i64) {
+        self.cells.fill(value);
+    }
+
+    /// The cells beside (x, y) that are inside the grid: up, right, down,
+    /// left.
+    pub fn neighbors(&self, x: i64, y: i64) -> Vec<(i64, i64)> {
+        SIDES.iter().map(|(dx, dy)| (x + dx, y + dy)).filter(|&(nx, ny)| self.slot(nx, ny).is_some()).collect()
+    }
+
+    /// Set every cell joined to (x, y) through side-by-side cells of the
+    /// same value to `value`, like a paint bucket; returns how many changed.
+    pub fn flood_fill(&mut self, x: i64, y: i64, value: i64) -> usize {
+        let Some(old) = self.get(x, y) else { return 0 };
+        if old == value {
+            return 0;
+        }
+        let mut todo = vec![(x, y)];
+        let mut changed = 0;
+        while let Some((cx, cy)) = todo.pop() {
+            if self.get(cx, cy) != Some(old) {
+                continue;
+            }
+            self.set(cx, cy, value);
+            changed += 1;
+            todo.extend(self.neighbors(cx, cy));
+        }
+        changed
+    }
+
+    /// The shortest way from `start` to `goal` through open (0) cells,
+    /// both ends included, or None if there is none. The start cell itself
+    /// needn't be open, so whatever stands there can still find its way.
+    pub fn path(&self, start: (i64, i64), goal: (i64, i64)) -> Option<Vec<(i64, i64)>> {
+        let from = self.slot(start.0, start.1)?;
+        let to = self.slot(goal.0, goal.1)?;
+        if from != to && self.cells[to] != 0 {
+            return None;
+        }
+        let position = |i: usize| ((i % self.width) as i64, (i / self.width) as i64);
+        let estimate = |i: usize| {
+            let (x, y) = position(i);
+            (x - goal.0).unsigned_abs() + (y - goal.1).unsigned_abs()
+        };
+        let mut came_from = vec![usize::MAX; self.cells.len()];
+        let mut cost = vec![u64::MAX; self.cells.len()];
+        // Lowest estimated total first; ties go to the cell found first
+        let mut open = BinaryHeap::new();
+        let mut found = 0u64;
+        cost[from] = 0;
+        open.push(Reverse((estimate(from), found, from)));
+        while let Some(Reverse((_, _, current))) = open.pop() {
+            if current == to {
+                let mut steps = vec![position(to)];
+                let mut at = to;
+                while at != from {
+                    at = came_from[at];
+                    steps.push(position(at));
+                }
+                steps.reverse();
+                return Some(steps);
+            }
+            let (x, y) = position(current);
+            for (nx, ny) in self.neighbors(x, y) {
+                let next = self.slot(nx, ny).unwrap();
+                if self.cells[next] != 0 || cost[current] + 1 >= cost[next] {
+                    continue;
+                }
+                cost[next] = cost[current] + 1;
+                came_from[next] = current;
+                found += 1;
+                open.push(Reverse((cost[next] + estimate(next), found, next)));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid drawn as rows of text: `#` is 1, anything else 0.
+    fn maze(rows: &[&str]) -> Grid {
+        let mut grid = Grid::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                grid.set(x as i64, y as i64, (c == '#') as i64);
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_get_set_fill_and_neighbors() {
+        let mut grid = Grid::new(3, 2);
+        assert!(grid.set(2, 1, 7));
+        assert!(!grid.set(3, 0, 7));
+        assert_eq!((grid.get(2, 1), grid.get(0, 0), grid.get(-1, 0)), (Some(7), Some(0), None));
+        grid.fill(4);
+        assert_eq!(grid.get(2, 1), Some(4));
+        assert_eq!(grid.neighbors(0, 0), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors(1, 1), vec![(1, 0), (2, 1), (0, 1)]);
+    }
+
+    #[test]
+    fn test_flood_fill_stays_inside_walls() {
+        let mut grid = maze(&[
+            "..#..",
+            "..#..",
+            "###..",
+        ]);
+        assert_eq!(grid.flood_fill(0, 0, 5), 4);
+        assert_eq!(grid.get(1, 1), Some(5));
+        assert_eq!(grid.get(3, 0), Some(0));
+        assert_eq!(grid.flood_fill(0, 0, 5), 0);
+        assert_eq!(grid.flood_fill(9, 9, 5), 0);
+    }
+
+    #[test]
+    fn test_path_goes_around_walls() {
+        let grid = maze(&[
+            "S.#..",
+            ".##.#",
+            "....G",
+        ]);
+        let path = grid.path((0, 0), (4, 2)).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (3, 2), (4, 2)]);
+        assert_eq!(grid.path((0, 0), (0, 0)), Some(vec![(0, 0)]));
+        // The long way round, up through the gap beside the goal
+        assert_eq!(grid.path((0, 0), (3, 0)).map(|p| p.len()), Some(8));
+        // Onto a wall, off the grid, or walled off
+        assert_eq!(grid.path((0, 0), (2, 0)), None);
+        assert_eq!(grid.path((0, 0), (5, 0)), None);
+        let walled = maze(&[
+            ".#.",
+            "##.",
+        ]);
+        assert_eq!(walled.path((0, 0), (2, 1)), None);
+    }
+}
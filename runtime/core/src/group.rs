@@ -0,0 +1,175 @@
+//! Parent/child groups: after `sword.parent = player`, the sword moves
+//! whenever the player does, and goes when the player is removed.
+//!
+//! Children keep their own positions. Once a frame, [`follow_parents`] moves
+//! each one by however far its parent moved since the last time, so a child
+//! can still move on its own and physics and tweens can move a parent.
+
+use crate::store::ObjectStore;
+
+/// How many parents up an object is from one without a parent (0), or None
+/// if its chain of parents loops.
+fn depth(store: &ObjectStore, index: usize) -> Option<usize> {
+    let slots = store.slots();
+    let mut current = index;
+    for depth in 0..=slots.len() {
+        match slots[current].parent.and_then(|p| store.index(p).ok()) {
+            Some(parent) => current = parent,
+            None => return Some(depth),
+        }
+    }
+    None
+}
+
+/// Move every child by however far its parent moved since the last call,
+/// grandchildren by their parent's move plus their grandparent's, and so on.
+pub fn follow_parents(store: &mut ObjectStore) {
+    let mut order: Vec<(usize, usize)> = (0..store.slots().len())
+        .filter(|&i| store.slots()[i].alive)
+        .filter_map(|i| depth(store, i).map(|d| (d, i)))
+        .collect();
+    // Parents before their children, so a child sees its parent's full move
+    order.sort_unstable();
+    let parents: Vec<Option<usize>> =
+        store.slots().iter().map(|o| o.parent.and_then(|p| store.index(p).ok())).collect();
+    let slots = store.slots_mut();
+    let mut moved = vec![(0.0, 0.0); slots.len()];
+    for &(_, i) in &order {
+        if let Some(p) = parents[i] {
+            let (dx, dy) = moved[p];
+            slots[i].x += dx;
+            slots[i].y += dy;
+        }
+        let o = &slots[i];
+        moved[i] = (o.x - o.followed.0, o.y - o.followed.1);
+    }
+    for &(_, i) in &order {
+        slots[i].followed = (slots[i].x, slots[i].y);
+    }
+}
+
+/// Make `parent` the object `child` moves with, or, with None, let it move
+/// on its own again.
+pub fn set_parent(store: &mut ObjectStore, child: i64, parent: Option<i64>) -> Result<(), String> {
+    let index = store.index(child).map_err(|e| e.to_string())?;
+    if let Some(parent) = parent {
+        let mut ancestor = Some(parent);
+        while let Some(handle) = ancestor {
+            if handle == child {
+                return Err("an object can't be its own parent, even through its children".into());
+            }
+            ancestor = store.get(handle).map_err(|e| format!("can't be the parent: {e}"))?.parent;
+        }
+    }
+    // Moves made before joining aren't the child's to follow
+    follow_parents(store);
+    store.slots_mut()[index].parent = parent;
+    Ok(())
+}
+
+/// Handles of the objects whose parent is `handle`, oldest first.
+pub fn children(store: &ObjectStore, handle: i64) -> Vec<i64> {
+    let mut found: Vec<usize> = (0..store.slots().len())
+        .filter(|&i| store.slots()[i].alive && store.slots()[i].parent == Some(handle))
+        .collect();
+    found.sort_by_key(|&i| store.serial(i));
+    found.into_iter().filter_map(|i| store.handle_of(i)).collect()
+}
+
+/// A live object followed by its children, theirs, and so on; empty if
+/// the handle doesn't name a live object.
+pub fn family(store: &ObjectStore, handle: i64) -> Vec<i64> {
+    if !store.is_alive(handle) {
+        return Vec::new();
+    }
+    let mut found = vec![handle];
+    let mut next = 0;
+    while next < found.len() {
+        found.extend(children(store, found[next]));
+        next += 1;
+    }
+    found
+}
+
+/// Remove an object together with its children, theirs, and so on.
+pub fn remove(store: &mut ObjectStore, handle: i64) -> Result<(), String> {
+    store.index(handle).map_err(|e| e.to_string())?;
+    for member in family(store, handle) {
+        let _ = store.remove(member);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::{GameObject, ObjectKind};
+
+    fn rect_at(store: &mut ObjectStore, x: f64, y: f64) -> i64 {
+        let mut o = GameObject::new(ObjectKind::Rect, 10.0, 10.0);
+        (o.x, o.y) = (x, y);
+        store.insert(o)
+    }
+
+    fn position(store: &ObjectStore, handle: i64) -> (f64, f64) {
+        let o = store.get(handle).unwrap();
+        (o.x, o.y)
+    }
+
+    #[test]
+    fn test_children_follow_parents_moves() {
+        let mut store = ObjectStore::new();
+        let ship = rect_at(&mut store, 100.0, 100.0);
+        let turret = rect_at(&mut store, 110.0, 90.0);
+        let barrel = rect_at(&mut store, 115.0, 80.0);
+        set_parent(&mut store, turret, Some(ship)).unwrap();
+        set_parent(&mut store, barrel, Some(turret)).unwrap();
+
+        store.get_mut(ship).unwrap().x += 50.0;
+        store.get_mut(turret).unwrap().y -= 5.0;
+        follow_parents(&mut store);
+        assert_eq!(position(&store, ship), (150.0, 100.0));
+        assert_eq!(position(&store, turret), (160.0, 85.0));
+        assert_eq!(position(&store, barrel), (165.0, 75.0));
+
+        // Nothing moved since: nothing follows again
+        follow_parents(&mut store);
+        assert_eq!(position(&store, barrel), (165.0, 75.0));
+
+        set_parent(&mut store, turret, None).unwrap();
+        store.get_mut(ship).unwrap().x += 50.0;
+        follow_parents(&mut store);
+        assert_eq!(position(&store, turret), (160.0, 85.0));
+    }
+
+    #[test]
+    fn test_joining_doesnt_replay_earlier_moves() {
+        let mut store = ObjectStore::new();
+        let ship = rect_at(&mut store, 0.0, 0.0);
+        store.get_mut(ship).unwrap().x = 300.0;
+        let flag = rect_at(&mut store, 300.0, -20.0);
+        set_parent(&mut store, flag, Some(ship)).unwrap();
+        follow_parents(&mut store);
+        assert_eq!(position(&store, flag), (300.0, -20.0));
+    }
+
+    #[test]
+    fn test_no_loops_and_removal_takes_children() {
+        let mut store = ObjectStore::new();
+        let a = rect_at(&mut store, 0.0, 0.0);
+        let b = rect_at(&mut store, 0.0, 0.0);
+        let c = rect_at(&mut store, 0.0, 0.0);
+        let other = rect_at(&mut store, 0.0, 0.0);
+        set_parent(&mut store, b, Some(a)).unwrap();
+        set_parent(&mut store, c, Some(b)).unwrap();
+        assert!(set_parent(&mut store, a, Some(c)).is_err());
+        assert!(set_parent(&mut store, a, Some(a)).is_err());
+        assert_eq!(children(&store, a), vec![b]);
+        assert_eq!(family(&store, a), vec![a, b, c]);
+
+        remove(&mut store, a).unwrap();
+        assert!(!store.is_alive(b) && !store.is_alive(c));
+        assert!(store.is_alive(other));
+        assert!(remove(&mut store, a).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! A backend with no window, no audio and no sleeping.
+//!
+//! The screen is a [`Framebuffer`] and time is virtual: waiting for the next
+//! frame just moves the clock forward, so a run is as fast as the CPU allows
+//! and identical every time — including the default random seed, which comes
+//! from the (virtual) clock.
+
+use crate::framebuffer::Framebuffer;
+use crate::platform::*;
+
+#[derive(Default)]
+pub struct HeadlessPlatform {
+    screen: Option<Framebuffer>,
+    images: Vec<Framebuffer>,
+    /// Offscreen layers, and which one drawing goes to (None = the screen).
+    surfaces: Vec<Framebuffer>,
+    target: Option<usize>,
+    blend: BlendMode,
+    /// Virtual clock in seconds.
+    time: f64,
+}
+
+impl HeadlessPlatform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn target(&mut self) -> Option<&mut Framebuffer> {
+        match self.target {
+            Some(i) => self.surfaces.get_mut(i),
+            None => self.screen.as_mut(),
+        }
+    }
+}
+
+impl Renderer for HeadlessPlatform {
+    fn open_screen(&mut self, width: i64, height: i64) {
+        self.screen = Some(Framebuffer::new(width.max(0) as u32, height.max(0) as u32));
+    }
+
+    fn clear(&mut self, color: Color) {
+        if let Some(fb) = self.target() {
+            fb.clear(color);
+        }
+    }
+
+    fn fill_rect(&mut self, x: i64, y: i64, w: i64, h: i64, color: Color) {
+        let blend = self.blend;
+        if let Some(fb) = self.target() {
+            fb.blend_rect(x, y, w, h, color, blend);
+        }
+    }
+
+    fn set_blend(&mut self, mode: BlendMode) {
+        self.blend = mode;
+    }
+
+    fn load_image(&mut self, path: &str) -> Option<usize> {
+        let image = Framebuffer::load_image(path).ok()?;
+        self.images.push(image);
+        Some(self.images.len() - 1)
+    }
+
+    fn upload_image(&mut self, image: Option<usize>, pixels: Framebuffer) -> usize {
+        match image {
+            Some(i) if i < self.images.len() => {
+                self.images[i] = pixels;
+                i
+            }
+            _ => {
+                self.images.push(pixels);
+                self.images.len() - 1
+            }
+        }
+    }
+
+    fn image_size(&self, image: usize) -> Option<(u32, u32)> {
+        self.images.get(image).map(|img| (img.width(), img.height()))
+    }
+
+    fn draw_sprite(&mut self, image: usize, sprite: &SpriteDraw) {
+        let fb = match self.target {
+            Some(i) => self.surfaces.get_mut(i),
+            None => self.screen.as_mut(),
+        };
+        if let (Some(fb), Some(img)) = (fb, self.images.get(image)) {
+            fb.draw_sprite(img, sprite);
+        }
+    }
+
+    fn create_surface(&mut self) -> usize {
+        let (w, h) = self.screen.as_ref().map_or((0, 0), |fb| (fb.width(), fb.height()));
+        self.surfaces.push(Framebuffer::transparent(w, h));
+        self.surfaces.len() - 1
+    }
+
+    fn set_target(&mut self, surface: Option<usize>) {
+        self.target = surface;
+    }
+
+    fn draw_surface(&mut self, surface: usize, layer: &LayerDraw) {
+        if surface >= self.surfaces.len() || self.target == Some(surface) {
+            return;
+        }
+        // Lend the surface out so the target can be borrowed alongside it
+        let src = std::mem::replace(&mut self.surfaces[surface], Framebuffer::transparent(0, 0));
+        if let Some(fb) = self.target() {
+            fb.draw_surface(&src, layer);
+        }
+        self.surfaces[surface] = src;
+    }
+
+    fn present(&mut self) {}
+
+    fn snapshot(&mut self) -> Option<Framebuffer> {
+        self.screen.clone()
+    }
+}
+
+impl AudioSink for HeadlessPlatform {
+    fn beep(&mut self, _freq: i64, _duration_ms: i64) {}
+
+    fn load_effect(&mut self, _path: &str) -> bool {
+        true
+    }
+
+    fn play_effect(&mut self, _path: &str) {}
+
+    fn set_effect_volume(&mut self, _path: &str, _volume: f64) {}
+}
+
+impl InputSource for HeadlessPlatform {
+    fn poll(&mut self, _input: &mut InputState) {}
+}
+
+impl Platform for HeadlessPlatform {
+    fn now(&self) -> f64 {
+        self.time
+    }
+
+    fn wait_until(&mut self, deadline: f64) {
+        self.time = self.time.max(deadline);
+    }
+
+    /// Virtual too, so clock-based seeds repeat from run to run.
+    fn wall_time(&self) -> f64 {
+        self.time
+    }
+
+    fn sleep_ms(&mut self, ms: i64) {
+        self.time += ms.max(0) as f64 / 1000.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_before_open_are_ignored() {
+        let mut p = HeadlessPlatform::new();
+        p.fill_rect(0, 0, 10, 10, Color::rgb(255, 0, 0));
+        assert!(p.snapshot().is_none());
+        p.open_screen(20, 10);
+        p.fill_circle(10, 5, 3, Color::rgb(0, 255, 0));
+        let fb = p.snapshot().unwrap();
+        assert_eq!(fb.pixel(10, 5), Some([0, 255, 0, 255]));
+        assert_eq!(fb.pixel(0, 0), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_fill_polygon_samples_pixel_centers() {
+        let mut p = HeadlessPlatform::new();
+        p.open_screen(10, 10);
+        let red = Color::rgb(255, 0, 0);
+        // A diamond: its corners are empty, its middle row full
+        p.fill_polygon(&[(5.0, 0.0), (10.0, 5.0), (5.0, 10.0), (0.0, 5.0)], red);
+        let fb = p.snapshot().unwrap();
+        assert_eq!(fb.pixel(0, 0), Some([0, 0, 0, 255]));
+        assert_eq!(fb.pixel(4, 0), Some([255, 0, 0, 255]));
+        assert_eq!(fb.pixel(3, 0), Some([0, 0, 0, 255]));
+        assert_eq!(fb.pixel(0, 4), Some([255, 0, 0, 255]));
+        assert_eq!(fb.pixel(8, 5), Some([255, 0, 0, 255]));
+        assert_eq!(fb.pixel(9, 5), Some([0, 0, 0, 255]));
+        assert_eq!(fb.pixel(9, 9), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_see_through_shapes_blend_each_pixel_once() {
+        let mut p = HeadlessPlatform::new();
+        p.open_screen(20, 20);
+        let half_white = Color::rgba(255, 255, 255, 128);
+        p.fill_circle(10, 10, 6, half_white);
+        p.stroke_rect(0, 0, 20, 20, 2, half_white);
+        let fb = p.snapshot().unwrap();
+        // The midpoint algorithm covers the middle rows from several octants
+        assert_eq!(fb.pixel(10, 10), Some([128, 128, 128, 255]));
+        assert_eq!(fb.pixel(16, 10), Some([128, 128, 128, 255]));
+        assert_eq!(fb.pixel(10, 16), Some([128, 128, 128, 255]));
+        assert_eq!(fb.pixel(0, 0), Some([128, 128, 128, 255]));
+        assert_eq!(fb.pixel(1, 10), Some([128, 128, 128, 255]));
+        assert_eq!(fb.pixel(2, 2), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_smooth_lines_and_circles() {
+        let mut p = HeadlessPlatform::new();
+        p.open_screen(20, 20);
+        let white = Color::rgb(255, 255, 255);
+        let gray = |fb: &Framebuffer, x, y| fb.pixel(x, y).unwrap()[0];
+        // Three pixels wide and horizontal: rows 4..=6, round ends
+        p.draw_thick_line((2.5, 5.5), (12.5, 5.5), 3.0, false, white);
+        let fb = p.snapshot().unwrap();
+        assert_eq!((gray(&fb, 7, 3), gray(&fb, 7, 4), gray(&fb, 7, 6), gray(&fb, 7, 7)), (0, 255, 255, 0));
+        assert_eq!((gray(&fb, 1, 5), gray(&fb, 0, 5), gray(&fb, 14, 5)), (255, 0, 0));
+
+        p.clear(Color::rgb(0, 0, 0));
+        p.fill_circle_smooth((10.0, 10.0), 5.0, true, white);
+        let fb = p.snapshot().unwrap();
+        assert_eq!(gray(&fb, 10, 10), 255);
+        // Pixel centers near the edge get partial coverage, far ones none
+        let edge = gray(&fb, 14, 10);
+        assert!(edge > 0 && edge < 255, "{edge}");
+        assert_eq!(gray(&fb, 16, 10), 0);
+
+        p.clear(Color::rgb(0, 0, 0));
+        p.stroke_circle((10.0, 10.0), 5.0, 1.0, false, white);
+        let fb = p.snapshot().unwrap();
+        assert_eq!((gray(&fb, 14, 9), gray(&fb, 13, 9), gray(&fb, 10, 10)), (255, 0, 0));
+    }
+
+    #[test]
+    fn test_gradient_runs_between_colors() {
+        let mut p = HeadlessPlatform::new();
+        p.open_screen(5, 3);
+        p.fill_gradient((0, 0, 5, 3), Color::rgb(0, 0, 0), Color::rgb(200, 0, 100), false);
+        let fb = p.snapshot().unwrap();
+        assert_eq!(fb.pixel(0, 1), Some([0, 0, 0, 255]));
+        assert_eq!(fb.pixel(2, 1), Some([100, 0, 50, 255]));
+        assert_eq!(fb.pixel(4, 2), Some([200, 0, 100, 255]));
+    }
+
+    #[test]
+    fn test_blend_mode_applies_until_reset() {
+        let mut p = HeadlessPlatform::new();
+        p.open_screen(2, 1);
+        p.clear(Color::rgb(100, 0, 0));
+        p.set_blend(BlendMode::Add);
+        p.fill_rect(0, 0, 1, 1, Color::rgb(100, 50, 0));
+        p.set_blend(BlendMode::Normal);
+        p.fill_rect(1, 0, 1, 1, Color::rgb(100, 50, 0));
+        let fb = p.snapshot().unwrap();
+        assert_eq!(fb.pixel(0, 0), Some([200, 50, 0, 255]));
+        assert_eq!(fb.pixel(1, 0), Some([100, 50, 0, 255]));
+    }
+
+    #[test]
+    fn test_surfaces_are_drawn_to_and_composited() {
+        let mut p = HeadlessPlatform::new();
+        p.open_screen(4, 1);
+        p.clear(Color::rgb(0, 0, 100));
+        let layer = p.create_surface();
+        p.set_target(Some(layer));
+        p.fill_rect(1, 0, 2, 1, Color::rgb(255, 0, 0));
+        p.set_target(None);
+        assert_eq!(p.snapshot().unwrap().pixel(1, 0), Some([0, 0, 100, 255]));
+        p.draw_surface(layer, &LayerDraw { x: 1.0, opacity: 0.5, ..LayerDraw::default() });
+        let fb = p.snapshot().unwrap();
+        assert_eq!(fb.pixel(1, 0), Some([0, 0, 100, 255]));
+        assert_eq!(fb.pixel(2, 0), Some([128, 0, 50, 255]));
+        assert_eq!(fb.pixel(3, 0), Some([128, 0, 50, 255]));
+    }
+
+    #[test]
+    fn test_clock_never_sleeps() {
+        let mut p = HeadlessPlatform::new();
+        p.wait_until(1.5);
+        p.sleep_ms(500);
+        p.wait_until(0.5);
+        assert_eq!(p.now(), 2.0);
+    }
+}
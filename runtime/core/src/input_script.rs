@@ -0,0 +1,218 @@
+//! Scripted input: key and mouse events pinned to frame numbers, so a
+//! program can be driven without a keyboard (golden-image tests, CI, replays).
+//!
+//! One event per line, `#` starts a comment. Frames count from 1; events for
+//! frame N are applied when that frame polls input. An optional `seed N` line
+//! fixes the random number seed. [`InputRecorder`] writes the same format.
+//!
+//! ```text
+//! seed 12345
+//! 1   press right
+//! 40  release right
+//! 45  mouse 120 300
+//! 90  quit
+//! ```
+
+use crate::platform::InputState;
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Press(String),
+    Release(String),
+    Mouse(i64, i64),
+    Quit,
+}
+
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputEvent::Press(key) => write!(f, "press {key}"),
+            InputEvent::Release(key) => write!(f, "release {key}"),
+            InputEvent::Mouse(x, y) => write!(f, "mouse {x} {y}"),
+            InputEvent::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputScript {
+    pub seed: Option<u64>,
+    /// Sorted by frame.
+    events: Vec<(u64, InputEvent)>,
+}
+
+impl InputScript {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut seed = None;
+        let mut events = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {msg}: `{line}`", i + 1);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if let ["seed", n] = words[..] {
+                seed = Some(n.parse().map_err(|_| err("bad seed"))?);
+                continue;
+            }
+            let frame: u64 = words[0].parse().map_err(|_| err("expected a frame number"))?;
+            let event = match words[1..] {
+                ["press", key] => InputEvent::Press(key.to_lowercase()),
+                ["release", key] => InputEvent::Release(key.to_lowercase()),
+                ["mouse", x, y] => InputEvent::Mouse(
+                    x.parse().map_err(|_| err("bad mouse x"))?,
+                    y.parse().map_err(|_| err("bad mouse y"))?,
+                ),
+                ["quit"] => InputEvent::Quit,
+                _ => return Err(err("expected press KEY, release KEY, mouse X Y or quit")),
+            };
+            events.push((frame, event));
+        }
+        events.sort_by_key(|(frame, _)| *frame);
+        Ok(Self { seed, events })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        Self::parse(&text).map_err(|e| format!("{path}: {e}"))
+    }
+
+    /// Apply the events for `frame` on top of what the platform reported.
+    pub fn apply(&self, frame: u64, input: &mut InputState) {
+        let start = self.events.partition_point(|(f, _)| *f < frame);
+        for (_, event) in self.events[start..].iter().take_while(|(f, _)| *f == frame) {
+            match event {
+                InputEvent::Press(key) => {
+                    input.keys.insert(key.clone(), true);
+                }
+                InputEvent::Release(key) => {
+                    input.keys.insert(key.clone(), false);
+                }
+                InputEvent::Mouse(x, y) => {
+                    input.mouse_x = *x;
+                    input.mouse_y = *y;
+                }
+                InputEvent::Quit => input.quit = true,
+            }
+        }
+    }
+}
+
+/// Logs live input in the [`InputScript`] format, one line per change.
+pub struct InputRecorder {
+    out: Box<dyn Write>,
+    seed: u64,
+    last: InputState,
+}
+
+impl InputRecorder {
+    pub fn new(mut out: Box<dyn Write>, seed: u64) -> io::Result<Self> {
+        writeln!(out, "# gbasic input log")?;
+        writeln!(out, "seed {seed}")?;
+        out.flush()?;
+        Ok(Self { out, seed, last: InputState::default() })
+    }
+
+    /// The seed written at the top of the log.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn create(path: &str, seed: u64) -> io::Result<Self> {
+        Self::new(Box::new(std::fs::File::create(path)?), seed)
+    }
+
+    /// Log whatever changed since the last call as events for `frame`.
+    pub fn record(&mut self, frame: u64, input: &InputState) -> io::Result<()> {
+        let mut keys: Vec<_> = input.keys.iter().collect();
+        keys.sort();
+        let mut events = Vec::new();
+        for (key, &down) in keys {
+            if self.last.keys.get(key).copied().unwrap_or(false) != down {
+                let key = key.clone();
+                events.push(if down { InputEvent::Press(key) } else { InputEvent::Release(key) });
+            }
+        }
+        if (input.mouse_x, input.mouse_y) != (self.last.mouse_x, self.last.mouse_y) {
+            events.push(InputEvent::Mouse(input.mouse_x, input.mouse_y));
+        }
+        if input.quit && !self.last.quit {
+            events.push(InputEvent::Quit);
+        }
+        for event in &events {
+            writeln!(self.out, "{frame} {event}")?;
+        }
+        if !events.is_empty() {
+            self.out.flush()?;
+            self.last = input.clone();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply() {
+        let script = InputScript::parse(
+            "# steer right, then stop\n40 release Right\n1 press right\n40 mouse 5 -2\n",
+        )
+        .unwrap();
+        let mut input = InputState::default();
+        script.apply(1, &mut input);
+        assert_eq!(input.keys.get("right"), Some(&true));
+        script.apply(2, &mut input);
+        assert_eq!(input.keys.get("right"), Some(&true));
+        script.apply(40, &mut input);
+        assert_eq!(input.keys.get("right"), Some(&false));
+        assert_eq!((input.mouse_x, input.mouse_y), (5, -2));
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let err = InputScript::parse("1 press a\n2 jump\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+        assert!(InputScript::parse("x press a").is_err());
+    }
+
+    #[test]
+    fn test_recording_replays_to_the_same_state() {
+        let path = std::env::temp_dir().join(format!("gbasic_input_{}.log", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut recorder = InputRecorder::create(path, 99).unwrap();
+        let mut live = InputState::default();
+        let mut frames = Vec::new();
+        for frame in 1..=5 {
+            match frame {
+                2 => {
+                    live.keys.insert("space".into(), true);
+                    live.keys.insert("a".into(), true);
+                }
+                3 => (live.mouse_x, live.mouse_y) = (10, 20),
+                4 => {
+                    live.keys.insert("space".into(), false);
+                }
+                5 => live.quit = true,
+                _ => {}
+            }
+            recorder.record(frame, &live).unwrap();
+            frames.push(live.clone());
+        }
+
+        let script = InputScript::load(path).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(script.seed, Some(99));
+        let mut replayed = InputState::default();
+        for (frame, expected) in (1..=5).zip(&frames) {
+            script.apply(frame, &mut replayed);
+            assert_eq!(replayed.keys, expected.keys, "frame {frame}");
+            assert_eq!((replayed.mouse_x, replayed.mouse_y, replayed.quit),
+                       (expected.mouse_x, expected.mouse_y, expected.quit));
+        }
+    }
+}
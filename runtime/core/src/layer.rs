@@ -0,0 +1,92 @@
+//! Layers: the numbered planes objects are drawn on, back to front.
+//!
+//! A layer nobody has touched costs nothing — its objects go straight to the
+//! screen. Once it is painted on, faded, hidden, given a scroll factor or an
+//! effect, it's drawn on its own offscreen surface and composited in order.
+
+use crate::platform::LayerDraw;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layer {
+    /// 0 = invisible, 1 = as opaque as drawn.
+    pub opacity: f64,
+    pub visible: bool,
+    /// How far the layer moves with the camera: 0 stays put, 0.5 moves
+    /// half as far (a distant background), 1 moves with the world.
+    pub scroll: f64,
+    pub blur: f64,
+    pub grayscale: f64,
+    pub brightness: f64,
+    /// Surface holding what was drawn with `Screen.Layer(n)`, kept from
+    /// frame to frame until the layer is cleared.
+    pub paint: Option<usize>,
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self { opacity: 1.0, visible: true, scroll: 1.0, blur: 0.0, grayscale: 0.0, brightness: 1.0, paint: None }
+    }
+}
+
+impl Layer {
+    /// True when the layer can be drawn straight on the screen.
+    pub fn is_plain(&self) -> bool {
+        self.paint.is_none()
+            && self.visible
+            && self.opacity >= 1.0
+            && self.scroll == 1.0
+            && !self.draw().has_effects()
+    }
+
+    /// How the layer's finished surface goes on the screen.
+    pub fn draw(&self) -> LayerDraw {
+        LayerDraw {
+            opacity: self.opacity.clamp(0.0, 1.0),
+            blur: self.blur,
+            grayscale: self.grayscale,
+            brightness: self.brightness,
+            ..LayerDraw::default()
+        }
+    }
+}
+
+/// Top-left corners of the copies of a screen-sized painting needed to fill
+/// the screen once it has scrolled to `(x, y)`. The painting repeats in both
+/// directions, so a background scrolls forever.
+pub fn tiles((x, y): (f64, f64), (w, h): (f64, f64)) -> Vec<(f64, f64)> {
+    if w <= 0.0 || h <= 0.0 {
+        return Vec::new();
+    }
+    let ox = (-x).rem_euclid(w).round();
+    let oy = (-y).rem_euclid(h).round();
+    let xs: &[f64] = if ox == 0.0 || ox == w { &[0.0] } else { &[ox - w, ox] };
+    let ys: &[f64] = if oy == 0.0 || oy == h { &[0.0] } else { &[oy - h, oy] };
+    ys.iter().flat_map(|&ty| xs.iter().map(move |&tx| (tx, ty))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_untouched_layer_is_plain() {
+        let mut layer = Layer::default();
+        assert!(layer.is_plain());
+        layer.brightness = 0.5;
+        assert!(!layer.is_plain());
+        layer = Layer { scroll: 0.5, ..Layer::default() };
+        assert!(!layer.is_plain());
+        layer = Layer { paint: Some(0), ..Layer::default() };
+        assert!(!layer.is_plain());
+    }
+
+    #[test]
+    fn test_tiles_wrap_the_painting() {
+        let size = (100.0, 50.0);
+        assert_eq!(tiles((0.0, 0.0), size), vec![(0.0, 0.0)]);
+        assert_eq!(tiles((200.0, 50.0), size), vec![(0.0, 0.0)]);
+        // Scrolled 30 right and 10 down: the painting moves up-left and repeats
+        assert_eq!(tiles((30.0, 10.0), size), vec![(-30.0, -10.0), (70.0, -10.0), (-30.0, 40.0), (70.0, 40.0)]);
+        assert_eq!(tiles((-30.0, 0.0), size), vec![(-70.0, 0.0), (30.0, 0.0)]);
+    }
+}
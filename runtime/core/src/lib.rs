@@ -0,0 +1,83 @@
+//! G-Basic runtime core — everything a runtime does that doesn't depend on
+//! where it runs.
+//!
+//! Game objects, physics, dynamic arrays, the memory store, the RNG and the
+//! bitmap font live here, together with the `extern "C"` ABI that compiled
+//! programs call. A backend (desktop, web) only implements [`Platform`] and
+//! hands one out from `gbasic_create_platform`, which is resolved when the
+//! program is linked against that backend's library.
+
+mod abi;
+pub mod animation;
+pub mod broadphase;
+pub mod camera;
+pub mod collision;
+pub mod config;
+pub mod font;
+pub mod framebuffer;
+pub mod grid;
+pub mod group;
+pub mod headless;
+pub mod input_script;
+mod json;
+pub mod layer;
+pub mod object;
+pub mod particles;
+pub mod physics;
+pub mod platform;
+pub mod query;
+pub mod rng;
+pub mod store;
+pub mod text;
+pub mod tilemap;
+pub mod ttf;
+pub mod tween;
+
+pub use config::RunConfig;
+pub use framebuffer::Framebuffer;
+pub use platform::{AudioSink, BlendMode, Color, InputSource, InputState, LayerDraw, Platform, Renderer, SpriteDraw};
+
+/// Platform used by the core's own unit tests: draws nothing, hears nothing.
+#[cfg(test)]
+mod test_platform {
+    use crate::framebuffer::Framebuffer;
+    use crate::platform::*;
+
+    struct NullPlatform;
+
+    impl Renderer for NullPlatform {
+        fn open_screen(&mut self, _width: i64, _height: i64) {}
+        fn clear(&mut self, _color: Color) {}
+        fn fill_rect(&mut self, _x: i64, _y: i64, _w: i64, _h: i64, _color: Color) {}
+        fn set_blend(&mut self, _mode: BlendMode) {}
+        fn load_image(&mut self, _path: &str) -> Option<usize> { None }
+        fn upload_image(&mut self, image: Option<usize>, _pixels: Framebuffer) -> usize { image.unwrap_or(0) }
+        fn image_size(&self, _image: usize) -> Option<(u32, u32)> { None }
+        fn draw_sprite(&mut self, _image: usize, _sprite: &SpriteDraw) {}
+        fn create_surface(&mut self) -> usize { 0 }
+        fn set_target(&mut self, _surface: Option<usize>) {}
+        fn draw_surface(&mut self, _surface: usize, _layer: &LayerDraw) {}
+        fn present(&mut self) {}
+    }
+
+    impl AudioSink for NullPlatform {
+        fn beep(&mut self, _freq: i64, _duration_ms: i64) {}
+        fn load_effect(&mut self, _path: &str) -> bool { true }
+        fn play_effect(&mut self, _path: &str) {}
+        fn set_effect_volume(&mut self, _path: &str, _volume: f64) {}
+    }
+
+    impl InputSource for NullPlatform {
+        fn poll(&mut self, _input: &mut InputState) {}
+    }
+
+    impl Platform for NullPlatform {
+        fn now(&self) -> f64 { 0.0 }
+        fn wait_until(&mut self, _deadline: f64) {}
+    }
+
+    #[unsafe(no_mangle)]
+    fn gbasic_create_platform() -> Box<dyn Platform> {
+        Box::new(NullPlatform)
+    }
+}
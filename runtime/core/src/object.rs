@@ -0,0 +1,514 @@
+//! Game objects created by `rect()`, `circle()`, `sprite()`, `sprite_sheet()`,
+//! `tilemap()`, `text()` and the vector shapes `line()`, `polygon()`,
+//! `triangle()`, `ellipse()` and `arc()`, and particle emitters from
+//! `emitter()`.
+
+use crate::animation::{Animator, SpriteSheet};
+use crate::collision::{self, Contact, Shape};
+use crate::particles::Emitter;
+use crate::platform::Color;
+use crate::text::Text;
+use crate::tilemap::Tilemap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectKind {
+    Rect,
+    Circle,
+    /// An image, by the backend's image id; sized and colliding like a rect.
+    Sprite(usize),
+    /// A grid of tiles cut from a tileset image, by image id. Collides
+    /// through its solid tiles; doesn't rotate.
+    Tilemap(usize),
+    /// Rendered text; sized and colliding like a rect.
+    Text,
+    /// A straight line through `points`; collides as a box `thickness` wide.
+    Line,
+    /// A closed shape through `points` (`polygon()` and `triangle()`).
+    /// Collides as its convex hull.
+    Polygon,
+    /// An oval filling its `w` × `h` box.
+    Ellipse,
+    /// Part of a circle, from `arc_start` to `arc_end`: a pie slice when
+    /// filled, the curve alone when not.
+    Arc,
+    /// A point particles stream out of; they don't collide, and the
+    /// emitter's `gravity` pulls on them rather than on it.
+    Emitter,
+}
+
+/// Corners used to draw curves per pixel of radius, within limits.
+fn curve_segments(radius: f64, sweep: f64) -> usize {
+    let full = (radius * 0.75).clamp(16.0, 96.0);
+    (full * sweep / 360.0).ceil().max(2.0) as usize
+}
+
+/// What an object collides as (`collision_shape`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionShape {
+    /// Its real shape: a circle, or a box that turns with the object.
+    Auto,
+    /// Its axis-aligned bounding box — cheapest.
+    Box,
+    /// The largest circle that fits inside it.
+    Circle,
+}
+
+impl CollisionShape {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "box" => Some(Self::Box),
+            "circle" => Some(Self::Circle),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameObject {
+    pub kind: ObjectKind,
+    /// Top-left corner for rects, sprites and ellipses, and of the box around
+    /// a line's or polygon's points; center for circles and arcs.
+    pub x: f64,
+    pub y: f64,
+    /// Unscaled size; for circles and arcs `w` holds the radius.
+    pub w: f64,
+    pub h: f64,
+    /// Clockwise rotation around the center, in degrees.
+    pub rotation: f64,
+    /// Size multipliers. Circles stay round, using the smaller one.
+    pub scale_x: f64,
+    pub scale_y: f64,
+    /// 0 = invisible, 1 = as opaque as the image or color.
+    pub opacity: f64,
+    /// Mirror the image left-right / top-bottom (sprites).
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Set for sprites made with `sprite_sheet()`, which show one frame, and
+    /// for tilemaps, whose tiles are frames of their tileset.
+    pub sheet: Option<SpriteSheet>,
+    pub tilemap: Option<Box<Tilemap>>,
+    pub text: Option<Box<Text>>,
+    pub emitter: Option<Box<Emitter>>,
+    /// Corners of a line or polygon, unscaled, relative to (x, y).
+    pub points: Vec<(f64, f64)>,
+    /// Where an arc starts and ends, in degrees clockwise from pointing right.
+    pub arc_start: f64,
+    pub arc_end: f64,
+    pub animation: Animator,
+    pub color: Color,
+    /// Filled in, or just outlined `thickness` pixels wide (shapes).
+    pub fill: bool,
+    pub thickness: f64,
+    pub visible: bool,
+    pub layer: i64,
+    /// Part of the UI: drawn at its screen position whatever the camera
+    /// shows, above the world.
+    pub ui: bool,
+    // Physics
+    pub vx: f64,
+    pub vy: f64,
+    pub gravity: f64,
+    pub solid: bool,
+    pub bounces: bool,
+    /// Share of speed kept when a `bounces` object rebounds (1 = all of it).
+    pub bounciness: f64,
+    /// Grip against surfaces: 0 slides forever, 1 stops quickly.
+    pub friction: f64,
+    /// 0 = doesn't push other movers; above 0, movers push each other
+    /// in proportion to their mass.
+    pub mass: f64,
+    pub collision_shape: CollisionShape,
+    /// Come back in at the opposite screen edge once fully past one.
+    pub wraps: bool,
+    // Lifetime
+    /// Seconds left before the runtime removes it; None for never.
+    pub lifetime: Option<f64>,
+    /// Removed once it has been on screen and left it.
+    pub remove_offscreen: bool,
+    /// Has been on screen, for `remove_offscreen`.
+    pub seen: bool,
+    // Groups
+    /// Name shared with others like it, for `Screen.objects(tag)` and the
+    /// other tag queries; empty for none.
+    pub tag: String,
+    /// Handle of the object it moves with, if any.
+    pub parent: Option<i64>,
+    /// Where it was when its children last caught up with it.
+    pub followed: (f64, f64),
+    // State
+    pub alive: bool,
+    /// Resting on top of something after the last physics step.
+    pub on_ground: bool,
+    /// Objects this one touched during the last frame's physics.
+    pub contacts: Vec<usize>,
+}
+
+impl GameObject {
+    pub fn new(kind: ObjectKind, w: f64, h: f64) -> Self {
+        Self {
+            kind,
+            x: 0.0,
+            y: 0.0,
+            w,
+            h,
+            rotation: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            opacity: 1.0,
+            flip_x: false,
+            flip_y: false,
+            sheet: None,
+            tilemap: None,
+            text: None,
+            emitter: None,
+            points: Vec::new(),
+            arc_start: 0.0,
+            arc_end: 360.0,
+            animation: Animator::default(),
+            color: Color::rgb(255, 255, 255),
+            fill: true,
+            thickness: 1.0,
+            visible: true,
+            layer: 0,
+            ui: false,
+            vx: 0.0,
+            vy: 0.0,
+            gravity: 0.0,
+            solid: false,
+            bounces: false,
+            bounciness: 1.0,
+            friction: 0.0,
+            mass: 0.0,
+            collision_shape: CollisionShape::Auto,
+            wraps: false,
+            lifetime: None,
+            remove_offscreen: false,
+            seen: false,
+            tag: String::new(),
+            parent: None,
+            followed: (0.0, 0.0),
+            alive: true,
+            on_ground: false,
+            contacts: Vec::new(),
+        }
+    }
+
+    /// A line or polygon through screen points, placed at the top-left of
+    /// the box around them.
+    pub fn with_points(kind: ObjectKind, points: &[(f64, f64)]) -> Self {
+        let (x1, y1, x2, y2) = collision::bounds(&Shape::Polygon(points.to_vec()));
+        let mut obj = Self::new(kind, x2 - x1, y2 - y1);
+        obj.x = x1;
+        obj.y = y1;
+        obj.points = points.iter().map(|&(x, y)| (x - x1, y - y1)).collect();
+        obj
+    }
+
+    /// Size on screen, scale included, before rotation; for circles the
+    /// radius twice.
+    pub fn scaled_size(&self) -> (f64, f64) {
+        match self.kind {
+            ObjectKind::Circle | ObjectKind::Arc => {
+                let r = self.w * self.scale_x.min(self.scale_y);
+                (r, r)
+            }
+            _ => (self.w * self.scale_x, self.h * self.scale_y),
+        }
+    }
+
+    /// The object's true outline, ignoring `collision_shape`.
+    pub fn outline(&self) -> Shape {
+        let (w, h) = self.scaled_size();
+        match self.kind {
+            ObjectKind::Rect | ObjectKind::Sprite(_) | ObjectKind::Text => Shape::Box {
+                x: self.x + w / 2.0,
+                y: self.y + h / 2.0,
+                hw: w / 2.0,
+                hh: h / 2.0,
+                angle: self.rotation.to_radians(),
+            },
+            ObjectKind::Circle => Shape::Circle { x: self.x, y: self.y, r: w },
+            ObjectKind::Emitter => Shape::Circle { x: self.x, y: self.y, r: 0.0 },
+            ObjectKind::Tilemap(_) => Shape::Box {
+                x: self.x + w / 2.0,
+                y: self.y + h / 2.0,
+                hw: w / 2.0,
+                hh: h / 2.0,
+                angle: 0.0,
+            },
+            ObjectKind::Line => {
+                let ((x1, y1), (x2, y2)) = match self.corners()[..] {
+                    [a, b] => (a, b),
+                    _ => ((self.x, self.y), (self.x, self.y)),
+                };
+                Shape::Box {
+                    x: (x1 + x2) / 2.0,
+                    y: (y1 + y2) / 2.0,
+                    hw: (x2 - x1).hypot(y2 - y1) / 2.0,
+                    hh: self.thickness / 2.0,
+                    angle: (y2 - y1).atan2(x2 - x1),
+                }
+            }
+            ObjectKind::Polygon | ObjectKind::Ellipse | ObjectKind::Arc => {
+                Shape::Polygon(collision::convex_hull(&self.corners()))
+            }
+        }
+    }
+
+    /// Whether it's drawn as an open line rather than a closed shape.
+    pub fn is_open(&self) -> bool {
+        match self.kind {
+            ObjectKind::Line => true,
+            ObjectKind::Arc => !self.fill && self.arc_sweep() < 360.0,
+            _ => false,
+        }
+    }
+
+    /// How far round an arc goes, in degrees: up to a full turn, wrapping
+    /// past 360 so `arc(r, 300, 60)` goes through 0.
+    fn arc_sweep(&self) -> f64 {
+        let sweep = self.arc_end - self.arc_start;
+        if sweep >= 360.0 || sweep <= -360.0 {
+            360.0
+        } else {
+            sweep.rem_euclid(360.0)
+        }
+    }
+
+    /// Corners of the object as drawn, in order, scale and rotation
+    /// included; curves come as many short edges.
+    pub fn corners(&self) -> Vec<(f64, f64)> {
+        let (w, h) = self.scaled_size();
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let turn = |(cx, cy): (f64, f64), (x, y): (f64, f64)| (cx + x * cos - y * sin, cy + x * sin + y * cos);
+        let oval = |(cx, cy): (f64, f64), rx: f64, ry: f64, start: f64, sweep: f64, closed: bool| {
+            let n = curve_segments(rx.max(ry), sweep);
+            let count = if closed { n } else { n + 1 };
+            (0..count)
+                .map(|i| {
+                    let a = (start + sweep * i as f64 / n as f64).to_radians();
+                    turn((cx, cy), (rx * a.cos(), ry * a.sin()))
+                })
+                .collect::<Vec<_>>()
+        };
+        let center = (self.x + w / 2.0, self.y + h / 2.0);
+        match self.kind {
+            ObjectKind::Circle => oval((self.x, self.y), w, w, 0.0, 360.0, true),
+            ObjectKind::Ellipse => oval(center, w / 2.0, h / 2.0, 0.0, 360.0, true),
+            ObjectKind::Arc => {
+                let sweep = self.arc_sweep();
+                if sweep >= 360.0 {
+                    return oval((self.x, self.y), w, w, 0.0, 360.0, true);
+                }
+                let mut corners = oval((self.x, self.y), w, w, self.arc_start, sweep, false);
+                if self.fill {
+                    corners.push((self.x, self.y));
+                }
+                corners
+            }
+            ObjectKind::Line | ObjectKind::Polygon => self
+                .points
+                .iter()
+                .map(|&(px, py)| turn(center, (px * self.scale_x - w / 2.0, py * self.scale_y - h / 2.0)))
+                .collect(),
+            _ => [(-w, -h), (w, -h), (w, h), (-w, h)].map(|(x, y)| turn(center, (x / 2.0, y / 2.0))).to_vec(),
+        }
+    }
+
+    /// Axis-aligned bounding box as (x1, y1, x2, y2), rotation included.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        collision::bounds(&self.outline())
+    }
+
+    /// The shape used for collisions.
+    pub fn shape(&self) -> Shape {
+        match (self.collision_shape, self.kind) {
+            (CollisionShape::Box, _) => {
+                let (x1, y1, x2, y2) = self.bounds();
+                Shape::Box {
+                    x: (x1 + x2) / 2.0,
+                    y: (y1 + y2) / 2.0,
+                    hw: (x2 - x1) / 2.0,
+                    hh: (y2 - y1) / 2.0,
+                    angle: 0.0,
+                }
+            }
+            (CollisionShape::Circle, ObjectKind::Rect | ObjectKind::Sprite(_) | ObjectKind::Text) => {
+                let (x, y) = self.center();
+                let (w, h) = self.scaled_size();
+                Shape::Circle { x, y, r: w.min(h) / 2.0 }
+            }
+            _ => self.outline(),
+        }
+    }
+
+    /// How this object overlaps `other`, with the normal pointing away from
+    /// `other`; touching edges don't count.
+    pub fn contact(&self, other: &GameObject) -> Option<Contact> {
+        collision::contact(&self.shape(), &other.shape())
+    }
+
+    /// Exact shape overlap; touching edges don't count. A tilemap only
+    /// overlaps objects through its solid tiles.
+    pub fn overlaps(&self, other: &GameObject) -> bool {
+        match (&self.tilemap, &other.tilemap) {
+            (Some(_), Some(_)) => false,
+            (Some(_), None) => other.overlaps(self),
+            (None, Some(_)) => other
+                .solid_cells(self.bounds())
+                .into_iter()
+                .any(|(col, row)| self.contact(&other.tile_box(col, row)).is_some()),
+            (None, None) => self.contact(other).is_some(),
+        }
+    }
+
+    /// Size of a tilemap cell on screen.
+    pub fn cell_size(&self) -> (f64, f64) {
+        self.sheet.map_or((0.0, 0.0), |s| (s.frame_w as f64 * self.scale_x, s.frame_h as f64 * self.scale_y))
+    }
+
+    /// Tilemap cell under a point.
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(i64, i64)> {
+        let (cw, ch) = self.cell_size();
+        (cw > 0.0 && ch > 0.0).then(|| (((x - self.x) / cw).floor() as i64, ((y - self.y) / ch).floor() as i64))
+    }
+
+    /// Solid tilemap cells that overlap an area, as (column, row).
+    pub fn solid_cells(&self, (x1, y1, x2, y2): (f64, f64, f64, f64)) -> Vec<(i64, i64)> {
+        let (Some(map), Some((c1, r1)), Some((c2, r2))) = (&self.tilemap, self.cell_at(x1, y1), self.cell_at(x2, y2)) else {
+            return Vec::new();
+        };
+        let (c1, r1) = (c1.max(0), r1.max(0));
+        let (c2, r2) = (c2.min(map.columns as i64 - 1), r2.min(map.rows as i64 - 1));
+        let mut cells = Vec::new();
+        for row in r1..=r2 {
+            for col in c1..=c2 {
+                if map.is_solid(col, row) {
+                    cells.push((col, row));
+                }
+            }
+        }
+        cells
+    }
+
+    /// A tilemap cell as a static solid rect with the map's surface.
+    pub fn tile_box(&self, col: i64, row: i64) -> GameObject {
+        let (cw, ch) = self.cell_size();
+        let mut tile = GameObject::new(ObjectKind::Rect, cw, ch);
+        tile.x = self.x + col as f64 * cw;
+        tile.y = self.y + row as f64 * ch;
+        tile.solid = true;
+        tile.bounces = self.bounces;
+        tile.bounciness = self.bounciness;
+        tile.friction = self.friction;
+        tile
+    }
+
+    /// How far along a ray (unit direction) it first meets this object from
+    /// outside, up to `max`; None if the ray starts inside it. A tilemap is
+    /// met at its first solid tile the ray doesn't start in.
+    pub fn ray(&self, from: (f64, f64), dir: (f64, f64), max: f64) -> Option<f64> {
+        if self.tilemap.is_none() {
+            return collision::ray(&self.shape(), from, dir, max).filter(|&t| t > 0.0);
+        }
+        let to = (from.0 + dir.0 * max, from.1 + dir.1 * max);
+        let area = (from.0.min(to.0), from.1.min(to.1), from.0.max(to.0), from.1.max(to.1));
+        self.solid_cells(area)
+            .into_iter()
+            .filter_map(|(col, row)| self.tile_box(col, row).ray(from, dir, max))
+            .min_by(f64::total_cmp)
+    }
+
+    /// Center of the bounding box.
+    pub fn center(&self) -> (f64, f64) {
+        let (x1, y1, x2, y2) = self.bounds();
+        ((x1 + x2) / 2.0, (y1 + y2) / 2.0)
+    }
+
+    /// Solid and not falling: physics never pushes it.
+    pub fn is_static(&self) -> bool {
+        self.solid && self.gravity == 0.0
+    }
+
+    /// Point-in-shape test, edges included. For tilemaps: a point on a tile.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        if let (Some(map), Some((col, row))) = (&self.tilemap, self.cell_at(x, y)) {
+            return map.tile(col, row) >= 0;
+        }
+        collision::contains(&self.shape(), x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect_at(x: f64, y: f64, w: f64, h: f64) -> GameObject {
+        let mut o = GameObject::new(ObjectKind::Rect, w, h);
+        o.x = x;
+        o.y = y;
+        o
+    }
+
+    #[test]
+    fn test_circle_bounds_are_centered() {
+        let mut c = GameObject::new(ObjectKind::Circle, 10.0, 10.0);
+        c.x = 50.0;
+        c.y = 40.0;
+        assert_eq!(c.bounds(), (40.0, 30.0, 60.0, 50.0));
+    }
+
+    #[test]
+    fn test_touching_edges_do_not_overlap() {
+        let a = rect_at(0.0, 0.0, 10.0, 10.0);
+        let b = rect_at(10.0, 0.0, 10.0, 10.0);
+        let c = rect_at(9.0, 9.0, 10.0, 10.0);
+        assert!(!a.overlaps(&b));
+        assert!(a.overlaps(&c));
+        assert!(a.contains(10.0, 10.0));
+        assert!(!a.contains(10.1, 5.0));
+    }
+
+    #[test]
+    fn test_scale_grows_from_the_top_left() {
+        let mut a = rect_at(10.0, 10.0, 10.0, 4.0);
+        a.scale_x = 2.0;
+        a.scale_y = 0.5;
+        assert_eq!(a.bounds(), (10.0, 10.0, 30.0, 12.0));
+        let mut c = GameObject::new(ObjectKind::Circle, 10.0, 10.0);
+        c.scale_x = 3.0;
+        c.scale_y = 2.0;
+        assert_eq!(c.bounds(), (-20.0, -20.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn test_shapes_turn_around_their_center() {
+        let mut ship = GameObject::with_points(ObjectKind::Polygon, &[(100.0, 100.0), (120.0, 110.0), (100.0, 120.0)]);
+        assert_eq!((ship.x, ship.y, ship.w, ship.h), (100.0, 100.0, 20.0, 20.0));
+        ship.rotation = 90.0;
+        let (x1, y1, x2, y2) = ship.bounds();
+        assert!((x1 - 100.0).abs() < 1e-9 && (y1 - 100.0).abs() < 1e-9, "{:?}", ship.bounds());
+        assert!((x2 - 120.0).abs() < 1e-9 && (y2 - 120.0).abs() < 1e-9, "{:?}", ship.bounds());
+        // Pointing down now: the nose is at the bottom middle
+        assert!(ship.contains(110.0, 119.0) && !ship.contains(101.0, 119.0));
+
+        let mut line = GameObject::with_points(ObjectKind::Line, &[(0.0, 0.0), (40.0, 40.0)]);
+        line.thickness = 2.0;
+        assert!(line.overlaps(&rect_at(19.0, 19.0, 2.0, 2.0)));
+        assert!(!line.overlaps(&rect_at(30.0, 0.0, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn test_arcs_go_clockwise_from_the_right() {
+        let mut pie = GameObject::new(ObjectKind::Arc, 10.0, 10.0);
+        (pie.arc_start, pie.arc_end) = (0.0, 90.0);
+        // Filled: a quarter slice from pointing right to pointing down
+        assert!(pie.contains(3.0, 3.0) && !pie.contains(-3.0, 3.0) && !pie.contains(3.0, -3.0));
+        (pie.arc_start, pie.arc_end) = (300.0, 60.0);
+        assert!(pie.contains(5.0, 0.0) && !pie.contains(-5.0, 0.0));
+        assert!(!pie.is_open());
+        pie.fill = false;
+        assert!(pie.is_open());
+    }
+}
@@ -0,0 +1,195 @@
+//! Particle emitters made with `emitter()`: a stream or burst of short-lived
+//! dots, moved and drawn by the runtime itself, so a program only places the
+//! emitter and sets how its particles look.
+
+use crate::platform::Color;
+use crate::rng::Rng;
+
+/// Most particles one emitter keeps alive; more are simply not emitted.
+pub const MAX_PARTICLES: usize = 10_000;
+
+/// One particle, in world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    /// Seconds since it was emitted.
+    pub age: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    pub particles: Vec<Particle>,
+    /// Particles per second; 0 only emits on `burst`.
+    pub rate: f64,
+    /// Seconds each particle lives.
+    pub lifetime: f64,
+    /// Starting speed in px/s, varying by up to `speed_spread` either way.
+    pub speed: f64,
+    pub speed_spread: f64,
+    /// Direction particles leave in, in degrees clockwise from pointing
+    /// right (-90 is up), varying over a cone `spread` degrees wide.
+    pub angle: f64,
+    pub spread: f64,
+    /// Diameter when emitted and when it dies, in pixels.
+    pub start_size: f64,
+    pub end_size: f64,
+    /// Color a particle turns into by the end of its life; without one it
+    /// keeps the emitter's color and fades out.
+    pub end_color: Option<Color>,
+    /// Particles owed to `rate` but not emitted yet.
+    carry: f64,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            rate: 50.0,
+            lifetime: 1.0,
+            speed: 100.0,
+            speed_spread: 0.0,
+            angle: -90.0,
+            spread: 360.0,
+            start_size: 6.0,
+            end_size: 6.0,
+            end_color: None,
+            carry: 0.0,
+        }
+    }
+}
+
+impl Emitter {
+    /// Move every particle `dt` seconds on, pulled down by `gravity`
+    /// (px/s²), drop the ones past their lifetime, then emit at `rate` from
+    /// `origin`.
+    pub fn update(&mut self, dt: f64, origin: (f64, f64), gravity: f64, rng: &mut Rng) {
+        for p in &mut self.particles {
+            p.age += dt;
+            p.vy += gravity * dt;
+            p.x += p.vx * dt;
+            p.y += p.vy * dt;
+        }
+        let lifetime = self.lifetime;
+        self.particles.retain(|p| p.age < lifetime);
+        if self.rate <= 0.0 {
+            self.carry = 0.0;
+            return;
+        }
+        self.carry += self.rate * dt;
+        // Every particle owed is paid off, but only the latest that fit are made
+        let owed = self.carry.floor();
+        self.carry -= owed;
+        let room = MAX_PARTICLES.saturating_sub(self.particles.len());
+        for k in 0..owed.min(room as f64) as usize {
+            // Each one has been on its way since its moment in the frame,
+            // so a fast stream comes out even rather than in clumps
+            let late = (self.carry + k as f64) / self.rate;
+            self.emit(origin, late, rng);
+        }
+    }
+
+    /// Emit `n` particles from `origin` at once, as many as fit.
+    pub fn burst(&mut self, n: usize, origin: (f64, f64), rng: &mut Rng) {
+        for _ in 0..n.min(MAX_PARTICLES.saturating_sub(self.particles.len())) {
+            self.emit(origin, 0.0, rng);
+        }
+    }
+
+    fn emit(&mut self, (x, y): (f64, f64), late: f64, rng: &mut Rng) {
+        if self.particles.len() >= MAX_PARTICLES || late >= self.lifetime {
+            return;
+        }
+        let half = self.spread.clamp(0.0, 360.0) / 2.0;
+        let angle = (self.angle + rng.range_f64(-half, half)).to_radians();
+        let speed = self.speed + rng.range_f64(-self.speed_spread, self.speed_spread);
+        let (vx, vy) = (speed * angle.cos(), speed * angle.sin());
+        self.particles.push(Particle { x: x + vx * late, y: y + vy * late, vx, vy, age: late });
+    }
+
+    /// Share of its life a particle has lived, 0 to 1.
+    fn life(&self, p: &Particle) -> f64 {
+        if self.lifetime > 0.0 { (p.age / self.lifetime).clamp(0.0, 1.0) } else { 1.0 }
+    }
+
+    /// How big a particle is now.
+    pub fn size(&self, p: &Particle) -> f64 {
+        let t = self.life(p);
+        self.start_size + (self.end_size - self.start_size) * t
+    }
+
+    /// What color a particle is now, for an emitter colored `color`.
+    pub fn color(&self, p: &Particle, color: Color) -> Color {
+        let end = self.end_color.unwrap_or(Color { a: 0, ..color });
+        color.mix(end, self.life(p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_emits_evenly_and_lifetime_removes() {
+        let mut rng = Rng::new(1);
+        let mut e = Emitter { rate: 30.0, spread: 0.0, angle: 0.0, ..Emitter::default() };
+        e.update(0.1, (0.0, 0.0), 0.0, &mut rng);
+        assert_eq!(e.particles.len(), 3);
+        // Emitted a third of the way apart, so they're 100/30 px apart
+        let mut xs: Vec<f64> = e.particles.iter().map(|p| p.x).collect();
+        xs.sort_by(f64::total_cmp);
+        assert!((xs[1] - xs[0] - 100.0 / 30.0).abs() < 1e-9);
+        for _ in 0..20 {
+            e.update(0.1, (0.0, 0.0), 0.0, &mut rng);
+        }
+        // A second's worth are alive at a time
+        assert!((29..=31).contains(&e.particles.len()));
+        assert!(e.particles.iter().all(|p| p.age < 1.0));
+    }
+
+    #[test]
+    fn test_burst_spreads_speed_and_angle() {
+        let mut rng = Rng::new(7);
+        let mut e = Emitter { rate: 0.0, speed: 100.0, speed_spread: 20.0, angle: -90.0, spread: 90.0, ..Emitter::default() };
+        e.burst(200, (50.0, 50.0), &mut rng);
+        e.update(0.5, (50.0, 50.0), 0.0, &mut rng);
+        assert_eq!(e.particles.len(), 200);
+        for p in &e.particles {
+            let speed = p.vx.hypot(p.vy);
+            assert!((80.0..=120.0).contains(&speed));
+            // Up, at most 45° either side
+            assert!(p.vy < 0.0 && p.vx.abs() <= -p.vy + 1e-9);
+            assert_eq!((p.x, p.y), (50.0 + p.vx * 0.5, 50.0 + p.vy * 0.5));
+        }
+    }
+
+    #[test]
+    fn test_gravity_size_and_color_over_life() {
+        let mut rng = Rng::new(3);
+        let mut e = Emitter { rate: 0.0, speed: 0.0, start_size: 10.0, end_size: 2.0, ..Emitter::default() };
+        e.burst(1, (0.0, 0.0), &mut rng);
+        e.update(0.5, (0.0, 0.0), 100.0, &mut rng);
+        let p = e.particles[0];
+        assert_eq!((p.vy, p.y), (50.0, 25.0));
+        assert_eq!(e.size(&p), 6.0);
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(e.color(&p, red), Color::rgba(255, 0, 0, 128));
+        e.end_color = Some(Color::rgb(0, 0, 255));
+        assert_eq!(e.color(&p, red), Color::rgb(128, 0, 128));
+    }
+
+    #[test]
+    fn test_particle_cap() {
+        let mut rng = Rng::new(5);
+        let mut e = Emitter { rate: 0.0, ..Emitter::default() };
+        e.burst(MAX_PARTICLES + 10, (0.0, 0.0), &mut rng);
+        assert_eq!(e.particles.len(), MAX_PARTICLES);
+        // Owing more than fits takes no longer than filling up
+        e.burst(usize::MAX, (0.0, 0.0), &mut rng);
+        let mut e = Emitter { rate: 1e300, ..Emitter::default() };
+        e.update(1.0, (0.0, 0.0), 0.0, &mut rng);
+        assert_eq!((e.particles.len(), e.carry), (MAX_PARTICLES, 0.0));
+    }
+}
@@ -0,0 +1,570 @@
+//! One physics tick for every live object.
+
+use crate::broadphase::SpatialHash;
+use crate::collision::Contact;
+use crate::object::GameObject;
+
+/// A contact normal at least this close to straight up (about 45°) means
+/// the upper object stands on the lower one.
+const GROUND_NORMAL: f64 = 0.7;
+
+/// Passes over the contacts per step, so stacked objects settle together.
+const SOLVER_PASSES: usize = 4;
+
+/// Advance `dt` seconds: apply gravity (px/s²) and velocity (px/s), bounce
+/// `bounces` objects off the screen edges and carry `wraps` ones over to
+/// the opposite edge, then resolve contacts so movers rest on, slide along
+/// and bounce off `solid` objects and push each other.
+pub fn step(objects: &mut [GameObject], dt: f64, screen_w: f64, screen_h: f64) {
+    for obj in objects.iter_mut() {
+        if !obj.alive || !obj.visible {
+            continue;
+        }
+        obj.on_ground = false;
+        // Apply gravity; an emitter's is for its particles
+        if obj.emitter.is_none() {
+            obj.vy += obj.gravity * dt;
+        }
+        // Apply velocity
+        obj.x += obj.vx * dt;
+        obj.y += obj.vy * dt;
+        // Bouncing off screen edges
+        if obj.bounces {
+            let rest_speed = rest_speed(obj.gravity.abs(), dt);
+            let (x1, y1, x2, y2) = obj.bounds();
+            // Turn back only what's heading out, so an object pushed past an
+            // edge while already coming back isn't sent out again
+            if (x1 <= 0.0 && obj.vx < 0.0) || (x2 >= screen_w && obj.vx > 0.0) {
+                obj.vx = -obj.vx * obj.bounciness;
+            }
+            // Clamp back inside
+            if x1 <= 0.0 {
+                obj.x -= x1;
+            } else if x2 >= screen_w {
+                obj.x -= x2 - screen_w;
+            }
+            if (y1 <= 0.0 && obj.vy < 0.0) || (y2 >= screen_h && obj.vy > 0.0) {
+                obj.vy = -obj.vy * obj.bounciness;
+                if obj.vy.abs() < rest_speed {
+                    obj.vy = 0.0;
+                }
+            }
+            if y1 <= 0.0 {
+                obj.y -= y1;
+            } else if y2 >= screen_h {
+                obj.y -= y2 - screen_h;
+                obj.on_ground = true;
+            }
+        }
+        if obj.wraps {
+            wrap(obj, screen_w, screen_h);
+        }
+    }
+
+    let maps: Vec<usize> = (0..objects.len()).filter(|&i| objects[i].alive && objects[i].tilemap.is_some()).collect();
+    let mut grid = SpatialHash::default();
+    for _ in 0..SOLVER_PASSES {
+        grid.rebuild(objects);
+        let mut touched = false;
+        for (i, j) in grid.pairs() {
+            let (head, tail) = objects.split_at_mut(j);
+            touched |= resolve(&mut head[i], &mut tail[0], i, j, dt);
+        }
+        for &m in &maps {
+            touched |= resolve_tiles(objects, m, dt);
+        }
+        if !touched {
+            break;
+        }
+    }
+}
+
+/// Push objects out of the solid tiles of the tilemap at index `m`, each
+/// tile acting as a static solid rect. Returns true on any contact.
+fn resolve_tiles(objects: &mut [GameObject], m: usize, dt: f64) -> bool {
+    let mut touched = false;
+    for i in (0..objects.len()).filter(|&i| i != m) {
+        let (obj, map) = if i < m {
+            let (head, tail) = objects.split_at_mut(m);
+            (&mut head[i], &mut tail[0])
+        } else {
+            let (head, tail) = objects.split_at_mut(i);
+            (&mut tail[0], &mut head[m])
+        };
+        if !obj.alive || obj.tilemap.is_some() || obj.emitter.is_some() || obj.is_static() {
+            continue;
+        }
+        let mut cells = map.solid_cells(obj.bounds());
+        // Deepest overlap first, so an object straddling two tiles settles
+        // on the one it's mostly over before the seam can catch it
+        let overlap = |(col, row): (i64, i64)| {
+            let (a, b) = (obj.bounds(), map.tile_box(col, row).bounds());
+            (a.2.min(b.2) - a.0.max(b.0)).max(0.0) * (a.3.min(b.3) - a.1.max(b.1)).max(0.0)
+        };
+        cells.sort_by(|&p, &q| overlap(q).total_cmp(&overlap(p)));
+        for (col, row) in cells {
+            let mut tile = map.tile_box(col, row);
+            let Some(contact) = obj.contact(&tile) else { continue };
+            // An edge shared with another solid tile is inside the wall
+            let (dc, dr) = if contact.nx.abs() > contact.ny.abs() {
+                (contact.nx.signum() as i64, 0)
+            } else {
+                (0, contact.ny.signum() as i64)
+            };
+            if map.tilemap.as_ref().is_some_and(|t| t.is_solid(col + dc, row + dr)) {
+                continue;
+            }
+            if resolve(obj, &mut tile, i, m, dt) {
+                touched = true;
+                if !map.contacts.contains(&i) {
+                    map.contacts.push(i);
+                }
+            }
+        }
+    }
+    touched
+}
+
+/// Once an object is all the way past a screen edge, bring it in just past
+/// the opposite one, so it slides back into view.
+fn wrap(obj: &mut GameObject, screen_w: f64, screen_h: f64) {
+    let (x1, y1, x2, y2) = obj.bounds();
+    if x1 > screen_w {
+        obj.x -= screen_w + (x2 - x1);
+    } else if x2 < 0.0 {
+        obj.x += screen_w + (x2 - x1);
+    }
+    if y1 > screen_h {
+        obj.y -= screen_h + (y2 - y1);
+    } else if y2 < 0.0 {
+        obj.y += screen_h + (y2 - y1);
+    }
+}
+
+/// Count `lifetime`s down by `dt` and find the objects whose lifetime ran out.
+pub fn count_down(objects: &mut [GameObject], dt: f64) -> Vec<usize> {
+    let mut due = Vec::new();
+    for (i, obj) in objects.iter_mut().enumerate().filter(|(_, o)| o.alive) {
+        if let Some(left) = &mut obj.lifetime {
+            *left -= dt;
+            if *left <= 0.0 {
+                due.push(i);
+            }
+        }
+    }
+    due
+}
+
+/// Find the `remove_offscreen` objects that were on screen and have left it.
+/// `world` is the world area on screen as (x1, y1, x2, y2); `ui` objects are
+/// checked against the screen itself.
+pub fn offscreen(objects: &mut [GameObject], world: (f64, f64, f64, f64), screen: (f64, f64)) -> Vec<usize> {
+    let mut due = Vec::new();
+    for (i, obj) in objects.iter_mut().enumerate().filter(|(_, o)| o.alive) {
+        if obj.remove_offscreen {
+            let (v1, w1, v2, w2) = if obj.ui { (0.0, 0.0, screen.0, screen.1) } else { world };
+            let (x1, y1, x2, y2) = obj.bounds();
+            let on_screen = x2 > v1 && x1 < v2 && y2 > w1 && y1 < w2;
+            if on_screen {
+                obj.seen = true;
+            } else if obj.seen {
+                due.push(i);
+            }
+        }
+    }
+    due
+}
+
+/// Forget last frame's contacts; called once per frame before stepping.
+pub fn clear_contacts(objects: &mut [GameObject]) {
+    for obj in objects.iter_mut() {
+        obj.contacts.clear();
+    }
+}
+
+/// Rebounds slower than this come to rest instead of jittering.
+fn rest_speed(gravity: f64, dt: f64) -> f64 {
+    2.0 * gravity * dt
+}
+
+/// Inverse masses of a touching pair, or None if they pass through each
+/// other. Static objects never move; a mover without `mass` can't push.
+fn inverse_masses(a: &GameObject, b: &GameObject) -> Option<(f64, f64)> {
+    let inv = |o: &GameObject| {
+        if o.is_static() {
+            0.0
+        } else if o.mass > 0.0 {
+            1.0 / o.mass
+        } else {
+            1.0
+        }
+    };
+    let (ia, ib) = match (a.solid, b.solid) {
+        (true, true) => (inv(a), inv(b)),
+        (false, false) if a.mass > 0.0 && b.mass > 0.0 => (inv(a), inv(b)),
+        (false, false) => return None,
+        (false, true) => (inv(a), if a.mass > 0.0 { inv(b) } else { 0.0 }),
+        (true, false) => (if b.mass > 0.0 { inv(a) } else { 0.0 }, inv(b)),
+    };
+    (ia + ib > 0.0).then_some((ia, ib))
+}
+
+/// Separate an overlapping pair and exchange impulses along the contact
+/// normal, with restitution and Coulomb friction. Returns true on contact.
+fn resolve(a: &mut GameObject, b: &mut GameObject, i: usize, j: usize, dt: f64) -> bool {
+    if !a.alive || !b.alive {
+        return false;
+    }
+    let Some((mut ia, mut ib)) = inverse_masses(a, b) else {
+        return false;
+    };
+    // Push out along the contact normal, which points from b to a
+    let Some(Contact { nx, ny, depth }) = a.contact(b) else {
+        return false;
+    };
+
+    // Whatever is already resting holds up the object gravity stacks on it
+    if ia > 0.0 && ib > 0.0 {
+        if ny <= -GROUND_NORMAL && b.on_ground && a.gravity > 0.0 {
+            ib = 0.0;
+        } else if ny >= GROUND_NORMAL && a.on_ground && b.gravity > 0.0 {
+            ia = 0.0;
+        }
+    }
+    let total = ia + ib;
+
+    a.x += nx * depth * ia / total;
+    a.y += ny * depth * ia / total;
+    b.x -= nx * depth * ib / total;
+    b.y -= ny * depth * ib / total;
+
+    let vn = (a.vx - b.vx) * nx + (a.vy - b.vy) * ny;
+    if vn < 0.0 {
+        let restitution = |o: &GameObject| if o.bounces { o.bounciness } else { 0.0 };
+        let mut e = restitution(a).max(restitution(b));
+        if -vn * e < rest_speed(a.gravity.abs().max(b.gravity.abs()), dt) {
+            e = 0.0;
+        }
+        let jn = -(1.0 + e) * vn / total;
+        a.vx += nx * jn * ia;
+        a.vy += ny * jn * ia;
+        b.vx -= nx * jn * ib;
+        b.vy -= ny * jn * ib;
+
+        // Friction opposes sliding, up to `friction` times the normal impulse
+        let (tx, ty) = (-ny, nx);
+        let vt = (a.vx - b.vx) * tx + (a.vy - b.vy) * ty;
+        let max_jt = (a.friction + b.friction) / 2.0 * jn;
+        let jt = (-vt / total).clamp(-max_jt, max_jt);
+        a.vx += tx * jt * ia;
+        a.vy += ty * jt * ia;
+        b.vx -= tx * jt * ib;
+        b.vy -= ty * jt * ib;
+    }
+
+    if ny <= -GROUND_NORMAL {
+        a.on_ground = true;
+    } else if ny >= GROUND_NORMAL {
+        b.on_ground = true;
+    }
+    if !a.contacts.contains(&j) {
+        a.contacts.push(j);
+    }
+    if !b.contacts.contains(&i) {
+        b.contacts.push(i);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::ObjectKind;
+
+    #[test]
+    fn test_gravity_accumulates_into_velocity() {
+        let mut objs = vec![GameObject::new(ObjectKind::Rect, 10.0, 10.0)];
+        objs[0].gravity = 0.5;
+        step(&mut objs, 1.0, 800.0, 600.0);
+        step(&mut objs, 1.0, 800.0, 600.0);
+        assert_eq!(objs[0].vy, 1.0);
+        assert_eq!(objs[0].y, 1.5);
+    }
+
+    #[test]
+    fn test_bounces_off_screen_edge() {
+        let mut ball = GameObject::new(ObjectKind::Circle, 10.0, 10.0);
+        ball.x = 795.0;
+        ball.y = 300.0;
+        ball.vx = 5.0;
+        ball.bounces = true;
+        let mut objs = vec![ball];
+        step(&mut objs, 1.0, 800.0, 600.0);
+        assert_eq!(objs[0].vx, -5.0);
+        assert_eq!(objs[0].x, 790.0);
+    }
+
+    #[test]
+    fn test_pushed_past_an_edge_keeps_coming_back() {
+        let mut ball = GameObject::new(ObjectKind::Circle, 10.0, 10.0);
+        ball.x = -5.0;
+        ball.y = 300.0;
+        ball.vx = 2.0;
+        ball.bounces = true;
+        let mut objs = vec![ball];
+        step(&mut objs, 1.0, 800.0, 600.0);
+        assert_eq!(objs[0].vx, 2.0);
+        assert_eq!(objs[0].x, 10.0);
+    }
+
+    #[test]
+    fn test_bounces_off_solid_object() {
+        let mut ball = GameObject::new(ObjectKind::Rect, 10.0, 10.0);
+        ball.x = 100.0;
+        ball.y = 85.0;
+        ball.vy = 10.0;
+        ball.bounces = true;
+        let mut floor = GameObject::new(ObjectKind::Rect, 200.0, 20.0);
+        floor.x = 50.0;
+        floor.y = 100.0;
+        floor.solid = true;
+        let mut objs = vec![ball, floor];
+        step(&mut objs, 1.0, 800.0, 600.0);
+        assert_eq!(objs[0].vy, -10.0);
+        assert_eq!(objs[0].y, 90.0);
+    }
+
+    #[test]
+    fn test_removed_objects_do_not_move() {
+        let mut o = GameObject::new(ObjectKind::Rect, 10.0, 10.0);
+        o.vx = 3.0;
+        o.alive = false;
+        let mut objs = vec![o];
+        step(&mut objs, 1.0, 800.0, 600.0);
+        assert_eq!(objs[0].x, 0.0);
+    }
+
+    #[test]
+    fn test_velocity_is_per_second() {
+        let mut o = GameObject::new(ObjectKind::Rect, 10.0, 10.0);
+        o.vx = 120.0;
+        let mut objs = vec![o];
+        for _ in 0..30 {
+            step(&mut objs, 1.0 / 60.0, 800.0, 600.0);
+        }
+        assert!((objs[0].x - 60.0).abs() < 1e-9);
+    }
+
+    fn rect_at(x: f64, y: f64, w: f64, h: f64) -> GameObject {
+        let mut o = GameObject::new(ObjectKind::Rect, w, h);
+        o.x = x;
+        o.y = y;
+        o
+    }
+
+    fn floor() -> GameObject {
+        let mut f = rect_at(0.0, 500.0, 800.0, 100.0);
+        f.solid = true;
+        f
+    }
+
+    fn run(objs: &mut [GameObject], steps: usize) {
+        for _ in 0..steps {
+            step(objs, 1.0 / 60.0, 800.0, 600.0);
+        }
+    }
+
+    #[test]
+    fn test_falling_object_rests_on_solid() {
+        let mut block = rect_at(100.0, 400.0, 30.0, 30.0);
+        block.gravity = 1000.0;
+        let mut objs = vec![block, floor()];
+        run(&mut objs, 120);
+        assert!((objs[0].y - 470.0).abs() < 1e-6, "y = {}", objs[0].y);
+        assert_eq!(objs[0].vy, 0.0);
+        assert!(objs[0].on_ground);
+        assert!(objs[0].contacts.contains(&1));
+    }
+
+    #[test]
+    fn test_solid_blocks_stack() {
+        let mut objs = vec![floor()];
+        for i in 0..3 {
+            let mut b = rect_at(100.0, 400.0 - i as f64 * 40.0, 30.0, 30.0);
+            b.gravity = 1000.0;
+            b.solid = true;
+            objs.push(b);
+        }
+        run(&mut objs, 240);
+        for (i, b) in objs[1..].iter().enumerate() {
+            let expected = 470.0 - i as f64 * 30.0;
+            assert!((b.y - expected).abs() < 0.5, "block {i} at {}", b.y);
+        }
+    }
+
+    #[test]
+    fn test_friction_stops_sliding() {
+        let mut slider = rect_at(100.0, 470.0, 30.0, 30.0);
+        slider.gravity = 1000.0;
+        slider.vx = 300.0;
+        let mut icy = vec![slider.clone(), floor()];
+        slider.friction = 1.0;
+        let mut rough = vec![slider, floor()];
+        run(&mut icy, 60);
+        run(&mut rough, 60);
+        assert_eq!(icy[0].vx, 300.0);
+        assert_eq!(rough[0].vx, 0.0);
+        assert!(rough[0].x < icy[0].x);
+    }
+
+    #[test]
+    fn test_bounciness_scales_rebound() {
+        let mut ball = rect_at(100.0, 485.0, 10.0, 10.0);
+        ball.vy = 600.0;
+        ball.bounces = true;
+        ball.bounciness = 0.5;
+        let mut objs = vec![ball, floor()];
+        run(&mut objs, 1);
+        assert_eq!(objs[0].vy, -300.0);
+    }
+
+    #[test]
+    fn test_mass_pushes_lighter_mover() {
+        let mut heavy = rect_at(100.0, 100.0, 20.0, 20.0);
+        heavy.mass = 3.0;
+        heavy.vx = 120.0;
+        let mut light = rect_at(121.0, 100.0, 20.0, 20.0);
+        light.mass = 1.0;
+        let ghost = rect_at(121.0, 100.0, 20.0, 20.0);
+        let mut objs = vec![heavy, light, ghost];
+        run(&mut objs, 1);
+        // Momentum is shared: 3 * 120 = 4 * v
+        assert!((objs[0].vx - 90.0).abs() < 1e-9);
+        assert!((objs[1].vx - 90.0).abs() < 1e-9);
+        assert!(objs[1].x > 121.0);
+        // Movers without mass pass through
+        assert_eq!(objs[2].x, 121.0);
+        assert!(!objs[0].contacts.contains(&2));
+    }
+
+    #[test]
+    fn test_weightless_stack_gains_no_energy() {
+        // Without gravity nothing rests on anything, so the middle block
+        // can't act as a wall and fling the top one off at twice its speed
+        let bottom = rect_at(0.0, 17.0, 10.0, 10.0);
+        let mut middle = rect_at(0.0, 9.5, 10.0, 10.0);
+        middle.vy = -100.0;
+        let top = rect_at(0.0, 0.0, 10.0, 10.0);
+        let mut objs = vec![bottom, middle, top];
+        for o in &mut objs {
+            o.mass = 1.0;
+            o.bounces = true;
+        }
+        let energy = |objs: &[GameObject]| objs.iter().map(|o| o.vx * o.vx + o.vy * o.vy).sum::<f64>();
+        let before = energy(&objs);
+        run(&mut objs, 1);
+        assert!(energy(&objs) <= before + 1e-9);
+    }
+
+    #[test]
+    fn test_ball_bounces_off_corner_along_normal() {
+        let mut ball = GameObject::new(ObjectKind::Circle, 5.0, 5.0);
+        ball.x = 97.0;
+        ball.y = 97.0;
+        ball.vx = 60.0;
+        ball.vy = 60.0;
+        ball.bounces = true;
+        let mut block = rect_at(100.0, 100.0, 50.0, 50.0);
+        block.solid = true;
+        let mut objs = vec![ball.clone(), block.clone()];
+        run(&mut objs, 1);
+        assert!((objs[0].vx + 60.0).abs() < 1e-9 && (objs[0].vy + 60.0).abs() < 1e-9);
+
+        // As a box it catches the corner on one axis only
+        ball.collision_shape = crate::object::CollisionShape::Box;
+        let mut objs = vec![ball, block];
+        run(&mut objs, 1);
+        assert_eq!((objs[0].vx, objs[0].vy), (60.0, -60.0));
+    }
+
+    fn tile_floor(csv: &str) -> GameObject {
+        let map = crate::tilemap::Tilemap::parse_csv(csv).unwrap();
+        let mut o = GameObject::new(ObjectKind::Tilemap(0), map.columns as f64 * 20.0, map.rows as f64 * 20.0);
+        o.sheet = Some(crate::animation::SpriteSheet { frame_w: 20, frame_h: 20 });
+        o.tilemap = Some(Box::new(map));
+        o.solid = true;
+        o.y = 500.0;
+        o
+    }
+
+    #[test]
+    fn test_slides_across_tile_seams() {
+        // Floor row with a wall at the end
+        let mut slider = rect_at(10.0, 470.0, 30.0, 30.0);
+        slider.gravity = 1000.0;
+        // Slow enough that each seam looks like a tiny step up
+        slider.vx = 12.0;
+        let mut objs = vec![slider, tile_floor("-1,-1,-1,-1,-1,-1,-1,-1,-1,-1,0\n0,0,0,0,0,0,0,0,0,0,0")];
+        run(&mut objs, 300);
+        assert!((objs[0].y - 490.0).abs() < 1e-6, "y = {}", objs[0].y);
+        assert_eq!(objs[0].vx, 12.0, "stuck at x = {}", objs[0].x);
+        assert!(objs[0].on_ground);
+        assert!(objs[1].contacts.contains(&0));
+        objs[0].vx = 300.0;
+        run(&mut objs, 60);
+        // Stopped by the wall tile at x = 200
+        assert!((objs[0].x - 170.0).abs() < 1e-6, "x = {}", objs[0].x);
+    }
+
+    #[test]
+    fn test_wraps_to_the_opposite_edge() {
+        let mut rock = rect_at(790.0, 300.0, 20.0, 20.0);
+        rock.vx = 600.0;
+        rock.vy = -600.0;
+        rock.wraps = true;
+        let mut objs = vec![rock];
+        // Just touching the edge from outside isn't past it
+        step(&mut objs, 1.0 / 60.0, 800.0, 600.0);
+        assert_eq!((objs[0].x, objs[0].y), (800.0, 290.0));
+        step(&mut objs, 1.0 / 60.0, 800.0, 600.0);
+        assert_eq!((objs[0].x, objs[0].y), (-10.0, 280.0));
+        objs[0].y = -25.0;
+        step(&mut objs, 1.0 / 60.0, 800.0, 600.0);
+        assert_eq!(objs[0].y, 585.0);
+    }
+
+    #[test]
+    fn test_lifetime_and_offscreen_removal() {
+        let screen = (0.0, 0.0, 800.0, 600.0);
+        let mut shot = rect_at(100.0, 100.0, 4.0, 4.0);
+        shot.lifetime = Some(0.5);
+        let mut bullet = rect_at(100.0, 10.0, 4.0, 4.0);
+        bullet.remove_offscreen = true;
+        // Not removed for starting off screen, only for leaving it
+        let mut incoming = rect_at(100.0, -50.0, 4.0, 4.0);
+        incoming.remove_offscreen = true;
+        let mut objs = vec![shot, bullet, incoming];
+        assert!(count_down(&mut objs, 0.25).is_empty());
+        assert!(offscreen(&mut objs, screen, (800.0, 600.0)).is_empty());
+        objs[1].y = -10.0;
+        assert_eq!(count_down(&mut objs, 0.25), vec![0]);
+        assert_eq!(offscreen(&mut objs, screen, (800.0, 600.0)), vec![1]);
+        objs[0].alive = false;
+        objs[1].alive = false;
+        // The world area on screen moves with the camera
+        objs[2].y = 10.0;
+        assert!(offscreen(&mut objs, screen, (800.0, 600.0)).is_empty());
+        assert_eq!(offscreen(&mut objs, (0.0, 100.0, 800.0, 700.0), (800.0, 600.0)), vec![2]);
+    }
+
+    #[test]
+    fn test_passable_tiles_let_objects_through() {
+        let mut ball = rect_at(10.0, 470.0, 10.0, 10.0);
+        ball.gravity = 1000.0;
+        let mut floor = tile_floor("1,1");
+        floor.tilemap.as_mut().unwrap().set_solid(1, false);
+        let mut objs = vec![ball, floor];
+        run(&mut objs, 60);
+        assert!(objs[0].y > 520.0);
+    }
+}
@@ -0,0 +1,549 @@
+//! The boundary between the core and a backend.
+//!
+//! The core decides *what* happens each frame; a backend only knows how to put
+//! pixels on a screen ([`Renderer`]), make noise ([`AudioSink`]) and collect
+//! key and mouse events ([`InputSource`]).
+
+use crate::framebuffer::Framebuffer;
+use std::collections::HashMap;
+
+/// An RGB color with alpha: 0 = invisible, 255 = opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Build a color from the i64 components the ABI passes around.
+    pub fn from_i64(r: i64, g: i64, b: i64) -> Self {
+        Self::rgb(r as u8, g as u8, b as u8)
+    }
+
+    /// Like [`from_i64`](Self::from_i64), with components clamped to 0..=255.
+    pub fn from_rgba_i64(r: i64, g: i64, b: i64, a: i64) -> Self {
+        let c = |v: i64| v.clamp(0, 255) as u8;
+        Self::rgba(c(r), c(g), c(b), c(a))
+    }
+
+    /// The same color with its alpha scaled by `share` (0..=1).
+    pub fn faded(self, share: f64) -> Self {
+        Self { a: (self.a as f64 * share.clamp(0.0, 1.0)).round() as u8, ..self }
+    }
+
+    /// Part way from `self` to `other`, alpha included; `t` runs 0..=1.
+    pub fn mix(self, other: Color, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Self::rgba(lerp(self.r, other.r), lerp(self.g, other.g), lerp(self.b, other.b), lerp(self.a, other.a))
+    }
+}
+
+/// How drawing combines with the pixels already on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Paint over, letting the old pixels show through by the alpha.
+    #[default]
+    Normal,
+    /// Brighten: add the color, scaled by its alpha. Good for light and fire.
+    Add,
+    /// Darken: multiply by the color, scaled by its alpha. Good for shadows.
+    Multiply,
+    /// Write the color as is, ignoring alpha.
+    Replace,
+}
+
+impl BlendMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "normal" => Some(Self::Normal),
+            "add" | "additive" => Some(Self::Add),
+            "multiply" => Some(Self::Multiply),
+            "replace" | "none" => Some(Self::Replace),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Add => "add",
+            Self::Multiply => "multiply",
+            Self::Replace => "replace",
+        }
+    }
+
+    /// `src` combined with the RGBA pixel `dst`. Where `dst` is see-through
+    /// (an offscreen layer) there is nothing to add to or multiply, so the
+    /// color is kept as drawn and its alpha builds up; a layer of normal
+    /// drawing composited later looks as if it had been drawn on screen.
+    pub fn blend(self, src: Color, dst: [u8; 4]) -> [u8; 4] {
+        self.blend_rgb([src.r, src.g, src.b], src.a as f64 / 255.0, dst)
+    }
+
+    /// [`blend`](Self::blend) with the alpha as 0..=1, for callers that
+    /// scale it (image pixels by opacity) and shouldn't round it first.
+    pub fn blend_rgb(self, src: [u8; 3], a: f64, dst: [u8; 4]) -> [u8; 4] {
+        if self == Self::Replace {
+            return [src[0], src[1], src[2], 255];
+        }
+        let da = dst[3] as f64 / 255.0;
+        let out_a = a + da * (1.0 - a);
+        if out_a <= 0.0 {
+            return [0; 4];
+        }
+        let mix = |s: u8, d: u8| {
+            let (s, d) = (s as f64, d as f64);
+            let over = match self {
+                Self::Add => d + s,
+                Self::Multiply => d * s / 255.0,
+                _ => s,
+            };
+            let s = (1.0 - da) * s + da * over;
+            ((a * s + (1.0 - a) * da * d) / out_a).round().clamp(0.0, 255.0) as u8
+        };
+        [mix(src[0], dst[0]), mix(src[1], dst[1]), mix(src[2], dst[2]), (out_a * 255.0).round() as u8]
+    }
+}
+
+/// Where and how [`Renderer::draw_sprite`] puts an image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteDraw {
+    /// Center of the image on screen.
+    pub x: f64,
+    pub y: f64,
+    /// Drawn size in pixels, before rotation.
+    pub w: f64,
+    pub h: f64,
+    /// Clockwise, in degrees, around the center.
+    pub rotation: f64,
+    /// 0 = invisible, 1 = as opaque as the image.
+    pub opacity: f64,
+    /// Mirror left-right / top-bottom.
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Part of the image to draw as [x, y, w, h]; `None` draws all of it.
+    pub source: Option<[u32; 4]>,
+}
+
+/// How [`Renderer::draw_surface`] puts an offscreen layer on the target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerDraw {
+    /// Where the surface's top-left corner goes.
+    pub x: f64,
+    pub y: f64,
+    /// 0 = invisible, 1 = as opaque as drawn.
+    pub opacity: f64,
+    /// Blur radius in pixels; 0 = sharp.
+    pub blur: f64,
+    /// 0 = full color, 1 = shades of gray.
+    pub grayscale: f64,
+    /// Multiplies every color; 1 = unchanged.
+    pub brightness: f64,
+}
+
+impl Default for LayerDraw {
+    fn default() -> Self {
+        Self { x: 0.0, y: 0.0, opacity: 1.0, blur: 0.0, grayscale: 0.0, brightness: 1.0 }
+    }
+}
+
+impl LayerDraw {
+    /// True when the layer's pixels go on unchanged, apart from opacity.
+    pub fn has_effects(&self) -> bool {
+        self.blur > 0.0 || self.grayscale > 0.0 || self.brightness != 1.0
+    }
+}
+
+pub trait Renderer {
+    /// Create the window (or canvas, or framebuffer). Drawing before this is a no-op.
+    fn open_screen(&mut self, width: i64, height: i64);
+
+    /// Fill the whole target with exactly `color`, alpha included, whatever
+    /// the blend mode. The screen is only ever cleared to opaque colors.
+    fn clear(&mut self, color: Color);
+
+    /// Fill a rectangle, combined with the screen by the current blend mode.
+    fn fill_rect(&mut self, x: i64, y: i64, w: i64, h: i64, color: Color);
+
+    /// How later drawing combines with the screen, until set again.
+    fn set_blend(&mut self, mode: BlendMode);
+
+    fn set_pixel(&mut self, x: i64, y: i64, color: Color) {
+        self.fill_rect(x, y, 1, 1, color);
+    }
+
+    /// Bresenham line, endpoints included.
+    fn draw_line(&mut self, x1: i64, y1: i64, x2: i64, y2: i64, color: Color) {
+        let (mut x, mut y) = (x1, y1);
+        let dx = (x2 - x1).abs();
+        let dy = -(y2 - y1).abs();
+        let sx = if x1 < x2 { 1 } else { -1 };
+        let sy = if y1 < y2 { 1 } else { -1 };
+        let mut err = dx + dy;
+        loop {
+            self.set_pixel(x, y, color);
+            if x == x2 && y == y2 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Filled circle from midpoint-algorithm scanlines, so every backend
+    /// produces the same pixels. Each row is drawn once, so see-through
+    /// colors blend evenly.
+    fn fill_circle(&mut self, cx: i64, cy: i64, radius: i64, color: Color) {
+        if radius < 0 {
+            return;
+        }
+        // Half-width of each row, from the top of the circle down
+        let mut rows = vec![0; 2 * radius as usize + 1];
+        let mut x = radius;
+        let mut y = 0;
+        let mut d = 1 - x;
+        while x >= y {
+            for (dy, half) in [(y, x), (-y, x), (x, y), (-x, y)] {
+                let row = &mut rows[(dy + radius) as usize];
+                *row = (*row).max(half);
+            }
+            y += 1;
+            if d <= 0 {
+                d += 2 * y + 1;
+            } else {
+                x -= 1;
+                d += 2 * (y - x) + 1;
+            }
+        }
+        for (i, half) in rows.into_iter().enumerate() {
+            self.fill_rect(cx - half, cy - radius + i as i64, 2 * half + 1, 1, color);
+        }
+    }
+
+    /// Rectangle outline `thickness` pixels wide, drawn inside the
+    /// rectangle without overlapping itself.
+    fn stroke_rect(&mut self, x: i64, y: i64, w: i64, h: i64, thickness: i64, color: Color) {
+        if w <= 0 || h <= 0 || thickness <= 0 {
+            return;
+        }
+        if 2 * thickness >= w.min(h) {
+            self.fill_rect(x, y, w, h, color);
+            return;
+        }
+        let t = thickness;
+        self.fill_rect(x, y, w, t, color);
+        self.fill_rect(x, y + h - t, w, t, color);
+        self.fill_rect(x, y + t, t, h - 2 * t, color);
+        self.fill_rect(x + w - t, y + t, t, h - 2 * t, color);
+    }
+
+    /// Line `thickness` pixels wide with round ends, between points in
+    /// pixel coordinates (a pixel's center is at +0.5). Anti-aliased edges
+    /// blend by how much of each pixel the line covers.
+    fn draw_thick_line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), thickness: f64, antialias: bool, color: Color) {
+        let half = thickness / 2.0;
+        if half <= 0.0 {
+            return;
+        }
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let len2 = dx * dx + dy * dy;
+        let reach = half + 1.0;
+        let (left, right) = (x1.min(x2) - reach, x1.max(x2) + reach);
+        let span = |cy: f64| {
+            if dy.abs() < 1e-9 {
+                return Some((left, right));
+            }
+            // Where the row crosses the line, widened to its distance `reach`
+            let at = x1 + (cy - y1) * dx / dy;
+            let width = reach * len2.sqrt() / dy.abs();
+            Some(((at - width).max(left), (at + width).min(right)))
+        };
+        let coverage = |px: f64, py: f64| {
+            let t = if len2 == 0.0 { 0.0 } else { (((px - x1) * dx + (py - y1) * dy) / len2).clamp(0.0, 1.0) };
+            half + 0.5 - (px - x1 - t * dx).hypot(py - y1 - t * dy)
+        };
+        fill_coverage(self, (y1.min(y2) - reach, y1.max(y2) + reach), span, coverage, antialias, color);
+    }
+
+    /// Filled circle around a point in pixel coordinates, either
+    /// anti-aliased or covering the pixels whose centers are inside.
+    fn fill_circle_smooth(&mut self, (cx, cy): (f64, f64), radius: f64, antialias: bool, color: Color) {
+        self.stroke_circle((cx, cy), radius, radius, antialias, color);
+    }
+
+    /// Ring `thickness` pixels wide just inside a circle's edge.
+    fn stroke_circle(&mut self, (cx, cy): (f64, f64), radius: f64, thickness: f64, antialias: bool, color: Color) {
+        if radius <= 0.0 || thickness <= 0.0 {
+            return;
+        }
+        let (outer, inner) = (radius, (radius - thickness).max(0.0));
+        let reach = outer + 1.0;
+        let span = |y: f64| {
+            let dy = y - cy;
+            (dy.abs() <= reach).then(|| {
+                let half = (reach * reach - dy * dy).sqrt();
+                (cx - half, cx + half)
+            })
+        };
+        let coverage = |px: f64, py: f64| {
+            let d = (px - cx).hypot(py - cy);
+            let outside = outer + 0.5 - d;
+            if inner > 0.0 { outside.min(d - inner + 0.5) } else { outside }
+        };
+        fill_coverage(self, (cy - reach, cy + reach), span, coverage, antialias, color);
+    }
+
+    /// Rectangle shading from `from` to `to`, top to bottom when
+    /// `vertical`, otherwise left to right.
+    fn fill_gradient(&mut self, (x, y, w, h): (i64, i64, i64, i64), from: Color, to: Color, vertical: bool) {
+        if w <= 0 || h <= 0 {
+            return;
+        }
+        let steps = if vertical { h } else { w };
+        for i in 0..steps {
+            let color = from.mix(to, if steps == 1 { 0.0 } else { i as f64 / (steps - 1) as f64 });
+            if vertical {
+                self.fill_rect(x, y + i, w, 1, color);
+            } else {
+                self.fill_rect(x + i, y, 1, h, color);
+            }
+        }
+    }
+
+    /// Filled polygon, sampled at pixel centers with the even-odd rule, so
+    /// every backend produces the same pixels.
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+        let top = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let bottom = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let mut crossings = Vec::new();
+        for y in (top - 0.5).ceil() as i64..(bottom - 0.5).ceil() as i64 {
+            let cy = y as f64 + 0.5;
+            crossings.clear();
+            for (i, &(x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(i + 1) % points.len()];
+                if (y1 <= cy) != (y2 <= cy) {
+                    crossings.push(x1 + (cy - y1) / (y2 - y1) * (x2 - x1));
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                // Pixels whose centers lie inside the span
+                let x1 = (span[0] - 0.5).ceil() as i64;
+                let x2 = (span[1] - 0.5).ceil() as i64;
+                if x2 > x1 {
+                    self.fill_rect(x1, y, x2 - x1, 1, color);
+                }
+            }
+        }
+    }
+
+    /// Lines `thickness` pixels wide through `points`, back to the first when
+    /// `closed`, built from `fill_polygon` so every backend produces the same
+    /// pixels. Thick lines get round joints.
+    fn stroke_polygon(&mut self, points: &[(f64, f64)], closed: bool, thickness: f64, color: Color) {
+        let half = thickness / 2.0;
+        if half <= 0.0 || points.len() < 2 {
+            return;
+        }
+        let ends = if closed { points.len() } else { points.len() - 1 };
+        for i in 0..ends {
+            let ((x1, y1), (x2, y2)) = (points[i], points[(i + 1) % points.len()]);
+            let len = (x2 - x1).hypot(y2 - y1);
+            if len == 0.0 {
+                continue;
+            }
+            let (nx, ny) = (-(y2 - y1) / len * half, (x2 - x1) / len * half);
+            self.fill_polygon(&[(x1 + nx, y1 + ny), (x2 + nx, y2 + ny), (x2 - nx, y2 - ny), (x1 - nx, y1 - ny)], color);
+        }
+        if half > 1.0 {
+            let joints = if closed { points } else { &points[1..points.len() - 1] };
+            for &(x, y) in joints {
+                let disc: Vec<_> = (0..16)
+                    .map(|i| {
+                        let a = i as f64 * std::f64::consts::TAU / 16.0;
+                        (x + half * a.cos(), y + half * a.sin())
+                    })
+                    .collect();
+                self.fill_polygon(&disc, color);
+            }
+        }
+    }
+
+    /// Load an image file, returning a backend-specific id for `draw_sprite`.
+    fn load_image(&mut self, path: &str) -> Option<usize>;
+
+    /// Keep pixels made while the program runs, such as rendered text, as an
+    /// image for `draw_sprite`: a new one, or in place of `image`.
+    fn upload_image(&mut self, image: Option<usize>, pixels: Framebuffer) -> usize;
+
+    /// Pixel size of a loaded image, or None while it isn't available yet.
+    fn image_size(&self, image: usize) -> Option<(u32, u32)>;
+
+    /// Draw an image scaled, rotated, faded and flipped as `sprite` says,
+    /// blending partly transparent pixels over what's there.
+    fn draw_sprite(&mut self, image: usize, sprite: &SpriteDraw);
+
+    /// Draw many parts of one image, such as the tiles of a tilemap.
+    /// Backends that upload images before drawing should do it once here.
+    fn draw_sprites(&mut self, image: usize, sprites: &[SpriteDraw]) {
+        for sprite in sprites {
+            self.draw_sprite(image, sprite);
+        }
+    }
+
+    /// A new see-through surface the size of the screen, for a layer.
+    fn create_surface(&mut self) -> usize;
+
+    /// Send later drawing to `surface`, or back to the screen with None.
+    fn set_target(&mut self, surface: Option<usize>);
+
+    /// Draw `surface` on the current target as `layer` says, blending it
+    /// over what's there.
+    fn draw_surface(&mut self, surface: usize, layer: &LayerDraw);
+
+    /// Show everything drawn since the last present.
+    fn present(&mut self);
+
+    /// Copy of what's on screen, for screenshots. None if unsupported.
+    fn snapshot(&mut self) -> Option<Framebuffer> {
+        None
+    }
+}
+
+/// Draw the pixels of rows `rows.0..rows.1` that a shape covers. `span`
+/// gives the x range worth testing on a row (by its center y), and
+/// `coverage` how far inside the shape a pixel center is: 0.5 at the edge,
+/// 1 or more fully inside. With `antialias` each edge pixel is blended by
+/// its share; without, it's drawn when its center is inside. Fully covered
+/// runs become one `fill_rect`, so every pixel is drawn once.
+fn fill_coverage<R: Renderer + ?Sized>(
+    r: &mut R,
+    rows: (f64, f64),
+    span: impl Fn(f64) -> Option<(f64, f64)>,
+    coverage: impl Fn(f64, f64) -> f64,
+    antialias: bool,
+    color: Color,
+) {
+    for y in rows.0.floor() as i64..rows.1.ceil() as i64 {
+        let cy = y as f64 + 0.5;
+        let Some((left, right)) = span(cy) else { continue };
+        let end = right.ceil() as i64;
+        let mut run = None;
+        for x in left.floor() as i64..=end {
+            let share = if x < end { coverage(x as f64 + 0.5, cy).clamp(0.0, 1.0) } else { 0.0 };
+            let share = match antialias {
+                true => share,
+                false if share >= 0.5 => 1.0,
+                false => 0.0,
+            };
+            if share >= 1.0 {
+                run.get_or_insert(x);
+                continue;
+            }
+            if let Some(start) = run.take() {
+                r.fill_rect(start, y, x - start, 1, color);
+            }
+            if share > 0.0 {
+                r.fill_rect(x, y, 1, 1, color.faded(share));
+            }
+        }
+    }
+}
+
+pub trait AudioSink {
+    fn beep(&mut self, freq: i64, duration_ms: i64);
+
+    /// Preload a sound effect; returns false if it couldn't be loaded.
+    fn load_effect(&mut self, path: &str) -> bool;
+
+    fn play_effect(&mut self, path: &str);
+
+    fn set_effect_volume(&mut self, path: &str, volume: f64);
+}
+
+/// Keyboard and mouse state as the program sees it.
+#[derive(Debug, Default, Clone)]
+pub struct InputState {
+    /// Held keys by lowercase SDL key name ("left", "space", "a").
+    pub keys: HashMap<String, bool>,
+    pub mouse_x: i64,
+    pub mouse_y: i64,
+    /// The user closed the window.
+    pub quit: bool,
+}
+
+pub trait InputSource {
+    /// Apply everything that happened since the last poll.
+    fn poll(&mut self, input: &mut InputState);
+}
+
+/// A complete backend. The defaults suit any target with `std` I/O.
+pub trait Platform: Renderer + AudioSink + InputSource {
+    /// Monotonic clock in seconds, used for frame pacing.
+    fn now(&self) -> f64;
+
+    /// Wait until `deadline` on the [`now`](Platform::now) clock; called once
+    /// per frame. Backends that don't own their loop yield here instead.
+    fn wait_until(&mut self, deadline: f64);
+
+    /// Seconds since the Unix epoch, for `System.Time()`.
+    fn wall_time(&self) -> f64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+
+    fn sleep_ms(&mut self, ms: i64) {
+        std::thread::sleep(std::time::Duration::from_millis(ms as u64));
+    }
+
+    fn exit(&mut self, code: i32) -> ! {
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        std::process::exit(code);
+    }
+
+    /// Write program output; `text` carries its own newlines.
+    fn write_stdout(&mut self, text: &str) {
+        print!("{text}");
+    }
+
+    fn read_file(&mut self, path: &str) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    /// Read a text file that ships with the program, such as a map.
+    fn load_text(&mut self, path: &str) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    /// Read a binary file that ships with the program, such as a font.
+    fn load_data(&mut self, path: &str) -> Option<Vec<u8>> {
+        std::fs::read(path).ok()
+    }
+
+    fn write_file(&mut self, path: &str, data: &str) {
+        let _ = std::fs::write(path, data);
+    }
+}
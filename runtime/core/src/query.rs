@@ -0,0 +1,164 @@
+//! Finding objects by where they are: the first one along a ray, the ones
+//! in an area or near a point, and whether anything solid stands between
+//! two objects.
+//!
+//! Only objects in the world count, using their collision shapes: hidden
+//! objects, `ui` objects drawn over the world and emitters are left out.
+//! The store's spatial hash narrows each search to the objects around it.
+
+use crate::object::{GameObject, ObjectKind};
+use crate::store::ObjectStore;
+
+/// Where a ray first met an object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub index: usize,
+    pub x: f64,
+    pub y: f64,
+    pub distance: f64,
+}
+
+fn in_world(o: &GameObject) -> bool {
+    o.alive && o.visible && !o.ui && o.kind != ObjectKind::Emitter
+}
+
+/// The box spanning two corners, a pixel bigger all round so shapes just
+/// touching its edges are still looked at.
+fn span((ax, ay): (f64, f64), (bx, by): (f64, f64)) -> (f64, f64, f64, f64) {
+    (ax.min(bx) - 1.0, ay.min(by) - 1.0, ax.max(bx) + 1.0, ay.max(by) + 1.0)
+}
+
+/// Slots of the objects in the world that may overlap `area`, oldest first.
+fn around(store: &mut ObjectStore, area: (f64, f64, f64, f64)) -> Vec<usize> {
+    let mut found = store.near(area);
+    found.retain(|&i| in_world(&store.slots()[i]));
+    found.sort_by_key(|&i| store.serial(i));
+    found
+}
+
+/// The first object a ray from `from` toward `direction` meets within
+/// `max` pixels. Objects the ray starts inside are passed through, so a ray
+/// from an enemy's center doesn't stop at the enemy.
+pub fn raycast(store: &mut ObjectStore, from: (f64, f64), direction: (f64, f64), max: f64) -> Option<Hit> {
+    let length = direction.0.hypot(direction.1);
+    if length == 0.0 || max <= 0.0 {
+        return None;
+    }
+    let dir = (direction.0 / length, direction.1 / length);
+    let mut first: Option<Hit> = None;
+    let to = (from.0 + dir.0 * max, from.1 + dir.1 * max);
+    for index in around(store, span(from, to)) {
+        let Some(distance) = store.slots()[index].ray(from, dir, max) else { continue };
+        // Oldest first, so ties go to the oldest
+        if first.is_none_or(|hit| distance < hit.distance) {
+            let (x, y) = (from.0 + dir.0 * distance, from.1 + dir.1 * distance);
+            first = Some(Hit { index, x, y, distance });
+        }
+    }
+    first
+}
+
+/// Objects overlapping the object in slot `area`, oldest first. The area
+/// itself can be hidden.
+pub fn objects_in(store: &mut ObjectStore, area: usize) -> Vec<usize> {
+    let (x1, y1, x2, y2) = store.slots()[area].bounds();
+    let found = around(store, span((x1, y1), (x2, y2)));
+    let slots = store.slots();
+    found.into_iter().filter(|&i| i != area && slots[i].overlaps(&slots[area])).collect()
+}
+
+/// Objects within `radius` of a point, nearest first.
+pub fn objects_near(store: &mut ObjectStore, (x, y): (f64, f64), radius: f64) -> Vec<usize> {
+    let radius = radius.max(0.0);
+    let mut probe = GameObject::new(ObjectKind::Circle, radius, radius);
+    (probe.x, probe.y) = (x, y);
+    let area = span((x - radius, y - radius), (x + radius, y + radius));
+    let mut found = around(store, area);
+    found.retain(|&i| store.slots()[i].contains(x, y) || probe.overlaps(&store.slots()[i]));
+    let distance = |i: usize| {
+        let (cx, cy) = store.slots()[i].center();
+        (cx - x).hypot(cy - y)
+    };
+    found.sort_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+    found
+}
+
+/// Whether nothing solid stands between the centers of the objects in
+/// slots `a` and `b`. Neither of them blocks the view, and nor does
+/// anything solid either one is inside of.
+pub fn line_of_sight(store: &mut ObjectStore, a: usize, b: usize) -> bool {
+    let (from, to) = (store.slots()[a].center(), store.slots()[b].center());
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let distance = dx.hypot(dy);
+    if distance == 0.0 {
+        return true;
+    }
+    let dir = (dx / distance, dy / distance);
+    around(store, span(from, to))
+        .into_iter()
+        .filter(|&i| i != a && i != b && store.slots()[i].solid)
+        .all(|i| store.slots()[i].ray(from, dir, distance).is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect_at(store: &mut ObjectStore, x: f64, y: f64, w: f64, h: f64) -> usize {
+        let mut o = GameObject::new(ObjectKind::Rect, w, h);
+        (o.x, o.y) = (x, y);
+        let handle = store.insert(o);
+        store.index(handle).unwrap()
+    }
+
+    #[test]
+    fn test_raycast_finds_the_first_object() {
+        let mut store = ObjectStore::new();
+        let guard = rect_at(&mut store, 0.0, 0.0, 20.0, 20.0);
+        let far = rect_at(&mut store, 200.0, 0.0, 20.0, 20.0);
+        let near = rect_at(&mut store, 100.0, 5.0, 10.0, 10.0);
+        let hidden = rect_at(&mut store, 50.0, 0.0, 20.0, 20.0);
+        store.slots_mut()[hidden].visible = false;
+
+        // From inside the guard: it doesn't count
+        let hit = raycast(&mut store, (10.0, 10.0), (3.0, 0.0), 500.0).unwrap();
+        assert_eq!(hit, Hit { index: near, x: 100.0, y: 10.0, distance: 90.0 });
+        store.slots_mut()[near].alive = false;
+        assert_eq!(raycast(&mut store, (10.0, 10.0), (1.0, 0.0), 500.0).map(|h| h.index), Some(far));
+        assert_eq!(raycast(&mut store, (10.0, 10.0), (1.0, 0.0), 150.0), None);
+        assert_eq!(raycast(&mut store, (10.0, 10.0), (0.0, 0.0), 500.0), None);
+        assert_eq!(raycast(&mut store, (300.0, 10.0), (-1.0, 0.0), 500.0).map(|h| h.index), Some(far));
+        assert_eq!(raycast(&mut store, (-50.0, 10.0), (1.0, 0.0), 500.0).map(|h| (h.index, h.x)), Some((guard, 0.0)));
+    }
+
+    #[test]
+    fn test_area_queries() {
+        let mut store = ObjectStore::new();
+        let zone = rect_at(&mut store, 0.0, 0.0, 100.0, 100.0);
+        store.slots_mut()[zone].visible = false;
+        let inside = rect_at(&mut store, 10.0, 10.0, 10.0, 10.0);
+        let edge = rect_at(&mut store, 95.0, 50.0, 10.0, 10.0);
+        let outside = rect_at(&mut store, 150.0, 150.0, 10.0, 10.0);
+        assert_eq!(objects_in(&mut store, zone), vec![inside, edge]);
+
+        // The zone is hidden, so only the others are near the point
+        assert_eq!(objects_near(&mut store, (140.0, 140.0), 20.0), vec![outside]);
+        assert_eq!(objects_near(&mut store, (100.0, 50.0), 60.0), vec![edge]);
+        assert_eq!(objects_near(&mut store, (100.0, 50.0), 90.0), vec![edge, inside]);
+        assert_eq!(objects_near(&mut store, (15.0, 15.0), 0.0), vec![inside]);
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let mut store = ObjectStore::new();
+        let guard = rect_at(&mut store, 0.0, 0.0, 20.0, 20.0);
+        let player = rect_at(&mut store, 200.0, 0.0, 20.0, 20.0);
+        let crate_ = rect_at(&mut store, 100.0, 0.0, 20.0, 20.0);
+        assert!(line_of_sight(&mut store, guard, player));
+        store.slots_mut()[crate_].solid = true;
+        assert!(!line_of_sight(&mut store, guard, player));
+        assert!(!line_of_sight(&mut store, player, guard));
+        store.slots_mut()[crate_].y = 30.0;
+        assert!(line_of_sight(&mut store, guard, player));
+    }
+}
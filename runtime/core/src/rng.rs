@@ -0,0 +1,185 @@
+//! The runtime's random number generator.
+//!
+//! Every random function in a program draws from one [`Rng`], so a seed
+//! (from `Math.Seed`, `--seed` or a replay log) reproduces the whole run.
+
+/// xoshiro256** — fast, statistically solid and reproducible from a seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+/// splitmix64 step, used to spread a small seed over the 256-bit state.
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        Self { state: std::array::from_fn(|_| splitmix64(&mut x)) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform float in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Uniform integer in `min..=max`; returns `min` for an empty range.
+    pub fn range(&mut self, min: i64, max: i64) -> i64 {
+        if min >= max {
+            return min;
+        }
+        let span = max.wrapping_sub(min) as u64;
+        if span == u64::MAX {
+            return self.next_u64() as i64;
+        }
+        min.wrapping_add(self.below(span + 1) as i64)
+    }
+
+    /// Uniform integer in `0..n` without modulo bias (n > 0).
+    fn below(&mut self, n: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let v = self.next_u64();
+            if v < zone {
+                return v % n;
+            }
+        }
+    }
+
+    /// Uniform float in [min, max).
+    pub fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    /// True with probability `p` (clamped to 0..=1).
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p.clamp(0.0, 1.0)
+    }
+
+    /// Fisher–Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Index picked with probability proportional to its weight. Negative
+    /// weights count as zero; None if nothing has weight.
+    pub fn weighted(&mut self, weights: &[f64]) -> Option<usize> {
+        let total: f64 = weights.iter().map(|w| w.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = self.next_f64() * total;
+        for (i, w) in weights.iter().enumerate() {
+            let w = w.max(0.0);
+            if target < w {
+                return Some(i);
+            }
+            target -= w;
+        }
+        // Rounding left us past the end: take the last weighted item
+        weights.iter().rposition(|&w| w > 0.0)
+    }
+}
+
+/// Seed used when nothing else picks one.
+pub const DEFAULT_SEED: u64 = 12345;
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_range_is_inclusive_and_bounded() {
+        let mut rng = Rng::default();
+        let mut seen = [false; 4];
+        for _ in 0..1000 {
+            let v = rng.range(1, 4);
+            assert!((1..=4).contains(&v));
+            seen[(v - 1) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert_eq!(rng.range(5, 5), 5);
+        assert_eq!(rng.range(9, 2), 9);
+    }
+
+    #[test]
+    fn test_small_seeds_differ_immediately() {
+        let a = Rng::new(0).next_f64();
+        let b = Rng::new(1).next_f64();
+        assert!(a != b && a > 0.0 && b > 0.0);
+    }
+
+    #[test]
+    fn test_float_helpers() {
+        let mut rng = Rng::new(3);
+        for _ in 0..1000 {
+            let f = rng.next_f64();
+            assert!((0.0..1.0).contains(&f));
+            assert!((2.0..5.0).contains(&rng.range_f64(2.0, 5.0)));
+        }
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+        assert!(!rng.chance(-2.0) && rng.chance(3.0));
+        assert_eq!(rng.range(i64::MIN, i64::MAX).signum().abs(), 1);
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let mut rng = Rng::new(11);
+        let mut items: Vec<i64> = (0..50).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_weighted() {
+        let mut rng = Rng::new(5);
+        let mut counts = [0; 3];
+        for _ in 0..10_000 {
+            counts[rng.weighted(&[1.0, 0.0, 3.0]).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((2000..3000).contains(&counts[0]), "{counts:?}");
+        assert_eq!(rng.weighted(&[0.0, -1.0]), None);
+        assert_eq!(rng.weighted(&[]), None);
+    }
+}
@@ -0,0 +1,243 @@
+//! Generational storage for game objects.
+//!
+//! A handle packs a slot index (low 32 bits) with the slot's generation
+//! (high 32 bits). Removing an object bumps its slot's generation, so old
+//! handles stop working even after the slot is reused for a new object.
+
+use crate::broadphase::SpatialHash;
+use crate::object::GameObject;
+
+/// Why a handle doesn't name a live object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleError {
+    /// The object was removed.
+    Removed,
+    /// The value never was a game object handle.
+    Invalid,
+}
+
+impl std::fmt::Display for HandleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandleError::Removed => write!(f, "this object was removed"),
+            HandleError::Invalid => write!(f, "not a game object"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ObjectStore {
+    /// One slot per index; a slot whose object isn't `alive` is free.
+    objects: Vec<GameObject>,
+    generations: Vec<u32>,
+    /// Per slot: when its object was created, counting up from 0.
+    serials: Vec<u64>,
+    next_serial: u64,
+    /// Slots ready for reuse.
+    free: Vec<usize>,
+    /// Slots freed since the last [`ObjectStore::recycle`].
+    released: Vec<usize>,
+    /// Spatial hash for [`ObjectStore::near`]; None until it's next needed.
+    broadphase: Option<Broadphase>,
+}
+
+#[derive(Debug)]
+struct Broadphase {
+    hash: SpatialHash,
+    /// Tilemaps, which the hash leaves out.
+    maps: Vec<usize>,
+    /// Slots handed out for changing since the hash was built.
+    changed: Vec<usize>,
+}
+
+impl ObjectStore {
+    pub const fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            generations: Vec::new(),
+            serials: Vec::new(),
+            next_serial: 0,
+            free: Vec::new(),
+            released: Vec::new(),
+            broadphase: None,
+        }
+    }
+
+    /// Note that the object in slot `index` may have moved, dropping the
+    /// spatial hash once patching it would cost more than building anew.
+    fn changed(&mut self, index: usize) {
+        let limit = self.objects.len() / 4 + 16;
+        if let Some(broadphase) = &mut self.broadphase {
+            broadphase.changed.push(index);
+            if broadphase.changed.len() > limit {
+                self.broadphase = None;
+            }
+        }
+    }
+
+    fn handle(&self, index: usize) -> i64 {
+        ((self.generations[index] as i64) << 32) | index as i64
+    }
+
+    /// Handle of the object in slot `index`, if one lives there.
+    pub fn handle_of(&self, index: usize) -> Option<i64> {
+        self.objects.get(index).filter(|o| o.alive).map(|_| self.handle(index))
+    }
+
+    /// Store a new object, reusing a free slot when there is one.
+    pub fn insert(&mut self, obj: GameObject) -> i64 {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.objects[index] = obj;
+                index
+            }
+            None => {
+                self.objects.push(obj);
+                self.generations.push(1);
+                self.serials.push(0);
+                self.objects.len() - 1
+            }
+        };
+        self.serials[index] = self.next_serial;
+        self.next_serial += 1;
+        self.changed(index);
+        self.handle(index)
+    }
+
+    /// Slot index of a live object.
+    pub fn index(&self, handle: i64) -> Result<usize, HandleError> {
+        let index = (handle & 0xFFFF_FFFF) as usize;
+        let generation = (handle >> 32) as u32;
+        match self.generations.get(index) {
+            Some(&g) if g == generation && self.objects[index].alive => Ok(index),
+            Some(&g) if generation != 0 && generation < g => Err(HandleError::Removed),
+            _ => Err(HandleError::Invalid),
+        }
+    }
+
+    pub fn get(&self, handle: i64) -> Result<&GameObject, HandleError> {
+        self.index(handle).map(|i| &self.objects[i])
+    }
+
+    pub fn get_mut(&mut self, handle: i64) -> Result<&mut GameObject, HandleError> {
+        let index = self.index(handle)?;
+        self.changed(index);
+        Ok(&mut self.objects[index])
+    }
+
+    pub fn is_alive(&self, handle: i64) -> bool {
+        self.index(handle).is_ok()
+    }
+
+    /// Remove a live object. Its slot becomes reusable after the next
+    /// [`ObjectStore::recycle`], so contacts recorded this frame can't point
+    /// at a newcomer.
+    pub fn remove(&mut self, handle: i64) -> Result<(), HandleError> {
+        let index = self.index(handle)?;
+        self.objects[index].alive = false;
+        self.generations[index] = self.generations[index].wrapping_add(1).max(1);
+        self.released.push(index);
+        Ok(())
+    }
+
+    /// Make slots removed since the last call available for new objects.
+    pub fn recycle(&mut self) {
+        self.free.append(&mut self.released);
+    }
+
+    /// Creation order of the object in slot `index`; reused slots don't
+    /// make an object older than it is.
+    pub fn serial(&self, index: usize) -> u64 {
+        self.serials[index]
+    }
+
+    /// Every slot, live or not, indexed like [`ObjectStore::index`].
+    pub fn slots(&self) -> &[GameObject] {
+        &self.objects
+    }
+
+    pub fn slots_mut(&mut self) -> &mut [GameObject] {
+        self.broadphase = None;
+        &mut self.objects
+    }
+
+    /// Slots whose objects may overlap `area` (x1, y1, x2, y2), in slot
+    /// order: those whose bounding boxes do, every tilemap, and any object
+    /// changed since the spatial hash was built. Callers check the exact
+    /// shapes, and whether the objects are alive.
+    pub fn near(&mut self, area: (f64, f64, f64, f64)) -> Vec<usize> {
+        let objects = &self.objects;
+        let broadphase = self.broadphase.get_or_insert_with(|| Broadphase {
+            hash: SpatialHash::build(objects),
+            maps: (0..objects.len()).filter(|&i| objects[i].alive && objects[i].tilemap.is_some()).collect(),
+            changed: Vec::new(),
+        });
+        let mut found = broadphase.hash.query(area);
+        found.extend(&broadphase.maps);
+        found.extend(&broadphase.changed);
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::ObjectKind;
+
+    fn rect() -> GameObject {
+        GameObject::new(ObjectKind::Rect, 10.0, 10.0)
+    }
+
+    #[test]
+    fn test_removed_handle_stays_dead_after_reuse() {
+        let mut store = ObjectStore::new();
+        let a = store.insert(rect());
+        let b = store.insert(rect());
+        store.remove(a).unwrap();
+        assert_eq!(store.get(a).err(), Some(HandleError::Removed));
+        assert_eq!(store.remove(a), Err(HandleError::Removed));
+
+        // Not reused until recycled
+        let c = store.insert(rect());
+        assert_eq!(store.slots().len(), 3);
+        store.recycle();
+        let d = store.insert(rect());
+        assert_eq!(store.slots().len(), 3);
+        assert_eq!(store.index(d), Ok(0));
+        assert_ne!(d, a);
+        assert_eq!(store.get(a).err(), Some(HandleError::Removed));
+        assert!(store.is_alive(b) && store.is_alive(c) && store.is_alive(d));
+        assert_eq!(store.handle_of(0), Some(d));
+        assert!(store.serial(0) > store.serial(2));
+    }
+
+    #[test]
+    fn test_invalid_handles() {
+        let mut store = ObjectStore::new();
+        store.insert(rect());
+        assert_eq!(store.get(0).err(), Some(HandleError::Invalid));
+        assert_eq!(store.get(-1).err(), Some(HandleError::Invalid));
+        assert_eq!(store.get((1 << 32) | 5).err(), Some(HandleError::Invalid));
+        assert_eq!(store.get(7 << 32).err(), Some(HandleError::Invalid));
+    }
+
+    #[test]
+    fn test_near_sees_objects_changed_since_the_hash_was_built() {
+        let mut store = ObjectStore::new();
+        let a = store.insert(rect());
+        let b = store.insert(rect());
+        store.get_mut(b).unwrap().x = 500.0;
+        assert_eq!(store.near((-5.0, -5.0, 5.0, 5.0)), vec![0]);
+
+        // Moved and added after the hash was built: still candidates
+        store.get_mut(a).unwrap().x = 300.0;
+        let c = store.insert(rect());
+        assert_eq!(store.near((-5.0, -5.0, 5.0, 5.0)), vec![0, 2]);
+        assert_eq!(store.index(c), Ok(2));
+        store.slots_mut();
+        assert_eq!(store.near((-5.0, -5.0, 5.0, 5.0)), vec![2]);
+        assert_eq!(store.near((495.0, -5.0, 505.0, 5.0)), vec![1]);
+    }
+}
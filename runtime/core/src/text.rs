@@ -0,0 +1,64 @@
+//! What a `text()` object shows and how.
+
+use crate::framebuffer::Framebuffer;
+use crate::platform::Color;
+use crate::ttf::{Align, Font};
+use std::rc::Rc;
+
+/// Font size of new text objects, in pixels.
+pub const DEFAULT_SIZE: f64 = 20.0;
+
+#[derive(Clone)]
+pub struct Text {
+    pub content: String,
+    /// Font size in pixels.
+    pub size: f64,
+    /// File the font came from; "" for the built-in one.
+    pub font_path: String,
+    pub font: Rc<Font>,
+    pub align: Align,
+    /// Backend image holding the rendered text, once there is one.
+    pub image: Option<usize>,
+    /// Color the image was rendered in; None when it's out of date.
+    pub rendered: Option<Color>,
+}
+
+impl std::fmt::Debug for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Text")
+            .field("content", &self.content)
+            .field("size", &self.size)
+            .field("font_path", &self.font_path)
+            .field("align", &self.align)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Text {
+    pub fn new(content: String, font: Rc<Font>) -> Self {
+        Self {
+            content,
+            size: DEFAULT_SIZE,
+            font_path: String::new(),
+            font,
+            align: Align::Left,
+            image: None,
+            rendered: None,
+        }
+    }
+
+    /// Size of the rendered image; empty text still takes a line's height.
+    pub fn measure(&self) -> (f64, f64) {
+        let (w, h) = self.font.measure(&self.content, self.size);
+        (w.ceil().max(1.0), h.ceil().max(1.0))
+    }
+
+    pub fn render(&self, color: Color) -> Framebuffer {
+        self.font.render(&self.content, self.size, self.align, color)
+    }
+
+    /// Whether the image needs redrawing to show `color`.
+    pub fn needs_render(&self, color: Color) -> bool {
+        self.image.is_none() || self.rendered != Some(color)
+    }
+}
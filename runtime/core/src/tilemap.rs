@@ -0,0 +1,179 @@
+//! Tile grids loaded from CSV or Tiled `.json` maps.
+//!
+//! Tiles are numbered like the frames of a sprite sheet: 0 is the tileset's
+//! top-left tile, counting left to right, then top to bottom. -1 is no tile.
+
+use crate::json::Json;
+
+/// Tiled keeps flip flags in the top bits of each tile id.
+const TILED_FLIP_BITS: u32 = 0xE000_0000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tilemap {
+    pub columns: usize,
+    pub rows: usize,
+    /// Row by row; -1 is empty.
+    tiles: Vec<i64>,
+    /// Tileset tiles that objects pass through.
+    passable: Vec<i64>,
+}
+
+impl Tilemap {
+    /// Comma-separated tile numbers, one map row per line, as Tiled's CSV
+    /// export writes them. Empty cells count as -1; short rows are padded.
+    pub fn parse_csv(text: &str) -> Result<Self, String> {
+        let mut rows = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row = line
+                .trim()
+                .trim_end_matches(',')
+                .split(',')
+                .map(|cell| match cell.trim() {
+                    "" => Ok(-1),
+                    cell => cell.parse::<i64>().map(|t| t.max(-1)).map_err(|_| format!("line {}: \"{cell}\" isn't a tile number", n + 1)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut tiles = Vec::with_capacity(columns * rows.len());
+        for row in &rows {
+            tiles.extend(row.iter().copied().chain(std::iter::repeat(-1)).take(columns));
+        }
+        Ok(Self { columns, rows: rows.len(), tiles, passable: Vec::new() })
+    }
+
+    /// The first tile layer of a Tiled map saved as JSON, numbered from the
+    /// first tileset. Tileset tiles with a `solid` property set to false are
+    /// passable.
+    pub fn parse_tiled_json(text: &str) -> Result<Self, String> {
+        let map = Json::parse(text)?;
+        let layer = map
+            .get("layers")
+            .and_then(Json::as_array)
+            .and_then(|layers| layers.iter().find(|l| l.get("type").and_then(Json::as_str) == Some("tilelayer")))
+            .ok_or("the map has no tile layer")?;
+        if layer.get("chunks").is_some() {
+            return Err("infinite maps aren't supported; turn off \"Infinite\" in the map properties".into());
+        }
+        let data = layer
+            .get("data")
+            .and_then(Json::as_array)
+            .ok_or("the tile layer's data isn't a plain list; save it with CSV layer format")?;
+        let columns = layer.get("width").and_then(Json::as_f64).ok_or("the tile layer has no width")? as usize;
+        let rows = layer.get("height").and_then(Json::as_f64).ok_or("the tile layer has no height")? as usize;
+        if columns.checked_mul(rows) != Some(data.len()) {
+            return Err(format!("the tile layer has {} tiles, expected {columns}x{rows}", data.len()));
+        }
+
+        let tileset = map.get("tilesets").and_then(Json::as_array).and_then(|t| t.first());
+        let first_gid = tileset.and_then(|t| t.get("firstgid")).and_then(Json::as_f64).unwrap_or(1.0) as i64;
+        let tiles = data
+            .iter()
+            .map(|gid| {
+                let gid = (gid.as_f64().unwrap_or(0.0) as u32 & !TILED_FLIP_BITS) as i64;
+                if gid == 0 { -1 } else { gid - first_gid }
+            })
+            .collect();
+
+        let mut passable = Vec::new();
+        for tile in tileset.and_then(|t| t.get("tiles")).and_then(Json::as_array).unwrap_or_default() {
+            let solid = tile
+                .get("properties")
+                .and_then(Json::as_array)
+                .and_then(|props| props.iter().find(|p| p.get("name").and_then(Json::as_str) == Some("solid")))
+                .and_then(|p| p.get("value"))
+                .and_then(Json::as_bool);
+            if let (Some(false), Some(id)) = (solid, tile.get("id").and_then(Json::as_f64)) {
+                passable.push(id as i64);
+            }
+        }
+        Ok(Self { columns, rows, tiles, passable })
+    }
+
+    /// Tile in a cell; -1 for an empty cell or one outside the map.
+    pub fn tile(&self, col: i64, row: i64) -> i64 {
+        if col < 0 || row < 0 || col as usize >= self.columns || row as usize >= self.rows {
+            return -1;
+        }
+        self.tiles[row as usize * self.columns + col as usize]
+    }
+
+    /// Change a cell; cells outside the map are ignored.
+    pub fn set_tile(&mut self, col: i64, row: i64, tile: i64) {
+        if col < 0 || row < 0 || col as usize >= self.columns || row as usize >= self.rows {
+            return;
+        }
+        self.tiles[row as usize * self.columns + col as usize] = tile.max(-1);
+    }
+
+    /// Whether objects collide with this tileset tile. Every tile is solid
+    /// until told otherwise.
+    pub fn set_solid(&mut self, tile: i64, solid: bool) {
+        self.passable.retain(|&t| t != tile);
+        if !solid {
+            self.passable.push(tile);
+        }
+    }
+
+    /// A cell holding a tile objects collide with.
+    pub fn is_solid(&self, col: i64, row: i64) -> bool {
+        let tile = self.tile(col, row);
+        tile >= 0 && !self.passable.contains(&tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv() {
+        let map = Tilemap::parse_csv("0,1,-1\n\n2,,\n3\n").unwrap();
+        assert_eq!((map.columns, map.rows), (3, 3));
+        assert_eq!(map.tile(1, 0), 1);
+        assert_eq!(map.tile(1, 1), -1);
+        assert_eq!(map.tile(2, 2), -1);
+        assert_eq!(map.tile(3, 0), -1);
+        assert!(Tilemap::parse_csv("0,x").is_err());
+    }
+
+    #[test]
+    fn test_tiled_json() {
+        let text = r#"{
+            "width": 2, "height": 2, "tilewidth": 16, "tileheight": 16,
+            "layers": [
+                {"type": "objectgroup", "objects": []},
+                {"type": "tilelayer", "width": 2, "height": 2, "data": [1, 0, 3, 2147483650]}
+            ],
+            "tilesets": [{"firstgid": 1, "tiles": [
+                {"id": 2, "properties": [{"name": "solid", "type": "bool", "value": false}]}
+            ]}]
+        }"#;
+        let map = Tilemap::parse_tiled_json(text).unwrap();
+        assert_eq!((map.tile(0, 0), map.tile(1, 0), map.tile(0, 1)), (0, -1, 2));
+        // Flipped tile keeps its number
+        assert_eq!(map.tile(1, 1), 1);
+        assert!(map.is_solid(0, 0));
+        assert!(!map.is_solid(0, 1));
+        assert!(Tilemap::parse_tiled_json(r#"{"layers": []}"#).is_err());
+        let huge = r#"{"layers": [{"type": "tilelayer", "width": 1e10, "height": 1e10, "data": [1]}]}"#;
+        assert_eq!(Tilemap::parse_tiled_json(huge).err().unwrap(), "the tile layer has 1 tiles, expected 10000000000x10000000000");
+    }
+
+    #[test]
+    fn test_edit_tiles_and_solidity() {
+        let mut map = Tilemap::parse_csv("0,0\n1,1").unwrap();
+        map.set_tile(1, 0, -1);
+        map.set_tile(5, 5, 0);
+        assert_eq!(map.tile(1, 0), -1);
+        assert!(!map.is_solid(1, 0));
+        map.set_solid(1, false);
+        assert!(!map.is_solid(0, 1));
+        map.set_solid(1, true);
+        assert!(map.is_solid(0, 1));
+    }
+}
@@ -0,0 +1,580 @@
+//! Just enough TrueType to draw text: character to glyph mapping, advance
+//! widths, glyph outlines and an anti-aliased rasterizer.
+//!
+//! Only fonts with TrueType (`glyf`) outlines are read; kerning and ligatures
+//! are ignored.
+
+use crate::framebuffer::Framebuffer;
+use crate::platform::Color;
+
+/// DejaVu Sans cut down to Latin-1 and a few symbols; see `fonts/README.md`.
+pub const DEFAULT_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans-Latin.ttf");
+
+/// How lines of text line up with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "left" => Some(Self::Left),
+            "center" | "centre" => Some(Self::Center),
+            "right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        }
+    }
+
+    /// Share of a line's width to its left when lined up in a wider box.
+    pub fn factor(self) -> f64 {
+        match self {
+            Self::Left => 0.0,
+            Self::Center => 0.5,
+            Self::Right => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cmap {
+    /// Segment mapping to delta values (BMP only).
+    Format4(usize),
+    /// Segmented coverage (all of Unicode).
+    Format12(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Font {
+    data: Vec<u8>,
+    units_per_em: f64,
+    ascent: f64,
+    descent: f64,
+    line_gap: f64,
+    num_glyphs: u16,
+    num_h_metrics: u16,
+    long_loca: bool,
+    cmap: Cmap,
+    loca: usize,
+    glyf: usize,
+    hmtx: usize,
+}
+
+/// A point of a glyph outline in font units.
+#[derive(Debug, Clone, Copy)]
+struct OutlinePoint {
+    x: f64,
+    y: f64,
+    on_curve: bool,
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn i16_at(data: &[u8], at: usize) -> Option<i16> {
+    u16_at(data, at).map(|v| v as i16)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+impl Font {
+    pub fn parse(data: Vec<u8>) -> Result<Self, String> {
+        let table = |tag: &[u8; 4]| -> Option<usize> {
+            let count = u16_at(&data, 4)? as usize;
+            (0..count)
+                .map(|i| 12 + 16 * i)
+                .find(|&rec| data.get(rec..rec + 4) == Some(&tag[..]))
+                .and_then(|rec| u32_at(&data, rec + 8))
+                .map(|off| off as usize)
+        };
+        if !matches!(u32_at(&data, 0), Some(0x0001_0000) | Some(0x7472_7565)) {
+            return Err("not a TrueType font".into());
+        }
+        let missing = |name: &str| format!("the font has no {name} table");
+        let head = table(b"head").ok_or_else(|| missing("head"))?;
+        let hhea = table(b"hhea").ok_or_else(|| missing("hhea"))?;
+        let maxp = table(b"maxp").ok_or_else(|| missing("maxp"))?;
+        let cmap = table(b"cmap").ok_or_else(|| missing("cmap"))?;
+        let loca = table(b"loca").ok_or_else(|| missing("loca (it may use PostScript outlines)"))?;
+        let glyf = table(b"glyf").ok_or_else(|| missing("glyf (it may use PostScript outlines)"))?;
+        let hmtx = table(b"hmtx").ok_or_else(|| missing("hmtx"))?;
+
+        let truncated = || "the font file is cut short".to_string();
+        let units_per_em = u16_at(&data, head + 18).ok_or_else(truncated)? as f64;
+        let long_loca = i16_at(&data, head + 50).ok_or_else(truncated)? != 0;
+        let ascent = i16_at(&data, hhea + 4).ok_or_else(truncated)? as f64;
+        let descent = i16_at(&data, hhea + 6).ok_or_else(truncated)? as f64;
+        let line_gap = i16_at(&data, hhea + 8).ok_or_else(truncated)? as f64;
+        let num_h_metrics = u16_at(&data, hhea + 34).ok_or_else(truncated)?;
+        let num_glyphs = u16_at(&data, maxp + 4).ok_or_else(truncated)?;
+        if units_per_em == 0.0 || num_h_metrics == 0 {
+            return Err("the font's metrics are broken".into());
+        }
+
+        // Prefer a full-Unicode map, then a BMP one
+        let mut best = None;
+        for i in 0..u16_at(&data, cmap + 2).ok_or_else(truncated)? as usize {
+            let rec = cmap + 4 + 8 * i;
+            let (Some(platform), Some(encoding), Some(offset)) = (u16_at(&data, rec), u16_at(&data, rec + 2), u32_at(&data, rec + 4)) else {
+                return Err(truncated());
+            };
+            let sub = cmap + offset as usize;
+            let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+            match u16_at(&data, sub) {
+                Some(12) if unicode => best = Some(Cmap::Format12(sub)),
+                Some(4) if unicode && best.is_none() => best = Some(Cmap::Format4(sub)),
+                _ => {}
+            }
+        }
+        let cmap = best.ok_or("the font has no Unicode character map")?;
+
+        Ok(Self { data, units_per_em, ascent, descent, line_gap, num_glyphs, num_h_metrics, long_loca, cmap, loca, glyf, hmtx })
+    }
+
+    /// The built-in font.
+    pub fn default_font() -> Self {
+        Self::parse(DEFAULT_FONT.to_vec()).expect("built-in font")
+    }
+
+    fn glyph_index(&self, ch: char) -> u16 {
+        let c = ch as u32;
+        let d = &self.data;
+        match self.cmap {
+            Cmap::Format4(sub) => {
+                if c > 0xFFFF {
+                    return 0;
+                }
+                let segs = u16_at(d, sub + 6).unwrap_or(0) as usize / 2;
+                let ends = sub + 14;
+                let starts = ends + 2 * segs + 2;
+                let deltas = starts + 2 * segs;
+                let range_offsets = deltas + 2 * segs;
+                for s in 0..segs {
+                    let end = u16_at(d, ends + 2 * s).unwrap_or(0) as u32;
+                    if c > end {
+                        continue;
+                    }
+                    let start = u16_at(d, starts + 2 * s).unwrap_or(0) as u32;
+                    if c < start {
+                        return 0;
+                    }
+                    let delta = u16_at(d, deltas + 2 * s).unwrap_or(0);
+                    let range_offset = u16_at(d, range_offsets + 2 * s).unwrap_or(0) as usize;
+                    if range_offset == 0 {
+                        return (c as u16).wrapping_add(delta);
+                    }
+                    let at = range_offsets + 2 * s + range_offset + 2 * (c - start) as usize;
+                    return match u16_at(d, at).unwrap_or(0) {
+                        0 => 0,
+                        g => g.wrapping_add(delta),
+                    };
+                }
+                0
+            }
+            Cmap::Format12(sub) => {
+                // No more groups than the table has room for, whatever it claims
+                let room = d.len().saturating_sub(sub + 16) / 12;
+                let groups = (u32_at(d, sub + 12).unwrap_or(0) as usize).min(room);
+                for g in 0..groups {
+                    let at = sub + 16 + 12 * g;
+                    let (start, end, first) = (u32_at(d, at), u32_at(d, at + 4), u32_at(d, at + 8));
+                    if let (Some(start), Some(end), Some(first)) = (start, end, first)
+                        && (start..=end).contains(&c)
+                    {
+                        return first.checked_add(c - start).and_then(|g| u16::try_from(g).ok()).unwrap_or(0);
+                    }
+                }
+                0
+            }
+        }
+    }
+
+    fn advance(&self, glyph: u16) -> f64 {
+        let i = glyph.min(self.num_h_metrics - 1) as usize;
+        u16_at(&self.data, self.hmtx + 4 * i).unwrap_or(0) as f64
+    }
+
+    fn glyph_data(&self, glyph: u16) -> Option<&[u8]> {
+        if glyph >= self.num_glyphs {
+            return None;
+        }
+        let g = glyph as usize;
+        let (start, end) = if self.long_loca {
+            (u32_at(&self.data, self.loca + 4 * g)? as usize, u32_at(&self.data, self.loca + 4 * g + 4)? as usize)
+        } else {
+            (u16_at(&self.data, self.loca + 2 * g)? as usize * 2, u16_at(&self.data, self.loca + 2 * g + 2)? as usize * 2)
+        };
+        if end <= start {
+            return None;
+        }
+        self.data.get(self.glyf + start..self.glyf + end)
+    }
+
+    /// Contours of a glyph in font units, y up.
+    fn outline(&self, glyph: u16, depth: u32) -> Vec<Vec<OutlinePoint>> {
+        let Some(g) = self.glyph_data(glyph) else { return Vec::new() };
+        let Some(contours) = i16_at(g, 0) else { return Vec::new() };
+        if contours >= 0 {
+            simple_outline(g, contours as usize).unwrap_or_default()
+        } else if depth < 8 {
+            self.composite_outline(g, depth).unwrap_or_default()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn composite_outline(&self, g: &[u8], depth: u32) -> Option<Vec<Vec<OutlinePoint>>> {
+        const WORDS: u16 = 0x1;
+        const XY_VALUES: u16 = 0x2;
+        const SCALE: u16 = 0x8;
+        const MORE: u16 = 0x20;
+        const XY_SCALE: u16 = 0x40;
+        const TWO_BY_TWO: u16 = 0x80;
+        let f2dot14 = |at: usize| i16_at(g, at).map(|v| v as f64 / 16384.0);
+        let mut contours = Vec::new();
+        let mut at = 10;
+        loop {
+            let flags = u16_at(g, at)?;
+            let component = u16_at(g, at + 2)?;
+            at += 4;
+            let (dx, dy) = if flags & WORDS != 0 {
+                at += 4;
+                (i16_at(g, at - 4)? as f64, i16_at(g, at - 2)? as f64)
+            } else {
+                at += 2;
+                (*g.get(at - 2)? as i8 as f64, *g.get(at - 1)? as i8 as f64)
+            };
+            // Components placed by matching points are rare; put them at the origin
+            let (dx, dy) = if flags & XY_VALUES != 0 { (dx, dy) } else { (0.0, 0.0) };
+            let (mut a, mut b, mut c, mut d) = (1.0, 0.0, 0.0, 1.0);
+            if flags & SCALE != 0 {
+                a = f2dot14(at)?;
+                d = a;
+                at += 2;
+            } else if flags & XY_SCALE != 0 {
+                (a, d) = (f2dot14(at)?, f2dot14(at + 2)?);
+                at += 4;
+            } else if flags & TWO_BY_TWO != 0 {
+                (a, b, c, d) = (f2dot14(at)?, f2dot14(at + 2)?, f2dot14(at + 4)?, f2dot14(at + 6)?);
+                at += 8;
+            }
+            for contour in self.outline(component, depth + 1) {
+                contours.push(
+                    contour
+                        .into_iter()
+                        .map(|p| OutlinePoint { x: a * p.x + c * p.y + dx, y: b * p.x + d * p.y + dy, on_curve: p.on_curve })
+                        .collect(),
+                );
+            }
+            if flags & MORE == 0 {
+                return Some(contours);
+            }
+        }
+    }
+
+    /// Distance between the baselines of two lines of `size` pixel text.
+    pub fn line_height(&self, size: f64) -> f64 {
+        (self.ascent - self.descent + self.line_gap) * size / self.units_per_em
+    }
+
+    fn line_width(&self, line: &str, scale: f64) -> f64 {
+        line.chars().map(|ch| self.advance(self.glyph_index(ch)) * scale).sum()
+    }
+
+    /// Width and height of `text` at `size` pixels; lines break at `\n`.
+    pub fn measure(&self, text: &str, size: f64) -> (f64, f64) {
+        let scale = size / self.units_per_em;
+        let width = text.split('\n').map(|line| self.line_width(line, scale)).fold(0.0, f64::max);
+        let lines = text.split('\n').count() as f64;
+        let height = (lines - 1.0) * self.line_height(size) + (self.ascent - self.descent) * scale;
+        (width, height)
+    }
+
+    /// Draw `text` on a transparent image just big enough for it, each line
+    /// lined up as `align` says.
+    pub fn render(&self, text: &str, size: f64, align: Align, color: Color) -> Framebuffer {
+        let scale = size / self.units_per_em;
+        let (width, height) = self.measure(text, size);
+        let (w, h) = ((width.ceil() as usize).max(1), (height.ceil() as usize).max(1));
+        let mut raster = Raster::new(w, h);
+        for (n, line) in text.split('\n').enumerate() {
+            let mut pen = (width - self.line_width(line, scale)) * align.factor();
+            let baseline = n as f64 * self.line_height(size) + self.ascent * scale;
+            for ch in line.chars() {
+                let glyph = self.glyph_index(ch);
+                for contour in self.outline(glyph, 0) {
+                    let points: Vec<OutlinePoint> = contour
+                        .iter()
+                        .map(|p| OutlinePoint { x: pen + p.x * scale, y: baseline - p.y * scale, on_curve: p.on_curve })
+                        .collect();
+                    raster.draw_contour(&points);
+                }
+                pen += self.advance(glyph) * scale;
+            }
+        }
+        let pixels = raster
+            .coverage()
+            .flat_map(|a| [color.r, color.g, color.b, (a * color.a as f64).round() as u8])
+            .collect();
+        Framebuffer::from_rgba(w as u32, h as u32, pixels).expect("sized to fit")
+    }
+}
+
+fn simple_outline(g: &[u8], contours: usize) -> Option<Vec<Vec<OutlinePoint>>> {
+    const ON_CURVE: u8 = 0x1;
+    const X_SHORT: u8 = 0x2;
+    const Y_SHORT: u8 = 0x4;
+    const REPEAT: u8 = 0x8;
+    const X_SAME: u8 = 0x10;
+    const Y_SAME: u8 = 0x20;
+    let ends: Vec<usize> = (0..contours).map(|i| u16_at(g, 10 + 2 * i).map(|e| e as usize)).collect::<Option<_>>()?;
+    let count = ends.last().map_or(0, |e| e + 1);
+    let mut at = 10 + 2 * contours;
+    at += 2 + u16_at(g, at)? as usize;
+
+    let mut flags = Vec::with_capacity(count);
+    while flags.len() < count {
+        let flag = *g.get(at)?;
+        at += 1;
+        flags.push(flag);
+        if flag & REPEAT != 0 {
+            let times = *g.get(at)?;
+            at += 1;
+            flags.extend(std::iter::repeat_n(flag, times as usize));
+        }
+    }
+    flags.truncate(count);
+
+    let mut read_coords = |short: u8, same: u8| -> Option<Vec<f64>> {
+        let mut value = 0i32;
+        let mut coords = Vec::with_capacity(count);
+        for &flag in &flags {
+            if flag & short != 0 {
+                let delta = *g.get(at)? as i32;
+                at += 1;
+                value += if flag & same != 0 { delta } else { -delta };
+            } else if flag & same == 0 {
+                value += i16_at(g, at)? as i32;
+                at += 2;
+            }
+            coords.push(value as f64);
+        }
+        Some(coords)
+    };
+    let xs = read_coords(X_SHORT, X_SAME)?;
+    let ys = read_coords(Y_SHORT, Y_SAME)?;
+
+    let mut start = 0;
+    let mut outline = Vec::with_capacity(contours);
+    for end in ends {
+        if end < start || end >= count {
+            return None;
+        }
+        outline.push((start..=end).map(|i| OutlinePoint { x: xs[i], y: ys[i], on_curve: flags[i] & ON_CURVE != 0 }).collect());
+        start = end + 1;
+    }
+    Some(outline)
+}
+
+/// Coverage accumulation rasterizer: each edge adds its signed area to the
+/// cells it crosses, and a running sum along each row gives the coverage.
+struct Raster {
+    w: usize,
+    h: usize,
+    acc: Vec<f64>,
+}
+
+impl Raster {
+    fn new(w: usize, h: usize) -> Self {
+        Self { w, h, acc: vec![0.0; w * h + 4] }
+    }
+
+    /// A closed contour of on- and off-curve points in pixels.
+    fn draw_contour(&mut self, points: &[OutlinePoint]) {
+        let n = points.len();
+        if n < 2 {
+            return;
+        }
+        // Start on an on-curve point, or between two off-curve ones
+        let first_on = points.iter().position(|p| p.on_curve);
+        let start = match first_on {
+            Some(i) => (points[i].x, points[i].y),
+            None => ((points[0].x + points[1].x) / 2.0, (points[0].y + points[1].y) / 2.0),
+        };
+        let offset = first_on.unwrap_or(0);
+        let mut current = start;
+        let mut control: Option<(f64, f64)> = None;
+        for k in 1..=n {
+            let p = points[(offset + k) % n];
+            let here = (p.x, p.y);
+            match (control, p.on_curve) {
+                (None, true) => {
+                    self.line(current, here);
+                    current = here;
+                }
+                (None, false) => control = Some(here),
+                (Some(c), true) => {
+                    self.quad(current, c, here);
+                    current = here;
+                    control = None;
+                }
+                (Some(c), false) => {
+                    let mid = ((c.0 + here.0) / 2.0, (c.1 + here.1) / 2.0);
+                    self.quad(current, c, mid);
+                    current = mid;
+                    control = Some(here);
+                }
+            }
+        }
+        match control {
+            Some(c) => self.quad(current, c, start),
+            None => self.line(current, start),
+        }
+    }
+
+    fn quad(&mut self, p0: (f64, f64), p1: (f64, f64), p2: (f64, f64)) {
+        let dev = ((p0.0 - 2.0 * p1.0 + p2.0).powi(2) + (p0.1 - 2.0 * p1.1 + p2.1).powi(2)).sqrt();
+        let steps = (1.0 + (dev * 2.0).sqrt()).floor().clamp(1.0, 16.0) as usize;
+        let mut prev = p0;
+        for i in 1..=steps {
+            let t = i as f64 / steps as f64;
+            let u = 1.0 - t;
+            let next = (u * u * p0.0 + 2.0 * u * t * p1.0 + t * t * p2.0, u * u * p0.1 + 2.0 * u * t * p1.1 + t * t * p2.1);
+            self.line(prev, next);
+            prev = next;
+        }
+    }
+
+    fn line(&mut self, p0: (f64, f64), p1: (f64, f64)) {
+        if (p0.1 - p1.1).abs() <= f64::EPSILON {
+            return;
+        }
+        let (dir, p0, p1) = if p0.1 < p1.1 { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+        let dxdy = (p1.0 - p0.0) / (p1.1 - p0.1);
+        let mut x = p0.0;
+        if p0.1 < 0.0 {
+            x -= p0.1 * dxdy;
+        }
+        let w = self.w as isize;
+        for y in (p0.1.max(0.0) as usize)..self.h.min(p1.1.ceil().max(0.0) as usize) {
+            let row = (y * self.w) as isize;
+            let dy = ((y + 1) as f64).min(p1.1) - (y as f64).max(p0.1);
+            let x_next = x + dxdy * dy;
+            let d = dy * dir;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            // Cells left of the image carry their area into column 0
+            let cell = |xi: isize| (row + xi.clamp(0, w)) as usize;
+            let x0_floor = x0.floor();
+            let x0i = x0_floor as isize;
+            let x1_ceil = x1.ceil();
+            let x1i = x1_ceil as isize;
+            if x1i <= x0i + 1 {
+                let xmf = 0.5 * (x + x_next) - x0_floor;
+                self.acc[cell(x0i)] += d - d * xmf;
+                self.acc[cell(x0i + 1)] += d * xmf;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0f = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+                let x1f = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1f * x1f;
+                self.acc[cell(x0i)] += d * a0;
+                if x1i == x0i + 2 {
+                    self.acc[cell(x0i + 1)] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0f);
+                    self.acc[cell(x0i + 1)] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.acc[cell(xi)] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f64 * s;
+                    self.acc[cell(x1i - 1)] += d * (1.0 - a2 - am);
+                }
+                self.acc[cell(x1i)] += d * am;
+            }
+            x = x_next;
+        }
+    }
+
+    /// Coverage of each pixel, row by row, from 0 to 1.
+    fn coverage(&self) -> impl Iterator<Item = f64> + '_ {
+        let mut sum = 0.0;
+        self.acc[..self.w * self.h].iter().map(move |a| {
+            sum += a;
+            sum.abs().min(1.0)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_font_metrics() {
+        let font = Font::default_font();
+        assert_ne!(font.glyph_index('A'), 0);
+        assert_ne!(font.glyph_index('é'), 0);
+        assert_eq!(font.glyph_index('\u{4E00}'), 0);
+        let (w1, h1) = font.measure("Hi", 20.0);
+        let (w2, h2) = font.measure("Hi\nHello", 20.0);
+        assert!(w1 > 10.0 && w1 < 40.0, "{w1}");
+        assert!(w2 > w1 && h2 > h1 * 1.8);
+        assert_eq!(font.measure("", 20.0).0, 0.0);
+        assert!(Font::parse(vec![0; 64]).is_err());
+    }
+
+    #[test]
+    fn test_broken_tables_are_skipped() {
+        let font = Font::default_font();
+        // A composite glyph cut short in its component's offsets
+        assert!(font.composite_outline(&[0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x20, 0, 1, 5], 0).is_none());
+        // A character map claiming four billion groups, one of them off the end of the glyph range
+        let mut cmap = vec![0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+        cmap.extend([0, 0, 0, 0x41, 0, 0, 0, 0x42, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let broken = Font { data: cmap, cmap: Cmap::Format12(0), ..font };
+        assert_eq!((broken.glyph_index('A'), broken.glyph_index('B'), broken.glyph_index('C')), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_render_fills_glyphs() {
+        let font = Font::default_font();
+        let white = Color::rgb(255, 255, 255);
+        // The bar of a capital I is solid ink
+        let fb = font.render("I", 40.0, Align::Left, white);
+        let (w, h) = (fb.width() as i64, fb.height() as i64);
+        let ink: Vec<u8> = (0..w).map(|x| fb.pixel(x, h / 2).unwrap()[3]).collect();
+        assert!(ink.contains(&255), "{ink:?}");
+        assert_eq!(fb.pixel(0, 0).unwrap()[3], 0);
+        // Composite glyphs (an accented letter) draw too
+        let fb = font.render("é", 40.0, Align::Left, white);
+        let total: u32 = fb.pixels().chunks_exact(4).map(|p| p[3] as u32).sum();
+        assert!(total > 255 * 20);
+    }
+
+    #[test]
+    fn test_align_lines() {
+        let font = Font::default_font();
+        let white = Color::rgb(255, 255, 255);
+        let first_ink = |fb: &Framebuffer, y: i64| (0..fb.width() as i64).find(|&x| fb.pixel(x, y).unwrap()[3] > 128);
+        let left = font.render("I\nIIIIII", 30.0, Align::Left, white);
+        let right = font.render("I\nIIIIII", 30.0, Align::Right, white);
+        let y = (font.line_height(30.0) / 2.0) as i64 + 3;
+        assert!(first_ink(&left, y).unwrap() < 10);
+        assert!(first_ink(&right, y).unwrap() > left.width() as i64 / 2);
+        assert_eq!(Align::parse("Center"), Some(Align::Center));
+    }
+}
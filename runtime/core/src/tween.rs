@@ -0,0 +1,230 @@
+//! Tweens: a property gliding to a new value over time along an easing
+//! curve, from `obj.tween("position", Point(400, 100), 0.5)`.
+
+use std::f64::consts::PI;
+
+/// The shape of an easing curve, eased in; out and in-out mirror it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    Quad,
+    Cubic,
+    Quart,
+    Quint,
+    Sine,
+    Expo,
+    Circ,
+    /// Pulls back a little before setting off.
+    Back,
+    /// Winds up like a spring.
+    Elastic,
+    /// Bounces off the start before leaving it.
+    Bounce,
+}
+
+/// Which end of the tween the curve's character shows at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    In,
+    Out,
+    InOut,
+}
+
+/// How a tween's progress is spread over its time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ease {
+    Linear,
+    Curve(Curve, Mode),
+}
+
+impl Ease {
+    /// `"linear"`, or a curve with its mode, like `"in_quad"`, `"out_bounce"`
+    /// or `"in_out_sine"`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name == "linear" {
+            return Some(Self::Linear);
+        }
+        let (mode, curve) = if let Some(rest) = name.strip_prefix("in_out_") {
+            (Mode::InOut, rest)
+        } else if let Some(rest) = name.strip_prefix("in_") {
+            (Mode::In, rest)
+        } else {
+            (Mode::Out, name.strip_prefix("out_")?)
+        };
+        let curve = match curve {
+            "quad" => Curve::Quad,
+            "cubic" => Curve::Cubic,
+            "quart" => Curve::Quart,
+            "quint" => Curve::Quint,
+            "sine" => Curve::Sine,
+            "expo" => Curve::Expo,
+            "circ" => Curve::Circ,
+            "back" => Curve::Back,
+            "elastic" => Curve::Elastic,
+            "bounce" => Curve::Bounce,
+            _ => return None,
+        };
+        Some(Self::Curve(curve, mode))
+    }
+
+    /// Progress at time `t` (0..=1): 0 at the start and 1 at the end, and
+    /// in between, for back and elastic curves, a little past either.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::Curve(curve, Mode::In) => ease_in(curve, t),
+            Self::Curve(curve, Mode::Out) => 1.0 - ease_in(curve, 1.0 - t),
+            Self::Curve(curve, Mode::InOut) if t < 0.5 => ease_in(curve, 2.0 * t) / 2.0,
+            Self::Curve(curve, Mode::InOut) => 1.0 - ease_in(curve, 2.0 - 2.0 * t) / 2.0,
+        }
+    }
+}
+
+fn ease_in(curve: Curve, t: f64) -> f64 {
+    match curve {
+        Curve::Quad => t * t,
+        Curve::Cubic => t.powi(3),
+        Curve::Quart => t.powi(4),
+        Curve::Quint => t.powi(5),
+        Curve::Sine => 1.0 - (t * PI / 2.0).cos(),
+        Curve::Expo if t == 0.0 => 0.0,
+        Curve::Expo => 2f64.powf(10.0 * t - 10.0),
+        Curve::Circ => 1.0 - (1.0 - t * t).sqrt(),
+        Curve::Back => {
+            const C1: f64 = 1.70158;
+            (C1 + 1.0) * t.powi(3) - C1 * t * t
+        }
+        Curve::Elastic if t == 0.0 || t == 1.0 => t,
+        Curve::Elastic => -(2f64.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * 2.0 * PI / 3.0).sin(),
+        Curve::Bounce => 1.0 - bounce_out(1.0 - t),
+    }
+}
+
+/// A ball dropped on the end value, bouncing lower each time.
+fn bounce_out(t: f64) -> f64 {
+    const N: f64 = 7.5625;
+    const D: f64 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Where a tween is after an update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress {
+    /// Still in its delay.
+    Waiting,
+    /// Eased share of the way from the start value to the end value.
+    Running(f64),
+    /// Over, at this share of the way; it won't move again.
+    Done(f64),
+}
+
+/// The timing of one tween: how long, along what curve, after what delay,
+/// and whether it repeats or comes back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tween {
+    /// Seconds from start to end value.
+    pub duration: f64,
+    pub ease: Ease,
+    /// Seconds to wait before starting.
+    pub delay: f64,
+    /// Start again each time it ends, forever.
+    pub looping: bool,
+    /// Go back to the start value after reaching the end one, taking as long
+    /// again.
+    pub yoyo: bool,
+    elapsed: f64,
+}
+
+impl Tween {
+    pub fn new(duration: f64, ease: Ease) -> Self {
+        Self { duration: duration.max(0.0), ease, delay: 0.0, looping: false, yoyo: false, elapsed: 0.0 }
+    }
+
+    /// Move `dt` seconds on.
+    pub fn advance(&mut self, dt: f64) -> Progress {
+        self.elapsed += dt;
+        let t = self.elapsed - self.delay;
+        if t < 0.0 {
+            return Progress::Waiting;
+        }
+        let trip = if self.yoyo { 2.0 } else { 1.0 };
+        let cycle = self.duration * trip;
+        if cycle <= 0.0 || (!self.looping && t >= cycle) {
+            let end = if self.yoyo { 0.0 } else { 1.0 };
+            return Progress::Done(end);
+        }
+        // Share of the way through the cycle, back down on a yoyo's return
+        let phase = t.rem_euclid(cycle) / self.duration;
+        let share = if phase <= 1.0 { phase } else { 2.0 - phase };
+        Progress::Running(self.ease.apply(share))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_every_ease_runs_from_zero_to_one() {
+        let curves = ["quad", "cubic", "quart", "quint", "sine", "expo", "circ", "back", "elastic", "bounce"];
+        for curve in curves {
+            for mode in ["in_", "out_", "in_out_"] {
+                let ease = Ease::parse(&format!("{mode}{curve}")).unwrap();
+                assert!(close(ease.apply(0.0), 0.0) && close(ease.apply(1.0), 1.0), "{mode}{curve}");
+            }
+        }
+        assert_eq!(Ease::parse("Linear"), Some(Ease::Linear));
+        assert_eq!(Ease::parse("out_wobble"), None);
+        assert_eq!(Ease::parse("bounce"), None);
+    }
+
+    #[test]
+    fn test_curve_shapes() {
+        let ease = |name: &str, t: f64| Ease::parse(name).unwrap().apply(t);
+        assert_eq!(ease("in_quad", 0.5), 0.25);
+        assert_eq!(ease("out_quad", 0.5), 0.75);
+        assert_eq!(ease("in_out_cubic", 0.25), 0.0625);
+        assert!(close(ease("in_out_sine", 0.5), 0.5));
+        // Back dips below the start; elastic and bounce overshoot or touch
+        // down on the way
+        assert!(ease("in_back", 0.3) < 0.0);
+        assert!(ease("out_elastic", 0.2) > 1.0);
+        assert!(close(ease("out_bounce", 1.0 / 2.75), 1.0));
+    }
+
+    #[test]
+    fn test_delay_yoyo_and_loop() {
+        let mut tween = Tween::new(1.0, Ease::Linear);
+        tween.delay = 0.5;
+        assert_eq!(tween.advance(0.25), Progress::Waiting);
+        assert_eq!(tween.advance(0.5), Progress::Running(0.25));
+        assert_eq!(tween.advance(1.0), Progress::Done(1.0));
+
+        let mut tween = Tween { yoyo: true, ..Tween::new(1.0, Ease::Linear) };
+        assert_eq!(tween.advance(0.5), Progress::Running(0.5));
+        assert_eq!(tween.advance(1.0), Progress::Running(0.5));
+        assert_eq!(tween.advance(0.5), Progress::Done(0.0));
+
+        let mut tween = Tween { looping: true, ..Tween::new(1.0, Ease::Linear) };
+        assert_eq!(tween.advance(0.75), Progress::Running(0.75));
+        assert_eq!(tween.advance(0.5), Progress::Running(0.25));
+
+        assert_eq!(Tween::new(0.0, Ease::Linear).advance(0.0), Progress::Done(1.0));
+    }
+}
@@ -0,0 +1,14 @@
+//! G-Basic headless runtime — no window, no audio, no sleeping.
+//!
+//! Link a program against this instead of the desktop runtime
+//! (`gbasic --target headless`) to run it anywhere, e.g. in CI. The screen is
+//! an in-memory framebuffer that `System.Screenshot` and
+//! `GBASIC_SCREENSHOT_EVERY` save as PNG; `GBASIC_FRAMES` ends the run.
+
+use gbasic_runtime_core::Platform;
+use gbasic_runtime_core::headless::HeadlessPlatform;
+
+#[unsafe(no_mangle)]
+fn gbasic_create_platform() -> Box<dyn Platform> {
+    Box::new(HeadlessPlatform::new())
+}
@@ -1,6 +1,21 @@
-//! G-Basic web runtime — wasm-bindgen stubs.
+//! G-Basic web runtime — Canvas 2D backend for the runtime core.
+//!
+//! Compiled programs link against the same `extern "C"` symbols as on desktop.
+//! A program's game loop never returns to the browser on its own, so the frame
+//! and sleep functions call into the JS host (`www/gbasic.js`), which suspends
+//! `main` with Asyncify and resumes it from `requestAnimationFrame`.
 
+use gbasic_runtime_core::{
+    AudioSink, BlendMode, Color, Framebuffer, InputSource, InputState, LayerDraw, Platform, Renderer, SpriteDraw,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::{Clamped, JsCast};
 use wasm_bindgen::prelude::*;
+use web_sys::{
+    AudioContext, CanvasRenderingContext2d, HtmlAudioElement, HtmlCanvasElement, HtmlImageElement,
+    ImageData, KeyboardEvent, MouseEvent,
+};
 
 #[wasm_bindgen]
 extern "C" {
@@ -8,27 +23,516 @@ extern "C" {
     fn log(s: &str);
 }
 
-#[wasm_bindgen]
-pub fn runtime_init(_width: i32, _height: i32) {
-    log("G-Basic web runtime initialized");
+// ─── JS host ───
+
+// Raw imports (not wasm-bindgen) so `wasm-opt --asyncify` can be told exactly
+// which calls may suspend the program.
+#[cfg(target_arch = "wasm32")]
+mod host {
+    #[link(wasm_import_module = "gbasic_host")]
+    unsafe extern "C" {
+        fn frame_yield();
+        fn sleep(ms: f64);
+        fn exit(code: i32);
+    }
+
+    pub fn yield_frame() {
+        unsafe { frame_yield() }
+    }
+
+    pub fn sleep_ms(ms: f64) {
+        unsafe { sleep(ms) }
+    }
+
+    pub fn exit_program(code: i32) {
+        unsafe { exit(code) }
+    }
 }
 
-#[wasm_bindgen]
-pub fn runtime_clear_screen(_r: u8, _g: u8, _b: u8) {
-    // Will be implemented via JS glue calling canvas context
+// Native builds (workspace `cargo build`/`cargo test`) have no JS host.
+#[cfg(not(target_arch = "wasm32"))]
+mod host {
+    pub fn yield_frame() {}
+
+    pub fn sleep_ms(ms: f64) {
+        std::thread::sleep(std::time::Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+    }
+
+    pub fn exit_program(code: i32) {
+        std::process::exit(code);
+    }
 }
 
-#[wasm_bindgen]
-pub fn runtime_present() {
-    // No-op for web — requestAnimationFrame handles presentation
+/// Allocate the scratch area the JS host hands to Asyncify when it suspends
+/// `main`. The first two words hold the start and end of the unwind stack.
+#[cfg(target_arch = "wasm32")]
+#[unsafe(no_mangle)]
+pub extern "C" fn runtime_web_asyncify_data(size: i32) -> i32 {
+    let size = size.max(64) as usize;
+    // Lives for the whole program
+    let buf: &'static mut [u32] = vec![0u32; size / 4].leak();
+    let base = buf.as_ptr() as u32;
+    buf[0] = base + 8;
+    buf[1] = base + size as u32;
+    base as i32
 }
 
-#[wasm_bindgen]
-pub fn runtime_should_quit() -> i32 {
-    0 // Web apps don't quit via this mechanism
+// ─── Input listeners ───
+
+/// Events the DOM delivered while the program was suspended.
+#[derive(Default)]
+struct PendingInput {
+    keys: Vec<(String, bool)>,
+    mouse: Option<(i64, i64)>,
 }
 
-#[wasm_bindgen]
-pub fn runtime_shutdown() {
-    log("G-Basic web runtime shutdown");
+thread_local! {
+    static PENDING_INPUT: RefCell<PendingInput> = RefCell::new(PendingInput::default());
+}
+
+/// Translate a DOM key event into the lowercase SDL key names `key()` expects.
+fn key_name(event: &KeyboardEvent) -> String {
+    let name = match event.code().as_str() {
+        "ShiftLeft" => "left shift",
+        "ShiftRight" => "right shift",
+        "ControlLeft" => "left ctrl",
+        "ControlRight" => "right ctrl",
+        "AltLeft" => "left alt",
+        "AltRight" => "right alt",
+        _ => match event.key().as_str() {
+            "ArrowLeft" => "left",
+            "ArrowRight" => "right",
+            "ArrowUp" => "up",
+            "ArrowDown" => "down",
+            " " => "space",
+            "Enter" => "return",
+            "Escape" => "escape",
+            "Backspace" => "backspace",
+            "Tab" => "tab",
+            "Delete" => "delete",
+            other => return other.to_lowercase(),
+        },
+    };
+    name.to_string()
+}
+
+/// Keyboard listeners go on the window so the canvas doesn't need focus;
+/// the mouse listener is relative to the canvas.
+fn install_input_listeners(canvas: &HtmlCanvasElement) {
+    let Some(window) = web_sys::window() else { return };
+
+    for (event_name, down) in [("keydown", true), ("keyup", false)] {
+        let on_key = Closure::<dyn FnMut(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+            let name = key_name(&e);
+            // Keep arrows and space from scrolling the page
+            if matches!(name.as_str(), "left" | "right" | "up" | "down" | "space") {
+                e.prevent_default();
+            }
+            PENDING_INPUT.with(|p| p.borrow_mut().keys.push((name, down)));
+        });
+        let _ = window.add_event_listener_with_callback(event_name, on_key.as_ref().unchecked_ref());
+        // Listeners live as long as the page
+        on_key.forget();
+    }
+
+    let on_mouse = Closure::<dyn FnMut(MouseEvent)>::new(|e: MouseEvent| {
+        PENDING_INPUT.with(|p| p.borrow_mut().mouse = Some((e.offset_x() as i64, e.offset_y() as i64)));
+    });
+    let _ = canvas.add_event_listener_with_callback("mousemove", on_mouse.as_ref().unchecked_ref());
+    on_mouse.forget();
+}
+
+// ─── Platform ───
+
+enum WebImage {
+    /// Loaded from a file.
+    File(HtmlImageElement),
+    /// Drawn by the program, such as rendered text.
+    Pixels(HtmlCanvasElement),
+    /// Pixels that couldn't be put on a canvas; never drawn.
+    Missing,
+}
+
+impl WebImage {
+    fn from_pixels(pixels: &Framebuffer) -> Option<Self> {
+        let document = web_sys::window()?.document()?;
+        let canvas: HtmlCanvasElement = document.create_element("canvas").ok()?.dyn_into().ok()?;
+        canvas.set_width(pixels.width());
+        canvas.set_height(pixels.height());
+        let ctx = context_2d(&canvas)?;
+        let data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(pixels.pixels()), pixels.width(), pixels.height()).ok()?;
+        ctx.put_image_data(&data, 0.0, 0.0).ok()?;
+        Some(Self::Pixels(canvas))
+    }
+
+    /// Size once the image is ready to draw.
+    fn size(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::File(img) => (img.complete() && img.natural_width() > 0).then(|| (img.natural_width(), img.natural_height())),
+            Self::Pixels(canvas) => Some((canvas.width(), canvas.height())),
+            Self::Missing => None,
+        }
+    }
+}
+
+struct WebPlatform {
+    /// Context drawing goes to: the page's canvas, or a layer's.
+    ctx: Option<CanvasRenderingContext2d>,
+    screen: Option<CanvasRenderingContext2d>,
+    /// Offscreen canvases for layers.
+    surfaces: Vec<HtmlCanvasElement>,
+    /// Last fill style set on the context; setting it is comparatively slow.
+    fill: Option<Color>,
+    blend: BlendMode,
+    images: Vec<WebImage>,
+    audio: Option<AudioContext>,
+    effects: HashMap<String, HtmlAudioElement>,
+    /// console.log always ends a line, so partial prints wait here.
+    line: String,
+}
+
+#[unsafe(no_mangle)]
+fn gbasic_create_platform() -> Box<dyn Platform> {
+    Box::new(WebPlatform {
+        ctx: None,
+        screen: None,
+        surfaces: Vec::new(),
+        fill: None,
+        blend: BlendMode::Normal,
+        images: Vec::new(),
+        audio: None,
+        effects: HashMap::new(),
+        line: String::new(),
+    })
+}
+
+fn css(c: Color) -> String {
+    format!("rgba({}, {}, {}, {})", c.r, c.g, c.b, c.a as f64 / 255.0)
+}
+
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas.get_context("2d").ok()??.dyn_into().ok()
+}
+
+/// Find the page's `<canvas id="gbasic-canvas">`, creating one if missing.
+fn find_or_create_canvas() -> Option<HtmlCanvasElement> {
+    let document = web_sys::window()?.document()?;
+    if let Some(el) = document.get_element_by_id("gbasic-canvas") {
+        return el.dyn_into().ok();
+    }
+    let canvas: HtmlCanvasElement = document.create_element("canvas").ok()?.dyn_into().ok()?;
+    canvas.set_id("gbasic-canvas");
+    document.body()?.append_child(&canvas).ok()?;
+    Some(canvas)
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// There is no filesystem in the browser; files live in localStorage.
+fn storage_key(path: &str) -> String {
+    format!("gbasic:file:{path}")
+}
+
+impl WebPlatform {
+    fn with_fill(&mut self, color: Color, f: impl FnOnce(&CanvasRenderingContext2d)) {
+        let Some(ctx) = &self.ctx else { return };
+        if self.fill != Some(color) {
+            ctx.set_fill_style_str(&css(color));
+            self.fill = Some(color);
+        }
+        f(ctx);
+    }
+
+    /// Browsers only allow audio once the page has seen a user gesture; until
+    /// then the context stays suspended and sounds are silently dropped.
+    fn audio_context(&mut self) -> Option<&AudioContext> {
+        if self.audio.is_none() {
+            self.audio = AudioContext::new().ok();
+        }
+        let ctx = self.audio.as_ref()?;
+        let _ = ctx.resume();
+        Some(ctx)
+    }
+
+    fn effect(&mut self, path: &str) -> Option<&HtmlAudioElement> {
+        if !self.effects.contains_key(path) {
+            let el = HtmlAudioElement::new_with_src(path).ok()?;
+            self.effects.insert(path.to_string(), el);
+        }
+        self.effects.get(path)
+    }
+}
+
+impl Renderer for WebPlatform {
+    fn open_screen(&mut self, width: i64, height: i64) {
+        let canvas = find_or_create_canvas().expect("Failed to find or create canvas");
+        canvas.set_width(width as u32);
+        canvas.set_height(height as u32);
+        let ctx = context_2d(&canvas).expect("Failed to get 2D context");
+        ctx.set_image_smoothing_enabled(false);
+        install_input_listeners(&canvas);
+        self.screen = Some(ctx.clone());
+        self.ctx = Some(ctx);
+        self.fill = None;
+    }
+
+    fn clear(&mut self, color: Color) {
+        if let Some(canvas) = self.ctx.as_ref().and_then(|ctx| ctx.canvas()) {
+            let (w, h) = (canvas.width() as f64, canvas.height() as f64);
+            let blend = self.blend;
+            self.set_blend(BlendMode::Normal);
+            self.with_fill(color, |ctx| {
+                ctx.clear_rect(0.0, 0.0, w, h);
+                if color.a > 0 {
+                    ctx.fill_rect(0.0, 0.0, w, h);
+                }
+            });
+            self.set_blend(blend);
+        }
+    }
+
+    fn fill_rect(&mut self, x: i64, y: i64, w: i64, h: i64, color: Color) {
+        // Canvas has no replace mode for fills; an opaque color does the same
+        let color = if self.blend == BlendMode::Replace { Color { a: 255, ..color } } else { color };
+        self.with_fill(color, |ctx| ctx.fill_rect(x as f64, y as f64, w as f64, h as f64));
+    }
+
+    fn set_blend(&mut self, mode: BlendMode) {
+        self.blend = mode;
+        if let Some(ctx) = &self.ctx {
+            let op = match mode {
+                BlendMode::Normal | BlendMode::Replace => "source-over",
+                BlendMode::Add => "lighter",
+                BlendMode::Multiply => "multiply",
+            };
+            let _ = ctx.set_global_composite_operation(op);
+        }
+    }
+
+    fn load_image(&mut self, path: &str) -> Option<usize> {
+        // Images decode asynchronously; draws before they finish are skipped
+        let image = HtmlImageElement::new().ok()?;
+        image.set_src(path);
+        self.images.push(WebImage::File(image));
+        Some(self.images.len() - 1)
+    }
+
+    fn upload_image(&mut self, image: Option<usize>, pixels: Framebuffer) -> usize {
+        let uploaded = WebImage::from_pixels(&pixels).unwrap_or(WebImage::Missing);
+        match image {
+            Some(i) if i < self.images.len() => {
+                self.images[i] = uploaded;
+                i
+            }
+            _ => {
+                self.images.push(uploaded);
+                self.images.len() - 1
+            }
+        }
+    }
+
+    fn image_size(&self, image: usize) -> Option<(u32, u32)> {
+        self.images.get(image)?.size()
+    }
+
+    fn draw_sprite(&mut self, image: usize, sprite: &SpriteDraw) {
+        let Some((image_w, image_h)) = self.image_size(image) else { return };
+        let (Some(ctx), Some(img)) = (&self.ctx, self.images.get(image)) else {
+            return;
+        };
+        ctx.save();
+        let _ = ctx.translate(sprite.x, sprite.y);
+        let _ = ctx.rotate(sprite.rotation.to_radians());
+        let _ = ctx.scale(if sprite.flip_x { -1.0 } else { 1.0 }, if sprite.flip_y { -1.0 } else { 1.0 });
+        ctx.set_global_alpha(sprite.opacity.clamp(0.0, 1.0));
+        let (w, h) = (sprite.w, sprite.h);
+        let [sx, sy, sw, sh] = sprite.source.unwrap_or([0, 0, image_w, image_h]).map(|v| v as f64);
+        let _ = match img {
+            WebImage::File(img) => ctx
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    img, sx, sy, sw, sh, -w / 2.0, -h / 2.0, w, h,
+                ),
+            WebImage::Pixels(canvas) => ctx
+                .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    canvas, sx, sy, sw, sh, -w / 2.0, -h / 2.0, w, h,
+                ),
+            WebImage::Missing => Ok(()),
+        };
+        ctx.restore();
+    }
+
+    fn create_surface(&mut self) -> usize {
+        let size = self.screen.as_ref().and_then(|ctx| ctx.canvas()).map(|c| (c.width(), c.height()));
+        let document = web_sys::window().and_then(|w| w.document());
+        let canvas = document.and_then(|d| d.create_element("canvas").ok()).and_then(|el| el.dyn_into::<HtmlCanvasElement>().ok());
+        let canvas = canvas.expect("Failed to create layer canvas");
+        let (w, h) = size.unwrap_or((0, 0));
+        canvas.set_width(w);
+        canvas.set_height(h);
+        if let Some(ctx) = context_2d(&canvas) {
+            ctx.set_image_smoothing_enabled(false);
+        }
+        self.surfaces.push(canvas);
+        self.surfaces.len() - 1
+    }
+
+    fn set_target(&mut self, surface: Option<usize>) {
+        self.ctx = match surface {
+            Some(i) => self.surfaces.get(i).and_then(context_2d),
+            None => self.screen.clone(),
+        };
+        // Fill style and blend mode belong to each context
+        self.fill = None;
+        self.set_blend(self.blend);
+    }
+
+    fn draw_surface(&mut self, surface: usize, layer: &LayerDraw) {
+        let (Some(ctx), Some(canvas)) = (&self.ctx, self.surfaces.get(surface)) else { return };
+        ctx.save();
+        let _ = ctx.set_global_composite_operation("source-over");
+        ctx.set_global_alpha(layer.opacity.clamp(0.0, 1.0));
+        if layer.has_effects() {
+            ctx.set_filter(&format!(
+                "blur({}px) grayscale({}) brightness({})",
+                layer.blur, layer.grayscale, layer.brightness
+            ));
+        }
+        let _ = ctx.draw_image_with_html_canvas_element(canvas, layer.x.round(), layer.y.round());
+        ctx.restore();
+    }
+
+    fn present(&mut self) {
+        // Canvas draws show up once the browser gets control back at the end
+        // of the frame.
+    }
+}
+
+impl InputSource for WebPlatform {
+    fn poll(&mut self, input: &mut InputState) {
+        let pending = PENDING_INPUT.with(|p| std::mem::take(&mut *p.borrow_mut()));
+        for (name, down) in pending.keys {
+            input.keys.insert(name, down);
+        }
+        if let Some((x, y)) = pending.mouse {
+            input.mouse_x = x;
+            input.mouse_y = y;
+        }
+    }
+}
+
+impl AudioSink for WebPlatform {
+    fn beep(&mut self, freq: i64, dur: i64) {
+        let Some(ctx) = self.audio_context() else { return };
+        let Ok(osc) = ctx.create_oscillator() else { return };
+        osc.frequency().set_value(freq as f32);
+        let _ = osc.connect_with_audio_node(&ctx.destination());
+        let start = ctx.current_time();
+        let _ = osc.start();
+        let _ = osc.stop_with_when(start + dur as f64 / 1000.0);
+    }
+
+    fn load_effect(&mut self, path: &str) -> bool {
+        self.effect(path).is_some()
+    }
+
+    fn play_effect(&mut self, path: &str) {
+        if let Some(el) = self.effect(path) {
+            // Restart if the effect is still playing from last time
+            el.set_current_time(0.0);
+            let _ = el.play();
+        }
+    }
+
+    fn set_effect_volume(&mut self, path: &str, volume: f64) {
+        if let Some(el) = self.effect(path) {
+            el.set_volume(volume.clamp(0.0, 1.0));
+        }
+    }
+}
+
+impl Platform for WebPlatform {
+    fn now(&self) -> f64 {
+        web_sys::window()
+            .and_then(|w| w.performance())
+            .map(|p| p.now() / 1000.0)
+            .unwrap_or(0.0)
+    }
+
+    fn wait_until(&mut self, deadline: f64) {
+        // The browser shows frames on requestAnimationFrame, so yield at least
+        // once, then keep yielding until the deadline is reached; a little
+        // slack keeps a frame that lands just short from costing a whole
+        // extra display refresh.
+        const SLACK: f64 = 0.002;
+        host::yield_frame();
+        while self.now() < deadline - SLACK {
+            host::yield_frame();
+        }
+    }
+
+    fn wall_time(&self) -> f64 {
+        web_sys::window()
+            .and_then(|w| w.performance())
+            .map(|p| (p.time_origin() + p.now()) / 1000.0)
+            .unwrap_or(0.0)
+    }
+
+    fn sleep_ms(&mut self, ms: i64) {
+        host::sleep_ms(ms as f64);
+    }
+
+    fn exit(&mut self, code: i32) -> ! {
+        if !self.line.is_empty() {
+            log(&std::mem::take(&mut self.line));
+        }
+        host::exit_program(code);
+        unreachable!("the host does not return from exit");
+    }
+
+    fn write_stdout(&mut self, text: &str) {
+        self.line.push_str(text);
+        while let Some(pos) = self.line.find('\n') {
+            let rest = self.line.split_off(pos + 1);
+            self.line.pop();
+            log(&std::mem::replace(&mut self.line, rest));
+        }
+    }
+
+    fn read_file(&mut self, path: &str) -> Option<String> {
+        local_storage()?.get_item(&storage_key(path)).ok().flatten()
+    }
+
+    /// Fetched from the page's server, blocking; maps are small and
+    /// loaded once.
+    fn load_text(&mut self, path: &str) -> Option<String> {
+        let request = web_sys::XmlHttpRequest::new().ok()?;
+        request.open_with_async("GET", path, false).ok()?;
+        request.send().ok()?;
+        if request.status().ok()? != 200 {
+            return None;
+        }
+        request.response_text().ok().flatten()
+    }
+
+    /// Like `load_text`, with the browser told to leave the bytes alone:
+    /// each byte comes back as one character in U+F700..U+F7FF.
+    fn load_data(&mut self, path: &str) -> Option<Vec<u8>> {
+        let request = web_sys::XmlHttpRequest::new().ok()?;
+        request.open_with_async("GET", path, false).ok()?;
+        request.override_mime_type("text/plain; charset=x-user-defined").ok()?;
+        request.send().ok()?;
+        if request.status().ok()? != 200 {
+            return None;
+        }
+        let text = request.response_text().ok().flatten()?;
+        Some(text.chars().map(|c| c as u32 as u8).collect())
+    }
+
+    fn write_file(&mut self, path: &str, data: &str) {
+        if let Some(storage) = local_storage() {
+            let _ = storage.set_item(&storage_key(path), data);
+        }
+    }
 }
@@ -0,0 +1,76 @@
+// G-Basic web host.
+//
+// Compiled programs run their game loop inside `main`, which never returns to
+// the browser on its own. The runtime calls `frame_yield`/`sleep` from this
+// module at the end of every frame; we unwind the wasm stack with Asyncify,
+// wait for requestAnimationFrame (or a timer), then rewind back into `main`
+// exactly where it left off.
+//
+// This file doubles as the `gbasic_host` import module (see index.html).
+
+import init from "./game.js";
+
+const ASYNCIFY_DATA_SIZE = 64 * 1024;
+
+let wasm = null;
+let asyncifyData = 0;
+let state = "normal"; // "normal" | "unwinding" | "rewinding"
+let resumeAfter = null;
+
+class ProgramExit {
+  constructor(code) {
+    this.code = code;
+  }
+}
+
+function suspend(schedule) {
+  if (state === "rewinding") {
+    // Back from the pause: finish rewinding and carry on in wasm.
+    wasm.asyncify_stop_rewind();
+    state = "normal";
+    return;
+  }
+  resumeAfter = schedule;
+  state = "unwinding";
+  wasm.asyncify_start_unwind(asyncifyData);
+}
+
+export function frame_yield() {
+  suspend((resume) => requestAnimationFrame(resume));
+}
+
+export function sleep(ms) {
+  suspend((resume) => setTimeout(resume, ms));
+}
+
+export function exit(code) {
+  throw new ProgramExit(code);
+}
+
+function step() {
+  try {
+    wasm.main();
+  } catch (e) {
+    if (e instanceof ProgramExit) {
+      state = "normal";
+      return;
+    }
+    throw e;
+  }
+  if (state !== "unwinding") {
+    return; // `main` returned: the program is done.
+  }
+  wasm.asyncify_stop_unwind();
+  state = "normal";
+  resumeAfter(() => {
+    state = "rewinding";
+    wasm.asyncify_start_rewind(asyncifyData);
+    step();
+  });
+}
+
+export async function run() {
+  wasm = await init();
+  asyncifyData = wasm.runtime_web_asyncify_data(ASYNCIFY_DATA_SIZE);
+  step();
+}